@@ -0,0 +1,140 @@
+//! Validating lines and locating errors.
+
+use std::fmt;
+
+use ircv3_parse::{
+    error::{CommandError, ParamError, SourceError, TagError},
+    validators, IRCError, Message,
+};
+
+/// An invalid line, with the 1-based column of the error if it is known.
+#[derive(Debug)]
+pub struct Issue {
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn new(message: impl fmt::Display) -> Self {
+        Self {
+            column: None,
+            message: message.to_string(),
+        }
+    }
+
+    fn at(line: &str, offset: usize, message: impl fmt::Display) -> Self {
+        let column = line.get(..offset).map_or(offset, |s| s.chars().count()) + 1;
+        Self {
+            column: Some(column),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Parses `line` and runs every component through [`validators`].
+pub fn check(line: &str) -> Result<Message<'_>, Issue> {
+    let msg = ircv3_parse::parse(line).map_err(|e| parse_issue(line, e))?;
+
+    if let Some(tags) = msg.tags() {
+        for tag in tags.split() {
+            check_tag(line, tag)?;
+        }
+    }
+
+    if let Some(source) = msg.source() {
+        if let Err(e) = validators::source(source.as_str()) {
+            let offset = match &e {
+                SourceError::InvalidNickChar { position, .. } => {
+                    offset(line, source.name) + position
+                }
+                SourceError::InvalidUserChar { position, .. } => {
+                    offset(line, source.user.unwrap_or(source.name)) + position
+                }
+                SourceError::EmptyUser => offset(line, source.user.unwrap_or(source.name)),
+                SourceError::Hostname(_) => offset(line, source.host.unwrap_or(source.name)),
+                _ => offset(line, source.name),
+            };
+            return Err(Issue::at(line, offset, e));
+        }
+    }
+
+    let params = msg.params();
+    for middle in params.middles.iter() {
+        validators::param(middle).map_err(|e| param_issue(line, middle, e))?;
+    }
+    if let Some(trailing) = params.trailing.raw() {
+        validators::trailing(trailing).map_err(|e| param_issue(line, trailing, e))?;
+    }
+
+    Ok(msg)
+}
+
+fn check_tag(line: &str, tag: &str) -> Result<(), Issue> {
+    if tag.is_empty() {
+        return Err(Issue::at(line, offset(line, tag), TagError::EmptyKey));
+    }
+
+    let (key, value) = match tag.split_once('=') {
+        Some((key, value)) => (key, Some(value)),
+        None => (tag, None),
+    };
+
+    validators::tag_key(key).map_err(|e| {
+        // Character positions count from the key part after the vendor or
+        // the client-only `+`.
+        let part = key
+            .rfind('/')
+            .map_or(key.starts_with('+') as usize, |i| i + 1);
+        let offset = match &e {
+            TagError::InvalidKeyChar { position, .. } => offset(line, key) + part + position,
+            _ => offset(line, key),
+        };
+        Issue::at(line, offset, e)
+    })?;
+
+    if let Some(value) = value {
+        validators::tag_value(value).map_err(|e| {
+            let offset = match &e {
+                TagError::InvalidValueChar { position, .. } => offset(line, value) + position,
+                _ => offset(line, value),
+            };
+            Issue::at(line, offset, e)
+        })?;
+    }
+
+    Ok(())
+}
+
+fn param_issue(line: &str, param: &str, e: ParamError) -> Issue {
+    let offset = match &e {
+        ParamError::InvalidMiddleChar { position, .. } => offset(line, param) + position,
+        _ => offset(line, param),
+    };
+    Issue::at(line, offset, e)
+}
+
+fn parse_issue(line: &str, e: IRCError) -> Issue {
+    match &e {
+        IRCError::Command(CommandError::InvalidCommand { position, .. }) => {
+            Issue::at(line, command_offset(line) + position, e)
+        }
+        IRCError::Command(_) => Issue::at(line, command_offset(line), e),
+        _ => Issue::new(e),
+    }
+}
+
+/// Skips the tags and the source, which start with `@` and `:`.
+fn command_offset(line: &str) -> usize {
+    let mut rest = line;
+    for prefix in ['@', ':'] {
+        if rest.starts_with(prefix) {
+            rest = rest.find(' ').map_or("", |i| &rest[i + 1..]);
+        }
+    }
+    line.len() - rest.len()
+}
+
+/// Byte offset of `part`, a slice of `line`.
+fn offset(line: &str, part: &str) -> usize {
+    (part.as_ptr() as usize).saturating_sub(line.as_ptr() as usize)
+}
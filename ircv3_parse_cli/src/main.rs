@@ -0,0 +1,227 @@
+//! `ircv3-parse`: inspect, validate and convert IRC lines.
+//!
+//! Reads one message per line from the given files, or stdin, and prints each
+//! valid one in the chosen format. Invalid lines are reported on stderr as
+//! `file:line:column: message` and skipped.
+//!
+//! Exit codes: `0` if every line was valid, `1` if any was not, `2` for
+//! usage errors and unreadable input.
+
+mod check;
+mod output;
+
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    process::ExitCode,
+};
+
+use check::{check, Issue};
+use output::{from_json, Format};
+
+const USAGE: &str = "\
+Usage: ircv3-parse [OPTIONS] [FILE]...
+
+Reads IRC messages, one per line, from each FILE or from stdin if none is
+given or FILE is `-`, validates them and prints them.
+
+Options:
+  -f, --format <FORMAT>  Output format: pretty, json or raw
+                         [default: pretty, or raw with --from-json]
+  -c, --check            Only validate, print nothing for valid lines
+      --from-json        Read the JSON printed by `--format json` instead
+  -h, --help             Print this help
+  -V, --version          Print the version
+
+Exit status is 0 if every line is valid, 1 if any line is invalid and 2 on
+usage or I/O errors.";
+
+struct Options {
+    format: Option<Format>,
+    check: bool,
+    from_json: bool,
+    files: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => return ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("ircv3-parse: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut invalid = false;
+
+    let files = if options.files.is_empty() {
+        vec!["-".to_owned()]
+    } else {
+        options.files.clone()
+    };
+
+    for file in &files {
+        let (name, reader): (&str, Box<dyn BufRead>) = if file == "-" {
+            ("<stdin>", Box::new(io::stdin().lock()))
+        } else {
+            match File::open(file) {
+                Ok(f) => (file, Box::new(BufReader::new(f))),
+                Err(e) => {
+                    eprintln!("ircv3-parse: {file}: {e}");
+                    return ExitCode::from(2);
+                }
+            }
+        };
+
+        match run(&options, name, reader, &mut out, &mut invalid) {
+            Ok(true) => {}
+            // The reader went away, e.g. `| head`; stop quietly.
+            Ok(false) => break,
+            Err(e) => {
+                eprintln!("ircv3-parse: {name}: {e}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    if let Err(e) = out.flush() {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("ircv3-parse: {e}");
+            return ExitCode::from(2);
+        }
+    }
+
+    if invalid {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Returns `None` if `--help` or `--version` was handled.
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        format: None,
+        check: false,
+        from_json: false,
+        files: Vec::new(),
+    };
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag.to_owned(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(None);
+            }
+            "-V" | "--version" => {
+                println!("ircv3-parse {}", env!("CARGO_PKG_VERSION"));
+                return Ok(None);
+            }
+            "-f" | "--format" => {
+                let value = match inline {
+                    Some(value) => value.to_owned(),
+                    None => args.next().ok_or_else(|| format!("{flag} needs a value"))?,
+                };
+                options.format =
+                    Some(Format::parse(&value).ok_or_else(|| format!("unknown format '{value}'"))?);
+            }
+            "-c" | "--check" => options.check = true,
+            "--from-json" => options.from_json = true,
+            "--" => {
+                options.files.extend(args.by_ref());
+            }
+            "-" => options.files.push(arg),
+            _ if flag.starts_with('-') => return Err(format!("unknown option '{flag}'")),
+            _ => options.files.push(arg),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+fn run(
+    options: &Options,
+    name: &str,
+    mut reader: impl BufRead,
+    out: &mut impl Write,
+    invalid: &mut bool,
+) -> io::Result<bool> {
+    let default = if options.from_json {
+        Format::Raw
+    } else {
+        Format::Pretty
+    };
+    let format = options.format.unwrap_or(default);
+
+    let mut buf = Vec::new();
+    let mut number = 0;
+
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(true);
+        }
+        number += 1;
+
+        let result = match std::str::from_utf8(&buf) {
+            Ok(line) => {
+                let line = line.trim_end_matches(['\r', '\n']);
+                if line.trim().is_empty() {
+                    continue;
+                }
+                process(line, options.from_json, format)
+            }
+            Err(e) => {
+                // Count characters like `Issue::at`, not bytes.
+                let valid = std::str::from_utf8(&buf[..e.valid_up_to()]);
+                Err(Issue {
+                    column: Some(valid.map_or(0, |s| s.chars().count()) + 1),
+                    message: "invalid UTF-8".to_owned(),
+                })
+            }
+        };
+
+        let text = match result {
+            Ok(text) => text,
+            Err(issue) => {
+                match issue.column {
+                    Some(column) => eprintln!("{name}:{number}:{column}: {}", issue.message),
+                    None => eprintln!("{name}:{number}: {}", issue.message),
+                }
+                *invalid = true;
+                continue;
+            }
+        };
+        if options.check {
+            continue;
+        }
+
+        // Pretty output ends with a newline already, so this leaves a blank
+        // line after each message.
+        match writeln!(out, "{text}") {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(false),
+            result => result?,
+        }
+    }
+}
+
+fn process(line: &str, json: bool, format: Format) -> Result<String, Issue> {
+    if !json {
+        return format.render(&check(line)?);
+    }
+
+    let built = from_json(line)?;
+    // Columns would point into the built line, not the JSON.
+    let msg = check(&built).map_err(|issue| Issue::new(issue.message))?;
+    format.render(&msg)
+}
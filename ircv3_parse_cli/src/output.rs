@@ -0,0 +1,203 @@
+//! Printing messages and building them from JSON.
+
+use std::fmt::Write;
+
+use ircv3_parse::{
+    components::{Numeric, TagValue},
+    unescape, validators, Commands, Message, MessageBuilder,
+};
+use serde_json::Value;
+
+use crate::check::Issue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One field per line, for reading.
+    Pretty,
+    /// The `Serialize` output of [`Message`], one object per line.
+    Json,
+    /// The message serialized again, with single spaces and a `:` trailing.
+    Raw,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "pretty" => Some(Self::Pretty),
+            "json" => Some(Self::Json),
+            "raw" => Some(Self::Raw),
+            _ => None,
+        }
+    }
+
+    pub fn render(self, msg: &Message<'_>) -> Result<String, Issue> {
+        match self {
+            Self::Pretty => Ok(pretty(msg)),
+            Self::Json => serde_json::to_string(msg).map_err(Issue::new),
+            Self::Raw => raw(msg),
+        }
+    }
+}
+
+fn pretty(msg: &Message<'_>) -> String {
+    let mut out = String::new();
+
+    if let Some(tags) = msg.tags() {
+        out.push_str("tags:\n");
+        for (key, value) in tags.iter() {
+            match value {
+                TagValue::Flag => writeln!(out, "  {key}"),
+                _ => writeln!(out, "  {key} = {:?}", unescape(value.as_str())),
+            }
+            .unwrap();
+        }
+    }
+
+    if let Some(source) = msg.source() {
+        writeln!(out, "source: {source}").unwrap();
+        writeln!(out, "  name: {}", source.name).unwrap();
+        if let Some(user) = source.user {
+            writeln!(out, "  user: {user}").unwrap();
+        }
+        if let Some(host) = source.host {
+            writeln!(out, "  host: {host}").unwrap();
+        }
+    }
+
+    let command = msg.command();
+    match command.numeric() {
+        Some(numeric) => writeln!(out, "command: {command} ({numeric})"),
+        None => writeln!(out, "command: {command}"),
+    }
+    .unwrap();
+
+    let params = msg.params();
+    if params.count() > 0 {
+        out.push_str("params:\n");
+        for (i, middle) in params.middles.iter().enumerate() {
+            writeln!(out, "  {i}: {middle:?}").unwrap();
+        }
+        if let Some(trailing) = params.trailing.raw() {
+            writeln!(out, "  {}: {trailing:?} (trailing)", params.middles.count()).unwrap();
+        }
+    }
+
+    out
+}
+
+fn raw(msg: &Message<'_>) -> Result<String, Issue> {
+    let mut builder = MessageBuilder::new();
+
+    if let Some(tags) = msg.tags() {
+        for (key, value) in tags.iter() {
+            match value {
+                TagValue::Flag => builder.add_tag_flag(key),
+                _ => builder.add_tag(key, Some(value.as_str())),
+            }
+            .map_err(Issue::new)?;
+        }
+    }
+
+    if let Some(source) = msg.source() {
+        builder
+            .set_source(source.name, source.user, source.host)
+            .map_err(Issue::new)?;
+    }
+
+    builder.set_command(msg.command()).map_err(Issue::new)?;
+
+    let params = msg.params();
+    builder
+        .add_params(params.middles.iter())
+        .map_err(Issue::new)?;
+    if let Some(trailing) = params.trailing.raw() {
+        builder.set_trailing(trailing).map_err(Issue::new)?;
+    }
+
+    build(builder)
+}
+
+/// Builds a line from the JSON shape that `--format json` prints.
+///
+/// Tag values are taken as they appear on the wire, i.e. still escaped, and
+/// `null` makes a tag a flag.
+pub fn from_json(input: &str) -> Result<String, Issue> {
+    let value: Value = serde_json::from_str(input).map_err(|e| Issue {
+        column: Some(e.column()),
+        message: e.to_string(),
+    })?;
+    let Value::Object(object) = value else {
+        return Err(Issue::new("expected a JSON object"));
+    };
+
+    let mut builder = MessageBuilder::new();
+
+    if let Some(tags) = object.get("tags") {
+        let tags = tags
+            .as_object()
+            .ok_or_else(|| Issue::new("\"tags\" must be an object"))?;
+        for (key, value) in tags {
+            match value {
+                Value::Null => builder.add_tag_flag(key),
+                Value::String(value) => builder.add_tag(key, Some(value)),
+                _ => {
+                    return Err(Issue::new(format!(
+                        "tag \"{key}\" must be a string or null"
+                    )))
+                }
+            }
+            .map_err(Issue::new)?;
+        }
+    }
+
+    if let Some(source) = object.get("source") {
+        let name = string(source, "name", "source")?
+            .ok_or_else(|| Issue::new("\"source\" needs a \"name\""))?;
+        let user = string(source, "user", "source")?;
+        let host = string(source, "host", "source")?;
+        builder.set_source(name, user, host).map_err(Issue::new)?;
+    }
+
+    let command = object
+        .get("command")
+        .and_then(Value::as_str)
+        .ok_or_else(|| Issue::new("\"command\" must be a string"))?;
+    // Numeric names like `RPL_WELCOME` become their code.
+    let command =
+        Numeric::from_name(command).map_or_else(|| Commands::from(command), Commands::from);
+    validators::command(command.as_str()).map_err(Issue::new)?;
+    builder.set_command(command).map_err(Issue::new)?;
+
+    if let Some(params) = object.get("params") {
+        if let Some(middles) = params.get("middles") {
+            let middles = middles
+                .as_array()
+                .ok_or_else(|| Issue::new("\"middles\" must be an array"))?;
+            for middle in middles {
+                let middle = middle
+                    .as_str()
+                    .ok_or_else(|| Issue::new("\"middles\" must only hold strings"))?;
+                builder.add_param(middle).map_err(Issue::new)?;
+            }
+        }
+        if let Some(trailing) = string(params, "trailing", "params")? {
+            builder.set_trailing(trailing).map_err(Issue::new)?;
+        }
+    }
+
+    build(builder)
+}
+
+fn string<'a>(object: &'a Value, key: &str, parent: &str) -> Result<Option<&'a str>, Issue> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(Issue::new(format!("\"{parent}.{key}\" must be a string"))),
+    }
+}
+
+fn build(builder: MessageBuilder) -> Result<String, Issue> {
+    let bytes = builder.build().map_err(Issue::new)?;
+    let line = String::from_utf8_lossy(&bytes);
+    Ok(line.trim_end_matches("\r\n").to_owned())
+}
@@ -0,0 +1,136 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn run(args: &[&str], stdin: impl AsRef<[u8]>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ircv3-parse"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_ref())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn pretty() {
+    let output = run(
+        &[],
+        "@msgid=a;+draft/reply=b\\sc;bot :nick!user@host PRIVMSG #rust :hi\r\n\n:irc.example.com 001 nick :Welcome\n",
+    );
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(
+        "tags:\n  msgid = \"a\"\n  +draft/reply = \"b c\"\n  bot\n\
+         source: nick!user@host\n  name: nick\n  user: user\n  host: host\n\
+         command: PRIVMSG\nparams:\n  0: \"#rust\"\n  1: \"hi\" (trailing)\n\n\
+         source: irc.example.com\n  name: irc.example.com\n\
+         command: 001 (RPL_WELCOME)\nparams:\n  0: \"nick\"\n  1: \"Welcome\" (trailing)\n\n",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn raw_is_canonical() {
+    let output = run(
+        &["--format=raw"],
+        "@a;b=c :n!u@h KICK #rust   bob :bye now\nPING  x\n",
+    );
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(
+        "@a;b=c :n!u@h KICK #rust bob :bye now\nPING x\n",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn reports_errors_with_positions() {
+    let input = "PING ok\n@k_y=1 PING x\n:ni#ck!u@h PING x\n:irc.example.com 01 x\n";
+    let output = run(&["--check"], input);
+
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("", stdout(&output));
+    let errors: Vec<_> = stderr(&output).lines().collect();
+    assert_eq!(3, errors.len());
+    assert!(errors[0].starts_with("<stdin>:2:3: tag key contains invalid character '_'"));
+    assert!(errors[1].starts_with("<stdin>:3:4: nickname contains invalid character '#'"));
+    assert!(errors[2].starts_with("<stdin>:4:"));
+}
+
+#[test]
+fn invalid_lines_are_skipped() {
+    let output = run(&["-f", "raw"], "PING a\n@=x PING b\nPING c\n");
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("PING a\nPING c\n", stdout(&output));
+    assert!(stderr(&output).starts_with("<stdin>:2:2: "));
+}
+
+#[test]
+fn invalid_utf8_column_counts_chars() {
+    let output = run(&["--check"], b"PRIVMSG #caf\xc3\xa9 :\xff\n");
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("<stdin>:1:16: invalid UTF-8\n", stderr(&output));
+}
+
+#[test]
+fn json_round_trip() {
+    let line = "@b=1;a;c=x\\sy :nick!user@host PRIVMSG #rust :hello world";
+    let json = run(&["-f", "json"], line);
+    assert_eq!(Some(0), json.status.code());
+    assert_eq!(
+        r##"{"tags":{"b":"1","a":null,"c":"x\\sy"},"source":{"name":"nick","user":"user","host":"host"},"command":"PRIVMSG","params":{"middles":["#rust"],"trailing":"hello world"}}"##,
+        stdout(&json).trim_end()
+    );
+
+    let back = run(&["--from-json"], stdout(&json));
+    assert_eq!(Some(0), back.status.code());
+    assert_eq!(format!("{line}\n"), stdout(&back));
+}
+
+#[test]
+fn from_json_errors() {
+    let input = concat!(
+        r#"{"command":"RPL_WELCOME","params":{"middles":["nick"]}}"#,
+        "\n",
+        r#"{"command":"BAD CMD"}"#,
+        "\n",
+        r#"{"command": 5"#,
+        "\n",
+    );
+    let output = run(&["--from-json"], input);
+
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("001 nick\n", stdout(&output));
+    let errors: Vec<_> = stderr(&output).lines().collect();
+    assert!(errors[0].starts_with("<stdin>:2: command must be all letters"));
+    assert!(errors[1].starts_with("<stdin>:3:13: "));
+}
+
+#[test]
+fn usage_errors() {
+    let output = run(&["--format", "yaml"], "");
+    assert_eq!(Some(2), output.status.code());
+    assert!(stderr(&output).starts_with("ircv3-parse: unknown format 'yaml'"));
+
+    let output = run(&["does/not/exist"], "");
+    assert_eq!(Some(2), output.status.code());
+
+    let output = run(&["--version"], "");
+    assert_eq!(Some(0), output.status.code());
+    assert!(stdout(&output).starts_with("ircv3-parse "));
+}
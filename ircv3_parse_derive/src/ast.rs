@@ -223,6 +223,23 @@ impl<'a> Generics<'a> {
         (impl_generics, struct_ty_generics, where_clause)
     }
 
+    /// Returns `Some(None)` without generics, and the lifetime if it is the
+    /// only generic parameter. `None` for anything else.
+    pub fn sole_lifetime(&self) -> Option<Option<&Lifetime>> {
+        if self.original.where_clause.is_some() {
+            return None;
+        }
+
+        let mut params = self.original.params.iter();
+        match (params.next(), params.next()) {
+            (None, _) => Some(None),
+            (Some(GenericParam::Lifetime(param)), None) if param.bounds.is_empty() => {
+                Some(Some(&param.lifetime))
+            }
+            _ => None,
+        }
+    }
+
     pub fn msg_lifetime(&self) -> Lifetime {
         self.generics
             .lifetimes()
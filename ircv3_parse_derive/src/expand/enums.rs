@@ -26,6 +26,7 @@ impl<'a> Enum<'a> {
         let msg_lifetime = self.generics.msg_lifetime();
 
         let setup = components.expand();
+        let dispatchable = self.generics.expand_dispatchable(name);
 
         quote! {
             impl #impl_generics ircv3_parse::de::FromMessage<#msg_lifetime>
@@ -39,6 +40,8 @@ impl<'a> Enum<'a> {
                     #body
                 }
             }
+
+            #dispatchable
         }
     }
 
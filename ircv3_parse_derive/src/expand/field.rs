@@ -14,14 +14,18 @@ impl<'a> FieldStruct<'a> {
         let (impl_generics, ty_generics, where_clause) = self.generics.split();
         let msg_lifetime = self.generics.msg_lifetime();
 
+        let command = self.attrs.expand_command_const();
         let validation = self.attrs.expand_command_check();
         let setup_code = self.components().expand();
         let impl_body = self.expand_de_body();
+        let dispatchable = self.generics.expand_dispatchable(name);
 
         quote! {
             impl #impl_generics ircv3_parse::de::FromMessage<#msg_lifetime>
                 for #name #ty_generics #where_clause
             {
+                #command
+
                 fn from_message(
                     msg: &ircv3_parse::Message<#msg_lifetime>
                 ) -> Result<Self, ircv3_parse::DeError> {
@@ -31,6 +35,8 @@ impl<'a> FieldStruct<'a> {
                     #impl_body
                 }
             }
+
+            #dispatchable
         }
     }
 
@@ -81,16 +87,13 @@ impl<'a> FieldStruct<'a> {
 }
 
 impl StructAttrs {
+    pub fn expand_command_const(&self) -> TokenStream {
+        expand_command_const(self.command.as_ref())
+    }
+
     pub fn expand_command_check(&self) -> TokenStream {
         if let Some(cmd) = &self.command {
-            quote! {
-                if msg.command() != #cmd {
-                    return Err(ircv3_parse::DeError::command_mismatch(
-                        #cmd,
-                        msg.command().as_str()
-                    ));
-                }
-            }
+            expand_command_check(cmd)
         } else {
             quote! {}
         }
@@ -98,8 +101,9 @@ impl StructAttrs {
 
     pub fn expand_ser(&self) -> TokenStream {
         if let Some(cmd) = &self.command {
+            let command = expand_command(cmd);
             quote! {
-                serialize.set_command(ircv3_parse::Commands::from(#cmd));
+                serialize.set_command(#command);
             }
         } else {
             quote! {}
@@ -196,3 +200,36 @@ fn expand_nested(field: &syn::Field) -> TokenStream {
         value
     }
 }
+
+pub fn expand_command_const(command: Option<&LitStr>) -> TokenStream {
+    match command {
+        Some(cmd) => quote! {
+            const COMMAND: Option<&'static str> = Some(#cmd);
+        },
+        None => quote! {},
+    }
+}
+
+/// Resolves a container `command`, which may also be a numeric name such as
+/// `ERR_NICKNAMEINUSE`, to `Commands`.
+pub fn expand_command(cmd: &LitStr) -> TokenStream {
+    quote! {
+        ircv3_parse::components::Numeric::from_name(#cmd)
+            .map_or_else(|| ircv3_parse::Commands::from(#cmd), ircv3_parse::Commands::from)
+    }
+}
+
+pub fn expand_command_check(cmd: &LitStr) -> TokenStream {
+    let command = expand_command(cmd);
+    quote! {
+        {
+            let expected = #command;
+            if msg.command() != expected {
+                return Err(ircv3_parse::DeError::command_mismatch(
+                    expected.as_str(),
+                    msg.command().as_str()
+                ));
+            }
+        }
+    }
+}
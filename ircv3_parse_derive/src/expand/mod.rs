@@ -4,9 +4,10 @@ mod field_kind;
 mod unit;
 
 use proc_macro2::TokenStream;
-use syn::{DeriveInput, Result};
+use quote::quote;
+use syn::{DeriveInput, Ident, Result};
 
-use crate::ast::{Input, Struct};
+use crate::ast::{Generics, Input, Struct};
 
 pub fn derive_from_message(node: &DeriveInput) -> Result<TokenStream> {
     let input = Input::from_syn(node, "FromMessage")?;
@@ -45,3 +46,23 @@ impl<'a> Struct<'a> {
         }
     }
 }
+
+impl<'a> Generics<'a> {
+    /// Implements `Dispatchable`, which needs the type for any message
+    /// lifetime. Skipped for types with other generics.
+    pub fn expand_dispatchable(&self, name: &Ident) -> TokenStream {
+        match self.sole_lifetime() {
+            Some(None) => quote! {
+                impl ircv3_parse::dispatch::Dispatchable for #name {
+                    type Target<'__msg> = #name;
+                }
+            },
+            Some(Some(lifetime)) => quote! {
+                impl<#lifetime> ircv3_parse::dispatch::Dispatchable for #name<#lifetime> {
+                    type Target<'__msg> = #name<'__msg>;
+                }
+            },
+            None => quote! {},
+        }
+    }
+}
@@ -4,6 +4,8 @@ use syn::LitStr;
 
 use crate::{ast::UnitStruct, attr::UnitStructAttrs, component_set::ComponentSet};
 
+use super::field::{expand_command, expand_command_check, expand_command_const};
+
 impl<'a> UnitStruct<'a> {
     pub fn expand_de(&self) -> TokenStream {
         let name = self.ident;
@@ -11,18 +13,24 @@ impl<'a> UnitStruct<'a> {
         let msg_lifetime = self.generics.msg_lifetime();
 
         let expected_value = self.expected_value();
+        let command = expand_command_const(self.attrs.command.as_ref());
         let impl_body = self.attrs.expand_de(&expected_value);
+        let dispatchable = self.generics.expand_dispatchable(name);
 
         quote! {
             impl #impl_generics ircv3_parse::de::FromMessage<#msg_lifetime>
                 for #name #ty_generics #where_clause
             {
+                #command
+
                 fn from_message(
                     msg: &ircv3_parse::Message<#msg_lifetime>
                 ) -> Result<Self, ircv3_parse::DeError> {
                     #impl_body
                 }
             }
+
+            #dispatchable
         }
     }
 
@@ -73,14 +81,7 @@ impl UnitStructAttrs {
         let setup_code = components.expand();
 
         let command_check = if let Some(cmd) = &self.command {
-            quote! {
-                if msg.command() != #cmd {
-                    return Err(ircv3_parse::DeError::command_mismatch(
-                        #cmd,
-                        msg.command().as_str()
-                    ));
-                }
-            }
+            expand_command_check(cmd)
         } else {
             quote! {}
         };
@@ -101,8 +102,9 @@ impl UnitStructAttrs {
 
     pub fn expand_ser(&self, expected_value: &LitStr) -> TokenStream {
         let command = if let Some(cmd) = &self.command {
+            let command = expand_command(cmd);
             quote! {
-                serialize.set_command(ircv3_parse::Commands::from(#cmd));
+                serialize.set_command(#command);
             }
         } else {
             quote! {}
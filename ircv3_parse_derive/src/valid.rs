@@ -223,13 +223,14 @@ impl<'a> Enum<'a> {
                         }
                     }
                 },
-                fields if !fields.has_any_kind() => {
-                    if !matches!(fields, VariantFields::Unnamed(fs) if fs.len() == 1) {
-                        errors.push(Error::new_spanned(
-                            variant.ident,
-                            error_msg::no_field_irc_attrs_requires_single_unnamed(),
-                        ));
-                    }
+                fields
+                    if !fields.has_any_kind()
+                        && !matches!(fields, VariantFields::Unnamed(fs) if fs.len() == 1) =>
+                {
+                    errors.push(Error::new_spanned(
+                        variant.ident,
+                        error_msg::no_field_irc_attrs_requires_single_unnamed(),
+                    ));
                 }
                 _ => {}
             }
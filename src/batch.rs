@@ -0,0 +1,380 @@
+//! IRCv3 `BATCH` tracking.
+//!
+//! Servers group related messages, e.g. a netsplit or a `CHATHISTORY` reply,
+//! between `BATCH +reference type [params]` and `BATCH -reference`. Messages
+//! inside the batch carry a `batch=reference` tag, and batches may be nested.
+//!
+//! [`BatchTracker`] holds batched messages until the outermost batch closes.
+//! It does not do any I/O.
+//!
+//! ```rust
+//! use ircv3_parse::batch::BatchTracker;
+//!
+//! let mut tracker = BatchTracker::new();
+//! let lines = [
+//!     ":irc.example.com BATCH +yXNAbvnRHTRBv netsplit irc.hub other.host",
+//!     "@batch=yXNAbvnRHTRBv :aji!a@a QUIT :irc.hub other.host",
+//!     "@batch=yXNAbvnRHTRBv :nenolod!a@a QUIT :irc.hub other.host",
+//!     ":irc.example.com BATCH -yXNAbvnRHTRBv",
+//! ];
+//! for line in lines {
+//!     assert!(tracker.handle(&ircv3_parse::parse(line)?)?);
+//! }
+//!
+//! // Unbatched messages are left to the caller.
+//! assert!(!tracker.handle(&ircv3_parse::parse(":nick!u@h PRIVMSG #rust :hi")?)?);
+//!
+//! let batch = tracker.poll_batch().unwrap();
+//! assert_eq!("netsplit", batch.kind);
+//! assert_eq!(["irc.hub", "other.host"], batch.params.as_slice());
+//! assert_eq!(2, batch.messages().count());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::compat::{String, ToOwned, ToString, Vec, VecDeque};
+
+use crate::{components::Tags, error::BatchError, DeError, Message};
+
+const BATCH_TAG: &str = "batch";
+
+/// A closed batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch {
+    pub reference: String,
+    /// The batch type, e.g. `netsplit` or `chathistory`.
+    pub kind: String,
+    pub params: Vec<String>,
+    /// Source of the opening `BATCH`.
+    pub source: Option<String>,
+    /// Nested batches, in the order they closed.
+    pub children: Vec<Batch>,
+    messages: Vec<String>,
+    tags: String,
+}
+
+impl Batch {
+    /// Returns the messages of this batch, excluding those of nested batches.
+    pub fn messages(&self) -> impl Iterator<Item = Message<'_>> {
+        self.messages
+            .iter()
+            .map(|line| crate::parse(line).expect("batched lines are parsed when added"))
+    }
+
+    /// Returns the tags of the opening `BATCH`.
+    pub fn tags(&self) -> Tags<'_> {
+        Tags::new(&self.tags)
+    }
+
+    /// Compares the batch type case-sensitively, as type names are.
+    #[inline]
+    pub fn is(&self, kind: &str) -> bool {
+        self.kind == kind
+    }
+}
+
+#[derive(Debug, Clone)]
+struct OpenBatch {
+    batch: Batch,
+    parent: Option<String>,
+}
+
+/// Collects batched messages until their batch closes.
+///
+/// Feed every incoming message to [`BatchTracker::handle()`]. Closed
+/// top-level batches are taken with [`BatchTracker::poll_batch()`]; nested
+/// batches end up in the [`Batch::children`] of their parent.
+#[derive(Debug, Clone, Default)]
+pub struct BatchTracker {
+    open: Vec<OpenBatch>,
+    complete: VecDeque<Batch>,
+}
+
+impl BatchTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes an incoming message.
+    ///
+    /// Returns `Ok(false)` for a message outside of any batch, which the caller
+    /// handles as usual.
+    ///
+    /// # Errors
+    ///
+    /// - [`BatchError::UnknownBatch`] if a message or `BATCH -` refers to a
+    ///   batch that is not open; the message is not kept
+    /// - [`BatchError::Parse`] if a batched message does not parse from its
+    ///   text, e.g. one built with [`Message::new()`]; the message is not kept
+    /// - [`BatchError::DuplicateBatch`] if a batch is opened twice
+    /// - [`BatchError::Abandoned`] if a batch closes while nested batches are
+    ///   still open; the batch itself is closed and the nested ones are dropped
+    pub fn handle(&mut self, msg: &Message<'_>) -> Result<bool, BatchError> {
+        let parent = msg
+            .tags()
+            .and_then(|tags| tags.get(BATCH_TAG))
+            .map(|value| value.as_str());
+
+        if msg.command().is_batch() {
+            let params = msg.params();
+            let reference = params.get(0).ok_or_else(|| DeError::not_found_param(0))?;
+
+            if let Some(reference) = reference.strip_prefix('+') {
+                self.open(msg, reference, parent)?;
+                return Ok(true);
+            }
+            if let Some(reference) = reference.strip_prefix('-') {
+                self.close(reference)?;
+                return Ok(true);
+            }
+
+            return Err(DeError::not_found_with_context(
+                "param",
+                reference,
+                "expected a batch reference starting with `+` or `-`",
+            )
+            .into());
+        }
+
+        let Some(parent) = parent else {
+            return Ok(false);
+        };
+
+        let batch = &mut self.find(parent)?.batch;
+        let line = msg.to_string();
+        crate::parse(&line)?;
+        batch.messages.push(line);
+        Ok(true)
+    }
+
+    /// Returns the next closed top-level batch.
+    pub fn poll_batch(&mut self) -> Option<Batch> {
+        self.complete.pop_front()
+    }
+
+    /// Returns `true` if a batch is still open.
+    pub fn is_pending(&self) -> bool {
+        !self.open.is_empty()
+    }
+
+    /// Drops every open batch and returns them, e.g. after a disconnect.
+    ///
+    /// Nested batches are returned on their own, not in their parent.
+    pub fn abandon(&mut self) -> Vec<Batch> {
+        self.open.drain(..).map(|open| open.batch).collect()
+    }
+
+    fn open(
+        &mut self,
+        msg: &Message<'_>,
+        reference: &str,
+        parent: Option<&str>,
+    ) -> Result<(), BatchError> {
+        if self.open.iter().any(|o| o.batch.reference == reference) {
+            return Err(BatchError::DuplicateBatch {
+                reference: reference.to_owned(),
+            });
+        }
+        if let Some(parent) = parent {
+            self.find(parent)?;
+        }
+
+        let params = msg.params();
+        let kind = params.get(1).ok_or_else(|| DeError::not_found_param(1))?;
+
+        self.open.push(OpenBatch {
+            batch: Batch {
+                reference: reference.to_owned(),
+                kind: kind.to_owned(),
+                params: params.iter().skip(2).map(ToOwned::to_owned).collect(),
+                source: msg.source().map(|s| s.as_str().to_owned()),
+                children: Vec::new(),
+                messages: Vec::new(),
+                tags: msg
+                    .tags()
+                    .map_or_else(String::new, |t| t.as_str().to_owned()),
+            },
+            parent: parent.map(ToOwned::to_owned),
+        });
+        Ok(())
+    }
+
+    fn close(&mut self, reference: &str) -> Result<(), BatchError> {
+        let index = self
+            .open
+            .iter()
+            .position(|o| o.batch.reference == reference)
+            .ok_or_else(|| BatchError::UnknownBatch {
+                reference: reference.to_owned(),
+            })?;
+        let closed = self.open.remove(index);
+
+        let mut abandoned = Vec::new();
+        let mut orphans = Vec::from([closed.batch.reference.clone()]);
+        while let Some(orphan) = orphans.pop() {
+            self.open.retain(|o| {
+                if o.parent.as_deref() == Some(orphan.as_str()) {
+                    orphans.push(o.batch.reference.clone());
+                    abandoned.push(o.batch.reference.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+
+        match closed.parent {
+            Some(parent) => self.find(&parent)?.batch.children.push(closed.batch),
+            None => self.complete.push_back(closed.batch),
+        }
+
+        if abandoned.is_empty() {
+            Ok(())
+        } else {
+            Err(BatchError::Abandoned {
+                references: abandoned,
+            })
+        }
+    }
+
+    fn find(&mut self, reference: &str) -> Result<&mut OpenBatch, BatchError> {
+        self.open
+            .iter_mut()
+            .find(|o| o.batch.reference == reference)
+            .ok_or_else(|| BatchError::UnknownBatch {
+                reference: reference.to_owned(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(tracker: &mut BatchTracker, line: &str) -> Result<bool, BatchError> {
+        tracker.handle(&crate::parse(line).unwrap())
+    }
+
+    #[test]
+    fn nested() {
+        let mut tracker = BatchTracker::new();
+        let lines = [
+            "@msgid=abc :irc.example.com BATCH +outer chathistory #rust",
+            "@batch=outer :a!a@a PRIVMSG #rust :first",
+            "@batch=outer :irc.example.com BATCH +inner draft/multiline #rust",
+            "@batch=inner :b!b@b PRIVMSG #rust :hello",
+            "@batch=inner :b!b@b PRIVMSG #rust :world",
+            ":irc.example.com BATCH -inner",
+            "@batch=outer :a!a@a PRIVMSG #rust :last",
+        ];
+        for line in lines {
+            assert!(feed(&mut tracker, line).unwrap());
+        }
+
+        assert!(tracker.poll_batch().is_none());
+        assert!(!feed(&mut tracker, ":a!a@a PRIVMSG #rust :outside").unwrap());
+        assert!(feed(&mut tracker, "BATCH -outer").unwrap());
+        assert!(!tracker.is_pending());
+
+        let batch = tracker.poll_batch().unwrap();
+        assert!(batch.is("chathistory"));
+        assert_eq!(["#rust"], batch.params.as_slice());
+        assert_eq!(Some("irc.example.com"), batch.source.as_deref());
+        assert_eq!(Some("abc"), batch.tags().get("msgid").map(|v| v.as_str()));
+
+        let texts: Vec<_> = batch
+            .messages()
+            .map(|m| m.params().trailing.as_str())
+            .collect();
+        assert_eq!(["first", "last"], texts.as_slice());
+
+        assert_eq!(1, batch.children.len());
+        let inner = &batch.children[0];
+        assert_eq!("inner", inner.reference);
+        assert_eq!("draft/multiline", inner.kind);
+        assert_eq!(2, inner.messages().count());
+        assert!(tracker.poll_batch().is_none());
+    }
+
+    #[test]
+    fn unmatched() {
+        let mut tracker = BatchTracker::new();
+
+        let err = feed(&mut tracker, "BATCH -nope").unwrap_err();
+        assert_eq!(
+            BatchError::UnknownBatch {
+                reference: "nope".to_owned()
+            },
+            err
+        );
+        assert!(feed(&mut tracker, "@batch=nope PRIVMSG #rust :hi")
+            .unwrap_err()
+            .is_unknown_batch());
+        assert!(feed(&mut tracker, "@batch=nope BATCH +child netsplit")
+            .unwrap_err()
+            .is_unknown_batch());
+        assert!(!tracker.is_pending());
+
+        feed(&mut tracker, "BATCH +a netsplit").unwrap();
+        assert!(feed(&mut tracker, "BATCH +a netjoin")
+            .unwrap_err()
+            .is_duplicate_batch());
+
+        assert!(matches!(
+            feed(&mut tracker, "BATCH +b").unwrap_err(),
+            BatchError::De(e) if e.is_not_found_param()
+        ));
+        assert!(matches!(
+            feed(&mut tracker, "BATCH a").unwrap_err(),
+            BatchError::De(_)
+        ));
+    }
+
+    #[test]
+    fn unparsable() {
+        let mut tracker = BatchTracker::new();
+        feed(&mut tracker, "BATCH +a netsplit").unwrap();
+
+        // Spans of a valid line over text that does not parse.
+        let scanner = crate::scanner::Scanner::new("@batch=a PRIVMSG #c :hi").unwrap();
+        let msg = Message::new("@batch=a P!IVMSG #c :hi", scanner);
+        assert!(matches!(
+            tracker.handle(&msg).unwrap_err(),
+            BatchError::Parse(_)
+        ));
+
+        feed(&mut tracker, "@batch=a PRIVMSG #c :hi").unwrap();
+        feed(&mut tracker, "BATCH -a").unwrap();
+        assert_eq!(1, tracker.poll_batch().unwrap().messages().count());
+    }
+
+    #[test]
+    fn abandoned() {
+        let mut tracker = BatchTracker::new();
+        feed(&mut tracker, "BATCH +a chathistory #rust").unwrap();
+        feed(&mut tracker, "@batch=a BATCH +b draft/multiline #rust").unwrap();
+        feed(&mut tracker, "@batch=b BATCH +c example").unwrap();
+        feed(&mut tracker, "BATCH +d netsplit x y").unwrap();
+
+        let err = feed(&mut tracker, "BATCH -a").unwrap_err();
+        assert!(err.is_abandoned());
+        assert_eq!(
+            BatchError::Abandoned {
+                references: vec!["b".to_owned(), "c".to_owned()]
+            },
+            err
+        );
+
+        let batch = tracker.poll_batch().unwrap();
+        assert_eq!("a", batch.reference);
+        assert!(batch.children.is_empty());
+
+        assert!(feed(&mut tracker, "@batch=b PRIVMSG #rust :late")
+            .unwrap_err()
+            .is_unknown_batch());
+
+        let open = tracker.abandon();
+        assert_eq!(1, open.len());
+        assert_eq!(["x", "y"], open[0].params.as_slice());
+        assert!(!tracker.is_pending());
+    }
+}
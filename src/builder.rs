@@ -239,6 +239,21 @@ mod tests {
         assert_eq!(":nick@example.com ", buffer.into_bytes());
     }
 
+    #[test]
+    fn source_empty() {
+        let source = IRCSourceSerializer::default();
+        assert!(source.is_empty());
+
+        let mut buffer = IRCSerializer::new();
+        source.to_message(&mut buffer).unwrap();
+        assert_eq!("", buffer.into_bytes());
+
+        let mut source = IRCSourceSerializer::default();
+        source.set_user("user").unwrap();
+        assert!(!source.is_empty());
+        assert!(source.to_message(&mut IRCSerializer::new()).is_err());
+    }
+
     #[test]
     fn source_server() {
         let mut source = IRCSourceSerializer::default();
@@ -286,6 +301,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn without_source() {
+        let mut msg = MessageBuilder::new();
+        msg.set_command(Commands::CAP).unwrap();
+        msg.add_params(["LS", "302"]).unwrap();
+
+        assert_eq!("CAP LS 302\r\n", msg.build().unwrap());
+    }
+
     #[test]
     fn to_message() {
         struct PrivMsg {
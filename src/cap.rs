@@ -0,0 +1,668 @@
+//! IRCv3 capability negotiation.
+//!
+//! - [`CapList`] parses capability lists such as `sasl=PLAIN,EXTERNAL multi-prefix`
+//! - [`CapMessage`] parses a `CAP` message sent by the server
+//! - [`CapNegotiator`] drives the negotiation without doing any I/O
+//!
+//! ```rust
+//! use ircv3_parse::cap::{CapNegotiator, CapState};
+//!
+//! let mut cap = CapNegotiator::new(["sasl", "multi-prefix", "away-notify"]);
+//! cap.start();
+//! assert_eq!("CAP LS 302\r\n", cap.poll_transmit().unwrap());
+//!
+//! let line = ":irc.example.com CAP * LS :sasl=PLAIN,EXTERNAL multi-prefix";
+//! cap.handle(&ircv3_parse::parse(line)?)?;
+//! assert_eq!("CAP REQ :sasl multi-prefix\r\n", cap.poll_transmit().unwrap());
+//!
+//! let line = ":irc.example.com CAP * ACK :sasl multi-prefix";
+//! cap.handle(&ircv3_parse::parse(line)?)?;
+//! assert_eq!("CAP END\r\n", cap.poll_transmit().unwrap());
+//!
+//! assert_eq!(CapState::Done, cap.state());
+//! assert!(cap.is_enabled("sasl"));
+//! assert_eq!(Some("PLAIN,EXTERNAL"), cap.value("sasl"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use bytes::Bytes;
+
+use crate::compat::{Split, String, ToOwned, ToString, Vec, VecDeque};
+
+use crate::{
+    components::{CapSubCommands, Numeric},
+    de::FromMessage,
+    error::CapError,
+    messages::{chunk, MAX_LINE_LEN},
+    Commands, DeError, Message, MessageBuilder, SerError,
+};
+
+/// A single capability of a [`CapList`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cap<'a> {
+    pub name: &'a str,
+    /// The value after `=`, only sent with `CAP LS 302`.
+    pub value: Option<&'a str>,
+    /// `true` when prefixed with `-`, i.e. the capability is being disabled.
+    pub remove: bool,
+}
+
+impl<'a> Cap<'a> {
+    /// Parses a single `[-]name[=value]` entry.
+    pub fn parse(entry: &'a str) -> Self {
+        let (remove, entry) = match entry.strip_prefix('-') {
+            Some(entry) => (true, entry),
+            None => (false, entry),
+        };
+
+        let (name, value) = match entry.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (entry, None),
+        };
+
+        Self {
+            name,
+            value,
+            remove,
+        }
+    }
+
+    /// Returns the comma-separated items of the value, e.g. the SASL mechanisms.
+    pub fn values(&self) -> impl Iterator<Item = &'a str> {
+        self.value
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.is_empty())
+    }
+}
+
+/// A space-separated list of capabilities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CapList<'a>(&'a str);
+
+impl<'a> CapList<'a> {
+    #[inline]
+    pub fn new(input: &'a str) -> Self {
+        Self(input)
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> CapIter<'a> {
+        CapIter(self.0.split(' '))
+    }
+
+    pub fn get(&self, name: &str) -> Option<Cap<'a>> {
+        self.iter().find(|cap| cap.name == name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+}
+
+impl<'a> IntoIterator for CapList<'a> {
+    type Item = Cap<'a>;
+    type IntoIter = CapIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a [`CapList`].
+#[derive(Debug, Clone)]
+pub struct CapIter<'a>(Split<'a, char>);
+
+impl<'a> Iterator for CapIter<'a> {
+    type Item = Cap<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.find(|s| !s.is_empty()).map(Cap::parse)
+    }
+}
+
+/// A `CAP` message sent by the server: `CAP <client> <subcommand> [*] [:<caps>]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapMessage<'a> {
+    pub client: &'a str,
+    pub subcommand: CapSubCommands,
+    /// `true` when more lines of the same `LS` or `LIST` reply follow.
+    pub continued: bool,
+    pub caps: CapList<'a>,
+}
+
+impl<'a> FromMessage<'a> for CapMessage<'a> {
+    const COMMAND: Option<&'static str> = Some("CAP");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        let command = msg.command();
+        if command != Commands::CAP {
+            return Err(DeError::command_mismatch("CAP", command.as_str()));
+        }
+
+        let params = msg.params();
+        let client = params.get(0).ok_or_else(|| DeError::not_found_param(0))?;
+        let subcommand = params.get(1).ok_or_else(|| DeError::not_found_param(1))?;
+
+        let (continued, caps) = match (params.get(2), params.get(3)) {
+            (Some("*"), Some(caps)) => (true, caps),
+            (Some(caps), _) => (false, caps),
+            (None, _) => (false, ""),
+        };
+
+        Ok(Self {
+            client,
+            subcommand: CapSubCommands::from(subcommand),
+            continued,
+            caps: CapList::new(caps),
+        })
+    }
+}
+
+/// Progress of a [`CapNegotiator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CapState {
+    /// [`CapNegotiator::start()`] has not been called yet.
+    Idle,
+    /// Waiting for the complete `CAP LS` reply.
+    Listing,
+    /// Waiting for `ACK`/`NAK` of the requested capabilities.
+    Requesting,
+    /// Every request was answered, `CAP END` is being held back.
+    Ready,
+    /// `CAP END` was sent or the server does not support capabilities.
+    Done,
+}
+
+/// Sans-IO capability negotiation.
+///
+/// Feed every incoming message to [`CapNegotiator::handle()`] and send whatever
+/// [`CapNegotiator::poll_transmit()`] returns. `CAP REQ` lines are split so each
+/// fits in [`MAX_LINE_LEN`] bytes.
+///
+/// With [`CapNegotiator::hold_end()`] the negotiator stops in [`CapState::Ready`]
+/// instead of sending `CAP END`, e.g. to authenticate with SASL first; call
+/// [`CapNegotiator::end()`] afterwards.
+///
+/// After negotiation `CAP NEW` and `CAP DEL` keep the available and enabled
+/// capabilities up to date, and newly offered wanted capabilities are requested.
+#[derive(Debug, Clone)]
+pub struct CapNegotiator {
+    wanted: Vec<String>,
+    version: Option<u16>,
+    hold_end: bool,
+    state: CapState,
+    available: Vec<(String, Option<String>)>,
+    enabled: Vec<String>,
+    pending: Vec<String>,
+    rejected: Vec<String>,
+    outgoing: VecDeque<Bytes>,
+}
+
+impl CapNegotiator {
+    pub fn new<I, S>(wanted: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            wanted: wanted.into_iter().map(|s| s.as_ref().to_owned()).collect(),
+            version: Some(302),
+            hold_end: false,
+            state: CapState::Idle,
+            available: Vec::new(),
+            enabled: Vec::new(),
+            pending: Vec::new(),
+            rejected: Vec::new(),
+            outgoing: VecDeque::new(),
+        }
+    }
+
+    /// Sets the version sent with `CAP LS`, `None` sends a plain `CAP LS`.
+    ///
+    /// Defaults to `302`, which enables capability values, multiline replies and
+    /// `CAP NEW`/`CAP DEL`.
+    pub fn version(mut self, version: Option<u16>) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Holds back `CAP END` until [`CapNegotiator::end()`] is called.
+    pub fn hold_end(mut self, hold: bool) -> Self {
+        self.hold_end = hold;
+        self
+    }
+
+    /// Queues `CAP LS [version]`.
+    pub fn start(&mut self) {
+        match self.version {
+            Some(version) => self.queue(&["LS", &version.to_string()], None),
+            None => self.queue(&["LS"], None),
+        }
+        .expect("CAP LS always serializes");
+
+        self.available.clear();
+        self.state = CapState::Listing;
+    }
+
+    /// Queues `CAP END` if it was not sent yet.
+    pub fn end(&mut self) {
+        if self.state == CapState::Done {
+            return;
+        }
+
+        self.queue(&["END"], None)
+            .expect("CAP END always serializes");
+        self.state = CapState::Done;
+    }
+
+    /// Processes an incoming message.
+    ///
+    /// Returns `Ok(true)` if the message was part of the negotiation.
+    ///
+    /// A `CAP REQ` line that cannot be serialized is a [`CapError::Ser`]; its
+    /// capabilities are not requested and the negotiation carries on without
+    /// them, sending `CAP END` once the rest are answered.
+    pub fn handle(&mut self, msg: &Message<'_>) -> Result<bool, CapError> {
+        let command = msg.command();
+
+        if command == Commands::CAP {
+            let cap = CapMessage::from_message(msg)?;
+            self.handle_cap(&cap)?;
+            return Ok(true);
+        }
+
+        match command.numeric() {
+            // The server does not know CAP at all.
+            Some(Numeric::ERR_UNKNOWNCOMMAND)
+                if msg
+                    .params()
+                    .get(1)
+                    .is_some_and(|c| c.eq_ignore_ascii_case("CAP")) =>
+            {
+                self.state = CapState::Done;
+                Ok(true)
+            }
+            Some(Numeric::ERR_INVALIDCAPCMD) => {
+                self.pending.clear();
+                self.finish();
+                Ok(true)
+            }
+            // Registration completed without negotiation.
+            Some(Numeric::RPL_WELCOME) if self.state != CapState::Idle => {
+                self.state = CapState::Done;
+                Ok(false)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Returns the next message to send.
+    pub fn poll_transmit(&mut self) -> Option<Bytes> {
+        self.outgoing.pop_front()
+    }
+
+    pub fn state(&self) -> CapState {
+        self.state
+    }
+
+    /// Returns `true` once no request is outstanding.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, CapState::Ready | CapState::Done)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enabled.iter().any(|cap| cap == name)
+    }
+
+    pub fn is_available(&self, name: &str) -> bool {
+        self.available.iter().any(|(cap, _)| cap == name)
+    }
+
+    /// Returns the value the server advertised for `name`.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.available
+            .iter()
+            .find(|(cap, _)| cap == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &str> {
+        self.enabled.iter().map(|s| s.as_str())
+    }
+
+    pub fn available(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.available
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_deref()))
+    }
+
+    /// Capabilities the server refused with `NAK`.
+    pub fn rejected(&self) -> impl Iterator<Item = &str> {
+        self.rejected.iter().map(|s| s.as_str())
+    }
+
+    fn handle_cap(&mut self, cap: &CapMessage<'_>) -> Result<(), SerError> {
+        match cap.subcommand {
+            CapSubCommands::LS => {
+                for c in cap.caps {
+                    self.add_available(c);
+                }
+
+                if !cap.continued && self.state == CapState::Listing {
+                    let result = self.request_wanted();
+                    if self.pending.is_empty() {
+                        self.finish();
+                    } else {
+                        self.state = CapState::Requesting;
+                    }
+                    return result;
+                }
+            }
+            CapSubCommands::ACK => {
+                for c in cap.caps {
+                    self.pending.retain(|p| p != c.name);
+                    if c.remove {
+                        self.enabled.retain(|e| e != c.name);
+                    } else if !self.is_enabled(c.name) {
+                        self.enabled.push(c.name.to_owned());
+                    }
+                }
+                self.finish_if_answered();
+            }
+            CapSubCommands::NAK => {
+                for c in cap.caps {
+                    self.pending.retain(|p| p != c.name);
+                    if !self.rejected.iter().any(|r| r == c.name) {
+                        self.rejected.push(c.name.to_owned());
+                    }
+                }
+                self.finish_if_answered();
+            }
+            CapSubCommands::NEW => {
+                for c in cap.caps {
+                    self.add_available(c);
+                }
+
+                if matches!(self.state, CapState::Ready | CapState::Done) {
+                    return self.request_wanted();
+                }
+            }
+            CapSubCommands::DEL => {
+                for c in cap.caps {
+                    self.available.retain(|(name, _)| name != c.name);
+                    self.enabled.retain(|name| name != c.name);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn add_available(&mut self, cap: Cap<'_>) {
+        let value = cap.value.map(|v| v.to_owned());
+        match self.available.iter_mut().find(|(name, _)| name == cap.name) {
+            Some(existing) => existing.1 = value,
+            None => self.available.push((cap.name.to_owned(), value)),
+        }
+    }
+
+    /// Queues `CAP REQ` for wanted capabilities that are available but neither
+    /// enabled, pending nor rejected.
+    ///
+    /// Only capabilities whose line was queued become pending.
+    fn request_wanted(&mut self) -> Result<(), SerError> {
+        let request: Vec<&str> = self
+            .wanted
+            .iter()
+            .filter(|w| {
+                self.is_available(w)
+                    && !self.is_enabled(w)
+                    && !self.pending.contains(w)
+                    && !self.rejected.contains(w)
+            })
+            .map(|s| s.as_str())
+            .collect();
+
+        // "CAP REQ :" + caps + CRLF
+        let budget = MAX_LINE_LEN - "CAP REQ :".len() - 2;
+
+        let lines: Vec<Vec<String>> = chunk(&request, budget, usize::MAX)
+            .into_iter()
+            .map(|caps| caps.iter().map(|&s| s.to_owned()).collect())
+            .collect();
+
+        let mut result = Ok(());
+        for caps in lines {
+            match self.queue(&["REQ"], Some(&caps.join(" "))) {
+                Ok(()) => self.pending.extend(caps),
+                Err(e) if result.is_ok() => result = Err(e),
+                Err(_) => {}
+            }
+        }
+        result
+    }
+
+    fn finish_if_answered(&mut self) {
+        if self.state == CapState::Requesting && self.pending.is_empty() {
+            self.finish();
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.state == CapState::Done {
+            return;
+        }
+
+        if self.hold_end {
+            self.state = CapState::Ready;
+        } else {
+            self.end();
+        }
+    }
+
+    fn queue(&mut self, params: &[&str], trailing: Option<&str>) -> Result<(), SerError> {
+        let mut builder = MessageBuilder::new();
+        builder.set_command(Commands::CAP)?.add_params(params)?;
+        if let Some(trailing) = trailing {
+            builder.set_trailing(trailing)?;
+        }
+
+        self.outgoing.push_back(builder.build()?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(cap: &mut CapNegotiator, line: &str) {
+        cap.handle(&crate::parse(line).unwrap()).unwrap();
+    }
+
+    fn drain(cap: &mut CapNegotiator) -> Vec<String> {
+        core::iter::from_fn(|| cap.poll_transmit())
+            .map(|b| String::from_utf8(b.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn cap_list() {
+        let list =
+            CapList::new("sasl=PLAIN,EXTERNAL multi-prefix  -away-notify draft/chathistory=100");
+        let caps: Vec<_> = list.iter().collect();
+
+        assert_eq!(4, caps.len());
+        assert_eq!(
+            Cap {
+                name: "sasl",
+                value: Some("PLAIN,EXTERNAL"),
+                remove: false
+            },
+            caps[0]
+        );
+        assert_eq!(
+            vec!["PLAIN", "EXTERNAL"],
+            caps[0].values().collect::<Vec<_>>()
+        );
+        assert_eq!((None, false), (caps[1].value, caps[1].remove));
+        assert_eq!(("away-notify", true), (caps[2].name, caps[2].remove));
+        assert_eq!(Some("100"), list.get("draft/chathistory").unwrap().value);
+        assert!(!list.contains("batch"));
+        assert!(CapList::new(" ").is_empty());
+    }
+
+    #[test]
+    fn cap_message() {
+        let msg: CapMessage =
+            crate::from_str(":irc.example.com CAP * LS * :sasl multi-prefix").unwrap();
+        assert_eq!(CapSubCommands::LS, msg.subcommand);
+        assert!(msg.continued);
+        assert!(msg.caps.contains("sasl"));
+
+        let msg: CapMessage = crate::from_str(":irc.example.com CAP alice ls :batch").unwrap();
+        assert_eq!(CapSubCommands::LS, msg.subcommand);
+        assert!(!msg.continued);
+
+        let msg: CapMessage = crate::from_str(":irc.example.com CAP alice ACK batch").unwrap();
+        assert_eq!(CapSubCommands::ACK, msg.subcommand);
+        assert!(msg.caps.contains("batch"));
+    }
+
+    #[test]
+    fn multiline_ls() {
+        let mut cap = CapNegotiator::new(["sasl", "batch", "server-time"]);
+        cap.start();
+        assert_eq!(["CAP LS 302\r\n"], drain(&mut cap).as_slice());
+
+        feed(&mut cap, ":srv CAP * LS * :multi-prefix sasl=PLAIN");
+        assert!(drain(&mut cap).is_empty());
+        assert_eq!(CapState::Listing, cap.state());
+
+        feed(&mut cap, ":srv CAP * LS :batch away-notify");
+        assert_eq!(["CAP REQ :sasl batch\r\n"], drain(&mut cap).as_slice());
+        assert_eq!(CapState::Requesting, cap.state());
+
+        feed(&mut cap, ":srv CAP * NAK :sasl batch");
+        assert_eq!(["CAP END\r\n"], drain(&mut cap).as_slice());
+        assert!(!cap.is_enabled("sasl"));
+        assert_eq!(vec!["sasl", "batch"], cap.rejected().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn nothing_wanted() {
+        let mut cap = CapNegotiator::new(["echo-message"]).version(None);
+        cap.start();
+        feed(&mut cap, ":srv CAP * LS :multi-prefix");
+        assert_eq!(["CAP LS\r\n", "CAP END\r\n"], drain(&mut cap).as_slice());
+        assert_eq!(CapState::Done, cap.state());
+    }
+
+    #[test]
+    fn unserializable_req() {
+        let mut cap = CapNegotiator::new(["bad\0cap"]);
+        cap.start();
+        let err = cap
+            .handle(&crate::parse(":srv CAP * LS :bad\0cap").unwrap())
+            .unwrap_err();
+        assert!(matches!(err, CapError::Ser(_)));
+        assert_eq!(
+            ["CAP LS 302\r\n", "CAP END\r\n"],
+            drain(&mut cap).as_slice()
+        );
+        assert_eq!(CapState::Done, cap.state());
+    }
+
+    #[test]
+    fn hold_end() {
+        let mut cap = CapNegotiator::new(["sasl"]).hold_end(true);
+        cap.start();
+        feed(&mut cap, ":srv CAP * LS :sasl");
+        feed(&mut cap, ":srv CAP * ACK :sasl");
+        assert_eq!(CapState::Ready, cap.state());
+        assert!(cap.is_finished());
+        assert_eq!(2, drain(&mut cap).len());
+
+        cap.end();
+        cap.end();
+        assert_eq!(["CAP END\r\n"], drain(&mut cap).as_slice());
+    }
+
+    #[test]
+    fn new_and_del() {
+        let mut cap = CapNegotiator::new(["away-notify", "batch"]);
+        cap.start();
+        feed(&mut cap, ":srv CAP * LS :batch");
+        feed(&mut cap, ":srv CAP * ACK :batch");
+        drain(&mut cap);
+
+        feed(&mut cap, ":srv CAP alice NEW :away-notify extended-join");
+        assert_eq!(["CAP REQ :away-notify\r\n"], drain(&mut cap).as_slice());
+        feed(&mut cap, ":srv CAP alice ACK :away-notify");
+        assert!(drain(&mut cap).is_empty());
+        assert!(cap.is_enabled("away-notify"));
+
+        feed(&mut cap, ":srv CAP alice DEL :batch");
+        assert!(!cap.is_enabled("batch"));
+        assert!(!cap.is_available("batch"));
+        assert_eq!(CapState::Done, cap.state());
+    }
+
+    #[test]
+    fn req_split() {
+        let wanted: Vec<String> = (0..60)
+            .map(|i| format!("vendor.example/cap-{i:02}"))
+            .collect();
+        let mut cap = CapNegotiator::new(&wanted);
+        cap.start();
+        drain(&mut cap);
+
+        feed(&mut cap, &format!(":srv CAP * LS :{}", wanted.join(" ")));
+        let lines = drain(&mut cap);
+        assert!(lines.len() > 1);
+
+        let mut requested = Vec::new();
+        for line in &lines {
+            assert!(line.len() <= MAX_LINE_LEN);
+            let caps = line.strip_prefix("CAP REQ :").unwrap().trim_end();
+            requested.extend(caps.split(' ').map(|s| s.to_string()));
+        }
+        assert_eq!(wanted, requested);
+
+        feed(
+            &mut cap,
+            &format!(":srv CAP * ACK :{}", wanted[..30].join(" ")),
+        );
+        assert_eq!(CapState::Requesting, cap.state());
+        feed(
+            &mut cap,
+            &format!(":srv CAP * ACK :{}", wanted[30..].join(" ")),
+        );
+        assert_eq!(["CAP END\r\n"], drain(&mut cap).as_slice());
+    }
+
+    #[test]
+    fn unsupported() {
+        let mut cap = CapNegotiator::new(["sasl"]);
+        cap.start();
+        feed(&mut cap, ":srv 421 * CAP :Unknown command");
+        assert_eq!(CapState::Done, cap.state());
+
+        let mut cap = CapNegotiator::new(["sasl"]);
+        cap.start();
+        feed(&mut cap, ":srv 001 alice :Welcome");
+        assert_eq!(CapState::Done, cap.state());
+    }
+}
@@ -2,8 +2,13 @@ use crate::compat::{Display, FmtResult, Formatter, String, ToString};
 
 use crate::{error::CommandError, validators};
 
+use super::Numeric;
+
 /// IRC command types following RFC 1459 and RFC 2812.
 ///
+/// Also covers the IRCv3 extension commands and the Twitch-specific commands.
+/// Anything else is kept as [`Commands::CUSTOM`].
+///
 /// # Case Insensitivity
 ///
 /// **All IRC commands are case-insensitive**.
@@ -11,6 +16,13 @@ use crate::{error::CommandError, validators};
 /// - [`Commands::from()`] accepts any case (PRIVMSG, privmsg, PrivMsg)
 /// - Comparisons via [`PartialEq`] are case-insensitive
 /// - [`Commands::as_str()`] always returns uppercase
+///
+/// # Numerics
+///
+/// Numeric replies are kept as [`Commands::NUMERIC`] with their three-digit code.
+/// [`Commands::from()`] takes the code; a [`Numeric`] converts with
+/// `Commands::from(Numeric::ERR_NICKNAMEINUSE)`, and [`Commands::numeric()`]
+/// resolves the code back to its name.
 #[derive(Debug, Clone, Copy, Hash)]
 pub enum Commands<'a> {
     NUMERIC(&'a str),
@@ -61,6 +73,31 @@ pub enum Commands<'a> {
     LINKS,
     USERHOST,
     WALLOPS,
+    // IRCv3 Extensions
+    TAGMSG,
+    BATCH,
+    ACCOUNT,
+    CHGHOST,
+    SETNAME,
+    FAIL,
+    WARN,
+    NOTE,
+    ACK,
+    MONITOR,
+    CHATHISTORY,
+    REDACT,
+    MARKREAD,
+    WEBIRC,
+    KNOCK,
+    SILENCE,
+    // Twitch
+    USERNOTICE,
+    ROOMSTATE,
+    USERSTATE,
+    GLOBALUSERSTATE,
+    CLEARCHAT,
+    CLEARMSG,
+    WHISPER,
     CUSTOM(&'a str),
 }
 
@@ -112,6 +149,29 @@ impl<'a> Commands<'a> {
             Self::LINKS => "LINKS",
             Self::USERHOST => "USERHOST",
             Self::WALLOPS => "WALLOPS",
+            Self::TAGMSG => "TAGMSG",
+            Self::BATCH => "BATCH",
+            Self::ACCOUNT => "ACCOUNT",
+            Self::CHGHOST => "CHGHOST",
+            Self::SETNAME => "SETNAME",
+            Self::FAIL => "FAIL",
+            Self::WARN => "WARN",
+            Self::NOTE => "NOTE",
+            Self::ACK => "ACK",
+            Self::MONITOR => "MONITOR",
+            Self::CHATHISTORY => "CHATHISTORY",
+            Self::REDACT => "REDACT",
+            Self::MARKREAD => "MARKREAD",
+            Self::WEBIRC => "WEBIRC",
+            Self::KNOCK => "KNOCK",
+            Self::SILENCE => "SILENCE",
+            Self::USERNOTICE => "USERNOTICE",
+            Self::ROOMSTATE => "ROOMSTATE",
+            Self::USERSTATE => "USERSTATE",
+            Self::GLOBALUSERSTATE => "GLOBALUSERSTATE",
+            Self::CLEARCHAT => "CLEARCHAT",
+            Self::CLEARMSG => "CLEARMSG",
+            Self::WHISPER => "WHISPER",
             Self::CUSTOM(custom) => custom,
         }
     }
@@ -162,6 +222,29 @@ impl<'a> Commands<'a> {
             Self::LINKS => 5,
             Self::USERHOST => 8,
             Self::WALLOPS => 7,
+            Self::TAGMSG => 6,
+            Self::BATCH => 5,
+            Self::ACCOUNT => 7,
+            Self::CHGHOST => 7,
+            Self::SETNAME => 7,
+            Self::FAIL => 4,
+            Self::WARN => 4,
+            Self::NOTE => 4,
+            Self::ACK => 3,
+            Self::MONITOR => 7,
+            Self::CHATHISTORY => 11,
+            Self::REDACT => 6,
+            Self::MARKREAD => 8,
+            Self::WEBIRC => 6,
+            Self::KNOCK => 5,
+            Self::SILENCE => 7,
+            Self::USERNOTICE => 10,
+            Self::ROOMSTATE => 9,
+            Self::USERSTATE => 9,
+            Self::GLOBALUSERSTATE => 15,
+            Self::CLEARCHAT => 9,
+            Self::CLEARMSG => 8,
+            Self::WHISPER => 7,
             Self::CUSTOM(unknown) => unknown.len(),
         }
     }
@@ -210,6 +293,29 @@ impl<'a> Commands<'a> {
             Self::LINKS => b"LINKS",
             Self::USERHOST => b"USERHOST",
             Self::WALLOPS => b"WALLOPS",
+            Self::TAGMSG => b"TAGMSG",
+            Self::BATCH => b"BATCH",
+            Self::ACCOUNT => b"ACCOUNT",
+            Self::CHGHOST => b"CHGHOST",
+            Self::SETNAME => b"SETNAME",
+            Self::FAIL => b"FAIL",
+            Self::WARN => b"WARN",
+            Self::NOTE => b"NOTE",
+            Self::ACK => b"ACK",
+            Self::MONITOR => b"MONITOR",
+            Self::CHATHISTORY => b"CHATHISTORY",
+            Self::REDACT => b"REDACT",
+            Self::MARKREAD => b"MARKREAD",
+            Self::WEBIRC => b"WEBIRC",
+            Self::KNOCK => b"KNOCK",
+            Self::SILENCE => b"SILENCE",
+            Self::USERNOTICE => b"USERNOTICE",
+            Self::ROOMSTATE => b"ROOMSTATE",
+            Self::USERSTATE => b"USERSTATE",
+            Self::GLOBALUSERSTATE => b"GLOBALUSERSTATE",
+            Self::CLEARCHAT => b"CLEARCHAT",
+            Self::CLEARMSG => b"CLEARMSG",
+            Self::WHISPER => b"WHISPER",
             Self::CUSTOM(unknown) => unknown.as_bytes(),
         }
     }
@@ -234,6 +340,38 @@ impl<'a> Commands<'a> {
         *self == Self::NOTICE
     }
 
+    #[inline]
+    pub fn is_tagmsg(&self) -> bool {
+        *self == Self::TAGMSG
+    }
+
+    #[inline]
+    pub fn is_batch(&self) -> bool {
+        *self == Self::BATCH
+    }
+
+    /// Returns `true` for the IRCv3 standard replies `FAIL`, `WARN` and `NOTE`.
+    #[inline]
+    pub fn is_standard_reply(&self) -> bool {
+        matches!(self, Self::FAIL | Self::WARN | Self::NOTE)
+    }
+
+    #[inline]
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Self::NUMERIC(_))
+    }
+
+    /// Returns the named [`Numeric`] for a known numeric reply.
+    ///
+    /// Returns `None` for non-numeric commands and unknown codes.
+    #[inline]
+    pub fn numeric(&self) -> Option<Numeric> {
+        match self {
+            Self::NUMERIC(code) => Numeric::from_code_str(code),
+            _ => None,
+        }
+    }
+
     pub fn validate(&self) -> Result<(), CommandError> {
         validators::command(self.as_str())
     }
@@ -292,6 +430,29 @@ impl<'a> From<&'a str> for Commands<'a> {
             "LINKS" => Self::LINKS,
             "USERHOST" => Self::USERHOST,
             "WALLOPS" => Self::WALLOPS,
+            "TAGMSG" => Self::TAGMSG,
+            "BATCH" => Self::BATCH,
+            "ACCOUNT" => Self::ACCOUNT,
+            "CHGHOST" => Self::CHGHOST,
+            "SETNAME" => Self::SETNAME,
+            "FAIL" => Self::FAIL,
+            "WARN" => Self::WARN,
+            "NOTE" => Self::NOTE,
+            "ACK" => Self::ACK,
+            "MONITOR" => Self::MONITOR,
+            "CHATHISTORY" => Self::CHATHISTORY,
+            "REDACT" => Self::REDACT,
+            "MARKREAD" => Self::MARKREAD,
+            "WEBIRC" => Self::WEBIRC,
+            "KNOCK" => Self::KNOCK,
+            "SILENCE" => Self::SILENCE,
+            "USERNOTICE" => Self::USERNOTICE,
+            "ROOMSTATE" => Self::ROOMSTATE,
+            "USERSTATE" => Self::USERSTATE,
+            "GLOBALUSERSTATE" => Self::GLOBALUSERSTATE,
+            "CLEARCHAT" => Self::CLEARCHAT,
+            "CLEARMSG" => Self::CLEARMSG,
+            "WHISPER" => Self::WHISPER,
         );
 
         if value.parse::<u16>().is_ok() {
@@ -302,6 +463,12 @@ impl<'a> From<&'a str> for Commands<'a> {
     }
 }
 
+impl From<Numeric> for Commands<'_> {
+    fn from(value: Numeric) -> Self {
+        Self::NUMERIC(value.code_str())
+    }
+}
+
 impl Display for Commands<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         f.write_str(self.as_str())
@@ -407,7 +574,7 @@ impl CapSubCommands {
 
 impl From<&str> for CapSubCommands {
     fn from(value: &str) -> Self {
-        match value {
+        parse_command!(value,
             "LS" => Self::LS,
             "LIST" => Self::LIST,
             "REQ" => Self::REQ,
@@ -416,8 +583,9 @@ impl From<&str> for CapSubCommands {
             "END" => Self::END,
             "NEW" => Self::NEW,
             "DEL" => Self::DEL,
-            _ => Self::UNKNOWN(value.to_string()),
-        }
+        );
+
+        Self::UNKNOWN(value.to_string())
     }
 }
 
@@ -449,7 +617,11 @@ impl serde::Serialize for CapSubCommands {
 
 #[cfg(test)]
 mod tests {
-    use crate::{components::Commands, de::FromMessage, ser::ToMessage};
+    use crate::{
+        components::{CapSubCommands, Commands},
+        de::FromMessage,
+        ser::ToMessage,
+    };
 
     #[test]
     fn from_case_insensitive() {
@@ -463,6 +635,65 @@ mod tests {
         assert_eq!(Commands::from("PiNg"), Commands::PING);
     }
 
+    #[test]
+    fn ircv3_commands() {
+        let commands = [
+            Commands::TAGMSG,
+            Commands::BATCH,
+            Commands::ACCOUNT,
+            Commands::CHGHOST,
+            Commands::SETNAME,
+            Commands::FAIL,
+            Commands::WARN,
+            Commands::NOTE,
+            Commands::ACK,
+            Commands::MONITOR,
+            Commands::CHATHISTORY,
+            Commands::REDACT,
+            Commands::MARKREAD,
+            Commands::WEBIRC,
+            Commands::KNOCK,
+            Commands::SILENCE,
+            Commands::USERNOTICE,
+            Commands::ROOMSTATE,
+            Commands::USERSTATE,
+            Commands::GLOBALUSERSTATE,
+            Commands::CLEARCHAT,
+            Commands::CLEARMSG,
+            Commands::WHISPER,
+        ];
+
+        for cmd in commands {
+            assert_eq!(cmd.as_str().len(), cmd.len());
+            assert_eq!(cmd.as_str().as_bytes(), cmd.as_bytes());
+            assert!(!matches!(Commands::from(cmd.as_str()), Commands::CUSTOM(_)));
+
+            let lower = cmd.as_str().to_lowercase();
+            assert!(!matches!(
+                Commands::from(lower.as_str()),
+                Commands::CUSTOM(_)
+            ));
+            assert_eq!(Commands::from(lower.as_str()), cmd);
+        }
+
+        assert!(Commands::from("tagmsg").is_tagmsg());
+        assert!(Commands::from("BATCH").is_batch());
+        assert!(Commands::from("FAIL").is_standard_reply());
+        assert!(!Commands::PRIVMSG.is_standard_reply());
+        assert!(Commands::from("001").is_numeric());
+    }
+
+    #[test]
+    fn cap_subcommands_case_insensitive() {
+        assert_eq!(CapSubCommands::from("LS"), CapSubCommands::LS);
+        assert_eq!(CapSubCommands::from("ls"), CapSubCommands::LS);
+        assert_eq!(CapSubCommands::from("Ack"), CapSubCommands::ACK);
+        assert_eq!(
+            CapSubCommands::from("foo"),
+            CapSubCommands::UNKNOWN("foo".to_string())
+        );
+    }
+
     #[test]
     fn partialeq_case_insensitive() {
         let cmd = Commands::PRIVMSG;
@@ -1,9 +1,13 @@
 mod commands;
+mod numeric;
 mod params;
+mod server_time;
 mod source;
 mod tags;
 
 pub use commands::{CapSubCommands, Commands};
+pub use numeric::{Numeric, NumericKind};
 pub use params::{Middles, Params};
+pub use server_time::ServerTime;
 pub use source::Source;
 pub use tags::{TagValue, Tags};
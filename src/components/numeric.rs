@@ -0,0 +1,414 @@
+use crate::compat::{Display, FmtResult, Formatter};
+
+/// Category of a [`Numeric`] reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumericKind {
+    /// Connection registration replies (`001`-`005`, `010`).
+    Registration,
+    /// Command replies (`RPL_*`).
+    Reply,
+    /// Error replies (`ERR_*`).
+    Error,
+    /// SASL authentication results (`900`-`908`).
+    Sasl,
+}
+
+impl NumericKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Registration => "registration",
+            Self::Reply => "reply",
+            Self::Error => "error",
+            Self::Sasl => "sasl",
+        }
+    }
+}
+
+impl Display for NumericKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+macro_rules! numerics {
+    ($($kind:ident { $($code:literal => $name:ident,)* })*) => {
+        /// Named numeric replies following RFC 1459, RFC 2812 and the modern IRC documentation.
+        ///
+        /// Converts between the three-digit code and the conventional name.
+        ///
+        /// ```rust
+        /// use ircv3_parse::components::{Numeric, NumericKind};
+        ///
+        /// let numeric = Numeric::from_code(433).unwrap();
+        /// assert_eq!(Numeric::ERR_NICKNAMEINUSE, numeric);
+        /// assert_eq!("ERR_NICKNAMEINUSE", numeric.as_str());
+        /// assert_eq!("433", numeric.code_str());
+        /// assert_eq!(NumericKind::Error, numeric.kind());
+        ///
+        /// assert_eq!(Some(Numeric::RPL_WELCOME), Numeric::from_name("RPL_WELCOME"));
+        /// ```
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Numeric {
+            $($($name,)*)*
+        }
+
+        impl Numeric {
+            /// All known numerics, ordered by code.
+            pub const ALL: &'static [Numeric] = &[$($(Self::$name,)*)*];
+
+            /// Returns the conventional name, e.g. `RPL_WELCOME`.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($(Self::$name => stringify!($name),)*)*
+                }
+            }
+
+            /// Returns the three-digit code as it appears on the wire, e.g. `001`.
+            pub fn code_str(&self) -> &'static str {
+                match self {
+                    $($(Self::$name => $code,)*)*
+                }
+            }
+
+            pub fn kind(&self) -> NumericKind {
+                match self {
+                    $($(Self::$name => NumericKind::$kind,)*)*
+                }
+            }
+
+            pub fn from_code(code: u16) -> Option<Self> {
+                Self::ALL.iter().copied().find(|numeric| numeric.code() == code)
+            }
+        }
+    };
+}
+
+numerics! {
+    Registration {
+        "001" => RPL_WELCOME,
+        "002" => RPL_YOURHOST,
+        "003" => RPL_CREATED,
+        "004" => RPL_MYINFO,
+        "005" => RPL_ISUPPORT,
+        "010" => RPL_BOUNCE,
+    }
+    Reply {
+        "200" => RPL_TRACELINK,
+        "201" => RPL_TRACECONNECTING,
+        "202" => RPL_TRACEHANDSHAKE,
+        "203" => RPL_TRACEUNKNOWN,
+        "204" => RPL_TRACEOPERATOR,
+        "205" => RPL_TRACEUSER,
+        "206" => RPL_TRACESERVER,
+        "207" => RPL_TRACESERVICE,
+        "208" => RPL_TRACENEWTYPE,
+        "209" => RPL_TRACECLASS,
+        "211" => RPL_STATSLINKINFO,
+        "212" => RPL_STATSCOMMANDS,
+        "219" => RPL_ENDOFSTATS,
+        "221" => RPL_UMODEIS,
+        "234" => RPL_SERVLIST,
+        "235" => RPL_SERVLISTEND,
+        "242" => RPL_STATSUPTIME,
+        "243" => RPL_STATSOLINE,
+        "251" => RPL_LUSERCLIENT,
+        "252" => RPL_LUSEROP,
+        "253" => RPL_LUSERUNKNOWN,
+        "254" => RPL_LUSERCHANNELS,
+        "255" => RPL_LUSERME,
+        "256" => RPL_ADMINME,
+        "257" => RPL_ADMINLOC1,
+        "258" => RPL_ADMINLOC2,
+        "259" => RPL_ADMINEMAIL,
+        "261" => RPL_TRACELOG,
+        "262" => RPL_TRACEEND,
+        "263" => RPL_TRYAGAIN,
+        "265" => RPL_LOCALUSERS,
+        "266" => RPL_GLOBALUSERS,
+        "276" => RPL_WHOISCERTFP,
+        "300" => RPL_NONE,
+        "301" => RPL_AWAY,
+        "302" => RPL_USERHOST,
+        "303" => RPL_ISON,
+        "305" => RPL_UNAWAY,
+        "306" => RPL_NOWAWAY,
+        "307" => RPL_WHOISREGNICK,
+        "311" => RPL_WHOISUSER,
+        "312" => RPL_WHOISSERVER,
+        "313" => RPL_WHOISOPERATOR,
+        "314" => RPL_WHOWASUSER,
+        "315" => RPL_ENDOFWHO,
+        "317" => RPL_WHOISIDLE,
+        "318" => RPL_ENDOFWHOIS,
+        "319" => RPL_WHOISCHANNELS,
+        "320" => RPL_WHOISSPECIAL,
+        "321" => RPL_LISTSTART,
+        "322" => RPL_LIST,
+        "323" => RPL_LISTEND,
+        "324" => RPL_CHANNELMODEIS,
+        "325" => RPL_UNIQOPIS,
+        "329" => RPL_CREATIONTIME,
+        "330" => RPL_WHOISACCOUNT,
+        "331" => RPL_NOTOPIC,
+        "332" => RPL_TOPIC,
+        "333" => RPL_TOPICWHOTIME,
+        "336" => RPL_INVITELIST,
+        "337" => RPL_ENDOFINVITELIST,
+        "338" => RPL_WHOISACTUALLY,
+        "341" => RPL_INVITING,
+        "346" => RPL_INVEXLIST,
+        "347" => RPL_ENDOFINVEXLIST,
+        "348" => RPL_EXCEPTLIST,
+        "349" => RPL_ENDOFEXCEPTLIST,
+        "351" => RPL_VERSION,
+        "352" => RPL_WHOREPLY,
+        "353" => RPL_NAMREPLY,
+        "354" => RPL_WHOSPCRPL,
+        "364" => RPL_LINKS,
+        "365" => RPL_ENDOFLINKS,
+        "366" => RPL_ENDOFNAMES,
+        "367" => RPL_BANLIST,
+        "368" => RPL_ENDOFBANLIST,
+        "369" => RPL_ENDOFWHOWAS,
+        "371" => RPL_INFO,
+        "372" => RPL_MOTD,
+        "374" => RPL_ENDOFINFO,
+        "375" => RPL_MOTDSTART,
+        "376" => RPL_ENDOFMOTD,
+        "378" => RPL_WHOISHOST,
+        "379" => RPL_WHOISMODES,
+        "381" => RPL_YOUREOPER,
+        "382" => RPL_REHASHING,
+        "383" => RPL_YOURESERVICE,
+        "391" => RPL_TIME,
+        "392" => RPL_USERSSTART,
+        "393" => RPL_USERS,
+        "394" => RPL_ENDOFUSERS,
+        "395" => RPL_NOUSERS,
+    }
+    Error {
+        "400" => ERR_UNKNOWNERROR,
+        "401" => ERR_NOSUCHNICK,
+        "402" => ERR_NOSUCHSERVER,
+        "403" => ERR_NOSUCHCHANNEL,
+        "404" => ERR_CANNOTSENDTOCHAN,
+        "405" => ERR_TOOMANYCHANNELS,
+        "406" => ERR_WASNOSUCHNICK,
+        "407" => ERR_TOOMANYTARGETS,
+        "408" => ERR_NOSUCHSERVICE,
+        "409" => ERR_NOORIGIN,
+        "410" => ERR_INVALIDCAPCMD,
+        "411" => ERR_NORECIPIENT,
+        "412" => ERR_NOTEXTTOSEND,
+        "413" => ERR_NOTOPLEVEL,
+        "414" => ERR_WILDTOPLEVEL,
+        "415" => ERR_BADMASK,
+        "417" => ERR_INPUTTOOLONG,
+        "421" => ERR_UNKNOWNCOMMAND,
+        "422" => ERR_NOMOTD,
+        "423" => ERR_NOADMININFO,
+        "424" => ERR_FILEERROR,
+        "431" => ERR_NONICKNAMEGIVEN,
+        "432" => ERR_ERRONEUSNICKNAME,
+        "433" => ERR_NICKNAMEINUSE,
+        "436" => ERR_NICKCOLLISION,
+        "437" => ERR_UNAVAILRESOURCE,
+        "441" => ERR_USERNOTINCHANNEL,
+        "442" => ERR_NOTONCHANNEL,
+        "443" => ERR_USERONCHANNEL,
+        "444" => ERR_NOLOGIN,
+        "445" => ERR_SUMMONDISABLED,
+        "446" => ERR_USERSDISABLED,
+        "451" => ERR_NOTREGISTERED,
+        "461" => ERR_NEEDMOREPARAMS,
+        "462" => ERR_ALREADYREGISTERED,
+        "463" => ERR_NOPERMFORHOST,
+        "464" => ERR_PASSWDMISMATCH,
+        "465" => ERR_YOUREBANNEDCREEP,
+        "466" => ERR_YOUWILLBEBANNED,
+        "467" => ERR_KEYSET,
+        "471" => ERR_CHANNELISFULL,
+        "472" => ERR_UNKNOWNMODE,
+        "473" => ERR_INVITEONLYCHAN,
+        "474" => ERR_BANNEDFROMCHAN,
+        "475" => ERR_BADCHANNELKEY,
+        "476" => ERR_BADCHANMASK,
+        "477" => ERR_NOCHANMODES,
+        "478" => ERR_BANLISTFULL,
+        "481" => ERR_NOPRIVILEGES,
+        "482" => ERR_CHANOPRIVSNEEDED,
+        "483" => ERR_CANTKILLSERVER,
+        "484" => ERR_RESTRICTED,
+        "485" => ERR_UNIQOPPRIVSNEEDED,
+        "491" => ERR_NOOPERHOST,
+        "501" => ERR_UMODEUNKNOWNFLAG,
+        "502" => ERR_USERSDONTMATCH,
+        "524" => ERR_HELPNOTFOUND,
+        "525" => ERR_INVALIDKEY,
+    }
+    Reply {
+        "670" => RPL_STARTTLS,
+        "671" => RPL_WHOISSECURE,
+    }
+    Error {
+        "691" => ERR_STARTTLS,
+        "696" => ERR_INVALIDMODEPARAM,
+    }
+    Reply {
+        "704" => RPL_HELPSTART,
+        "705" => RPL_HELPTXT,
+        "706" => RPL_ENDOFHELP,
+    }
+    Error {
+        "723" => ERR_NOPRIVS,
+    }
+    Reply {
+        "730" => RPL_MONONLINE,
+        "731" => RPL_MONOFFLINE,
+        "732" => RPL_MONLIST,
+        "733" => RPL_ENDOFMONLIST,
+    }
+    Error {
+        "734" => ERR_MONLISTFULL,
+    }
+    Sasl {
+        "900" => RPL_LOGGEDIN,
+        "901" => RPL_LOGGEDOUT,
+        "902" => ERR_NICKLOCKED,
+        "903" => RPL_SASLSUCCESS,
+        "904" => ERR_SASLFAIL,
+        "905" => ERR_SASLTOOLONG,
+        "906" => ERR_SASLABORTED,
+        "907" => ERR_SASLALREADY,
+        "908" => RPL_SASLMECHS,
+    }
+}
+
+impl Numeric {
+    /// Returns the numeric code, e.g. `433`.
+    pub fn code(&self) -> u16 {
+        let bytes = self.code_str().as_bytes();
+        (bytes[0] - b'0') as u16 * 100 + (bytes[1] - b'0') as u16 * 10 + (bytes[2] - b'0') as u16
+    }
+
+    /// Looks up a numeric by its three-digit code string, e.g. `"433"`.
+    pub fn from_code_str(code: &str) -> Option<Self> {
+        if code.len() != 3 {
+            return None;
+        }
+
+        code.parse::<u16>().ok().and_then(Self::from_code)
+    }
+
+    /// Looks up a numeric by its conventional name, e.g. `"ERR_NICKNAMEINUSE"`.
+    ///
+    /// The lookup is case-insensitive, like command names.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let prefix = name.get(..4)?;
+        if !(prefix.eq_ignore_ascii_case("RPL_") || prefix.eq_ignore_ascii_case("ERR_")) {
+            return None;
+        }
+
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|numeric| numeric.as_str().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns `true` for `ERR_*` numerics, including SASL failures.
+    pub fn is_error(&self) -> bool {
+        self.as_str().starts_with("ERR_")
+    }
+}
+
+impl Display for Numeric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<Numeric> for u16 {
+    fn from(value: Numeric) -> Self {
+        value.code()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Numeric {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::{Commands, Numeric, NumericKind};
+
+    #[test]
+    fn code_name_roundtrip() {
+        for numeric in Numeric::ALL {
+            assert_eq!(Some(*numeric), Numeric::from_code(numeric.code()));
+            assert_eq!(Some(*numeric), Numeric::from_code_str(numeric.code_str()));
+            assert_eq!(Some(*numeric), Numeric::from_name(numeric.as_str()));
+        }
+    }
+
+    #[test]
+    fn codes_are_unique_and_sorted() {
+        for pair in Numeric::ALL.windows(2) {
+            assert!(
+                pair[0].code() < pair[1].code(),
+                "{} >= {}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn kind() {
+        assert_eq!(NumericKind::Registration, Numeric::RPL_WELCOME.kind());
+        assert_eq!(NumericKind::Registration, Numeric::RPL_ISUPPORT.kind());
+        assert_eq!(NumericKind::Reply, Numeric::RPL_NAMREPLY.kind());
+        assert_eq!(NumericKind::Error, Numeric::ERR_NICKNAMEINUSE.kind());
+        assert_eq!(NumericKind::Sasl, Numeric::RPL_SASLSUCCESS.kind());
+        assert_eq!(NumericKind::Sasl, Numeric::ERR_SASLFAIL.kind());
+
+        assert!(Numeric::ERR_SASLFAIL.is_error());
+        assert!(!Numeric::RPL_SASLSUCCESS.is_error());
+    }
+
+    #[test]
+    fn from_name() {
+        assert_eq!(
+            Some(Numeric::ERR_NICKNAMEINUSE),
+            Numeric::from_name("err_nicknameinuse")
+        );
+        assert_eq!(None, Numeric::from_name("ERR_UNKNOWN_NAME"));
+        assert_eq!(None, Numeric::from_name("PRIVMSG"));
+        assert_eq!(None, Numeric::from_code(999));
+        assert_eq!(None, Numeric::from_code_str("33"));
+    }
+
+    #[test]
+    fn commands() {
+        let cmd = Commands::from("433");
+        assert_eq!(Some(Numeric::ERR_NICKNAMEINUSE), cmd.numeric());
+
+        // Names are not looked up; `From<&str>` keeps the command as written.
+        let cmd = Commands::from("ERR_NICKNAMEINUSE");
+        assert!(matches!(cmd, Commands::CUSTOM("ERR_NICKNAMEINUSE")));
+        assert_ne!(Commands::from("433"), cmd);
+
+        assert_eq!(
+            Commands::NUMERIC("001"),
+            Commands::from(Numeric::RPL_WELCOME)
+        );
+        assert_eq!(None, Commands::PRIVMSG.numeric());
+        assert_eq!(None, Commands::from("999").numeric());
+    }
+}
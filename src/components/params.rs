@@ -1,5 +1,6 @@
 use crate::compat::{
-    format, Debug, Display, FmtResult, Formatter, SplitAsciiWhitespace, String, Vec,
+    format, Chain, Debug, Display, FmtResult, Formatter, OptionIntoIter, SplitAsciiWhitespace,
+    String, Vec,
 };
 
 use crate::{error::ParamError, validators};
@@ -27,6 +28,37 @@ impl<'a> Params<'a> {
         self.input
     }
 
+    /// Returns an iterator over all parameters, middles first and then the trailing one.
+    ///
+    /// The last parameter of a message may be sent either way (`NICK alice` or
+    /// `NICK :alice`), so this is the usual way to read positional parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let msg = ircv3_parse::parse("KICK #channel alice :Bye")?;
+    /// let params: Vec<_> = msg.params().iter().collect();
+    ///
+    /// assert_eq!(params, ["#channel", "alice", "Bye"]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Chain<SplitAsciiWhitespace<'a>, OptionIntoIter<&'a str>> {
+        self.middles.iter().chain(self.trailing.raw())
+    }
+
+    /// Returns the parameter at `index`, counting the trailing parameter last.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&'a str> {
+        self.iter().nth(index)
+    }
+
+    /// Returns the number of parameters, including the trailing parameter.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.middles.count() + self.trailing.is_some() as usize
+    }
+
     /// Returns the parameters formatted as they would appear in a message.
     ///
     /// Includes the leading space and `:` prefix for trailing parameter.
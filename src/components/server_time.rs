@@ -0,0 +1,366 @@
+use crate::compat::{format, Display, FmtResult, Formatter};
+
+use crate::{
+    de::FromMessage,
+    ser::{MessageSerializer, ToMessage},
+    DeError, Message, SerError,
+};
+
+pub(crate) const TIME_TAG: &str = "time";
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// A UTC timestamp from the `server-time` tag, e.g. `2024-01-01T12:00:00.000Z`.
+///
+/// Fields are ordered from most to least significant, so comparing two values
+/// compares them chronologically. With the `chrono` feature it converts to and
+/// from `chrono::DateTime<Utc>`.
+///
+/// As a field with `#[irc(tag = "time")]`, it is read from and written to the
+/// `time` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServerTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+}
+
+impl ServerTime {
+    /// Parses `YYYY-MM-DDThh:mm:ss[.sss]Z`.
+    ///
+    /// Any number of fractional digits is accepted and truncated to
+    /// milliseconds. Returns `None` for any other format or an invalid date.
+    ///
+    /// ```rust
+    /// # use ircv3_parse::components::ServerTime;
+    /// let time = ServerTime::parse("2011-10-19T16:40:51.620Z").unwrap();
+    /// assert_eq!((2011, 10, 19), (time.year, time.month, time.day));
+    /// assert_eq!(1319042451620, time.unix_millis());
+    /// ```
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.strip_suffix('Z')?;
+        let bytes = value.as_bytes();
+        // The fields are sliced by byte offset.
+        if !value.is_ascii()
+            || bytes.len() < 19
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || bytes[10] != b'T'
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return None;
+        }
+
+        let millisecond = match &value[19..] {
+            "" => 0,
+            fraction => {
+                let digits = fraction.strip_prefix('.')?;
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let millis = digits.get(..3).unwrap_or(digits);
+                number(millis)? * 10u16.pow(3 - millis.len() as u32)
+            }
+        };
+
+        let time = Self {
+            year: number(&value[0..4])?,
+            month: number(&value[5..7])? as u8,
+            day: number(&value[8..10])? as u8,
+            hour: number(&value[11..13])? as u8,
+            minute: number(&value[14..16])? as u8,
+            second: number(&value[17..19])? as u8,
+            millisecond,
+        };
+        time.is_valid().then_some(time)
+    }
+
+    /// Builds a timestamp from milliseconds since the Unix epoch.
+    ///
+    /// Returns `None` outside of the years 0 to 9999.
+    pub fn from_unix_millis(millis: i64) -> Option<Self> {
+        let days = millis.div_euclid(MILLIS_PER_DAY);
+        let rest = millis.rem_euclid(MILLIS_PER_DAY);
+
+        let (year, month, day) = civil_from_days(days);
+        if !(0..=9999).contains(&year) {
+            return None;
+        }
+
+        Some(Self {
+            year: year as u16,
+            month,
+            day,
+            hour: (rest / 3_600_000) as u8,
+            minute: (rest / 60_000 % 60) as u8,
+            second: (rest / 1000 % 60) as u8,
+            millisecond: (rest % 1000) as u16,
+        })
+    }
+
+    /// Returns the milliseconds since the Unix epoch.
+    pub fn unix_millis(&self) -> i64 {
+        let days = days_from_civil(i64::from(self.year), self.month, self.day);
+        let seconds =
+            i64::from(self.hour) * 3600 + i64::from(self.minute) * 60 + i64::from(self.second);
+        days * MILLIS_PER_DAY + seconds * 1000 + i64::from(self.millisecond)
+    }
+
+    /// Returns the current time.
+    #[cfg(feature = "std")]
+    pub fn now() -> Self {
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64);
+        Self::from_unix_millis(millis).unwrap_or_default()
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn to_chrono(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp_millis(self.unix_millis())
+            .expect("years 0 to 9999 are within chrono's range")
+    }
+
+    /// Returns `None` outside of the years 0 to 9999.
+    #[cfg(feature = "chrono")]
+    pub fn from_chrono(time: &chrono::DateTime<chrono::Utc>) -> Option<Self> {
+        Self::from_unix_millis(time.timestamp_millis())
+    }
+
+    fn is_valid(&self) -> bool {
+        (1..=12).contains(&self.month)
+            && (1..=days_in_month(self.year, self.month)).contains(&self.day)
+            && self.hour < 24
+            && self.minute < 60
+            // Allows a leap second.
+            && self.second <= 60
+            && self.millisecond < 1000
+    }
+}
+
+impl Default for ServerTime {
+    /// The Unix epoch.
+    fn default() -> Self {
+        Self {
+            year: 1970,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            millisecond: 0,
+        }
+    }
+}
+
+impl Display for ServerTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, self.millisecond
+        )
+    }
+}
+
+impl<'a> FromMessage<'a> for ServerTime {
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        let value = msg
+            .tags()
+            .and_then(|tags| tags.get(TIME_TAG))
+            .ok_or_else(|| DeError::not_found_tag(TIME_TAG))?
+            .as_str();
+
+        Self::parse(value).ok_or_else(|| {
+            DeError::not_found_with_context(
+                "tag",
+                TIME_TAG,
+                format!("expected `YYYY-MM-DDThh:mm:ss.sssZ`, got `{value}`"),
+            )
+        })
+    }
+}
+
+impl ToMessage for ServerTime {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize
+            .tags()
+            .insert_tag(TIME_TAG, Some(&format!("{self}")))?;
+        Ok(())
+    }
+}
+
+fn number(digits: &str) -> Option<u16> {
+    if digits.bytes().all(|b| b.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Both conversions are from http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let time = ServerTime::parse("2024-01-01T12:00:00.000Z").unwrap();
+        assert_eq!(
+            ServerTime {
+                year: 2024,
+                month: 1,
+                day: 1,
+                hour: 12,
+                minute: 0,
+                second: 0,
+                millisecond: 0,
+            },
+            time
+        );
+        assert_eq!("2024-01-01T12:00:00.000Z", time.to_string());
+
+        let time = ServerTime::parse("2024-02-29T23:59:60.123456Z").unwrap();
+        assert_eq!(123, time.millisecond);
+        assert_eq!(60, time.second);
+        assert_eq!(
+            500,
+            ServerTime::parse("2024-01-01T00:00:00.5Z")
+                .unwrap()
+                .millisecond
+        );
+        assert_eq!(
+            0,
+            ServerTime::parse("2024-01-01T00:00:00Z")
+                .unwrap()
+                .millisecond
+        );
+
+        for invalid in [
+            "",
+            "2024-01-01T12:00:00.000",
+            "2024-01-01 12:00:00.000Z",
+            "2024-1-01T12:00:00.000Z",
+            "2023-02-29T12:00:00.000Z",
+            "2024-13-01T12:00:00.000Z",
+            "2024-01-01T24:00:00.000Z",
+            "2024-01-01T12:00:00.Z",
+            "2024-01-01T12:00:00,000Z",
+            "2024-01-01T12:00:+0.000Z",
+            "2024-01-01T12:00:00.000+01:00",
+            "2024-01-01T12:00:0éZ",
+            "2024-01-01T12:00:00.é0Z",
+            "2024-01-01T12:00:00.00éZ",
+        ] {
+            assert_eq!(None, ServerTime::parse(invalid), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn unix_millis() {
+        assert_eq!(0, ServerTime::default().unix_millis());
+        assert_eq!(Some(ServerTime::default()), ServerTime::from_unix_millis(0));
+
+        for value in [
+            "1969-12-31T23:59:59.999Z",
+            "2000-02-29T00:00:00.000Z",
+            "2024-01-01T12:00:00.000Z",
+            "0000-01-01T00:00:00.000Z",
+            "9999-12-31T23:59:59.999Z",
+        ] {
+            let time = ServerTime::parse(value).unwrap();
+            assert_eq!(Some(time), ServerTime::from_unix_millis(time.unix_millis()));
+        }
+
+        assert_eq!(
+            -1,
+            ServerTime::parse("1969-12-31T23:59:59.999Z")
+                .unwrap()
+                .unix_millis()
+        );
+        assert_eq!(
+            1_704_110_400_000,
+            ServerTime::parse("2024-01-01T12:00:00.000Z")
+                .unwrap()
+                .unix_millis()
+        );
+        assert_eq!(None, ServerTime::from_unix_millis(i64::MAX));
+
+        let earlier = ServerTime::parse("2024-01-01T12:00:00.000Z").unwrap();
+        let later = ServerTime::parse("2024-01-01T12:00:00.001Z").unwrap();
+        assert!(earlier < later);
+    }
+
+    #[test]
+    fn message() {
+        let msg = crate::parse("@time=2024-01-01T12:00:00.000Z PING :x").unwrap();
+        assert_eq!(
+            ServerTime::parse("2024-01-01T12:00:00.000Z"),
+            ServerTime::from_message(&msg).ok()
+        );
+
+        let msg = crate::parse("@time=2024-01-01T12:00:0éZ PING :x").unwrap();
+        assert_eq!(None, msg.tags().unwrap().server_time());
+
+        let msg = crate::parse("@time=yesterday PING :x").unwrap();
+        assert!(ServerTime::from_message(&msg)
+            .unwrap_err()
+            .is_not_found_tag());
+        let msg = crate::parse("PING :x").unwrap();
+        assert!(ServerTime::from_message(&msg)
+            .unwrap_err()
+            .is_not_found_tag());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono() {
+        let time = ServerTime::parse("2024-01-01T12:00:00.250Z").unwrap();
+        let chrono = time.to_chrono();
+        assert_eq!(1_704_110_400_250, chrono.timestamp_millis());
+        assert_eq!(Some(time), ServerTime::from_chrono(&chrono));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::compat::{Display, FmtResult, Formatter, Map, Split, String, Vec};
 
+use super::server_time::{ServerTime, TIME_TAG};
 use crate::{error::TagError, unescape, validators, EQ, SEMICOLON};
 
 type TagPair<'a> = (&'a str, TagValue<'a>);
@@ -142,6 +143,58 @@ impl<'a> Tags<'a> {
         self.get(key).map(|value| unescape(value.as_str()))
     }
 
+    /// Returns the `time` tag of `server-time`, see [`ServerTime::parse()`].
+    ///
+    /// ```rust
+    /// # use ircv3_parse::components::Tags;
+    /// let tags = Tags::new("time=2024-01-01T12:00:00.000Z;msgid=abc");
+    /// assert_eq!(1704110400000, tags.server_time().unwrap().unix_millis());
+    /// assert_eq!(Some("abc"), tags.msgid());
+    /// ```
+    pub fn server_time(&self) -> Option<ServerTime> {
+        self.get(TIME_TAG)
+            .and_then(|v| ServerTime::parse(v.as_str()))
+    }
+
+    /// Returns the `msgid` tag of `message-ids`.
+    pub fn msgid(&self) -> Option<&'a str> {
+        self.value("msgid")
+    }
+
+    /// Returns the `account` tag of `account-tag`, the sender's account name.
+    pub fn account(&self) -> Option<&'a str> {
+        self.value("account")
+    }
+
+    /// Returns the `label` tag of `labeled-response`.
+    pub fn label(&self) -> Option<&'a str> {
+        self.value("label")
+    }
+
+    /// Returns the reference of the batch the message belongs to.
+    pub fn batch(&self) -> Option<&'a str> {
+        self.value("batch")
+    }
+
+    /// Returns the `msgid` this message replies to, from `+reply` or
+    /// `+draft/reply`.
+    pub fn reply_to(&self) -> Option<&'a str> {
+        self.value("+reply").or_else(|| self.value("+draft/reply"))
+    }
+
+    /// Returns `true` if the sender is marked as a bot by `bot` or `draft/bot`.
+    pub fn bot(&self) -> bool {
+        self.contains("bot") || self.contains("draft/bot")
+    }
+
+    #[inline]
+    fn value(&self, key: &str) -> Option<&'a str> {
+        match self.get(key)? {
+            TagValue::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
     /// Returns an iterator over tag key-value pairs.
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = TagPair<'a>> {
@@ -0,0 +1,291 @@
+//! Client-to-Client Protocol messages.
+//!
+//! A CTCP message is a `PRIVMSG` (request) or `NOTICE` (reply) whose text is
+//! wrapped in `\x01`, e.g. `\x01ACTION waves\x01`. The closing `\x01` may be
+//! missing.
+//!
+//! ```rust
+//! use ircv3_parse::{ctcp::Ctcp, de::FromMessage};
+//!
+//! let ctcp = Ctcp::from_str(":nick!user@host PRIVMSG #rust :\x01ACTION waves\x01")?;
+//! assert!(ctcp.is_action());
+//! assert_eq!(Some("waves"), ctcp.params);
+//!
+//! let reply = Ctcp::new("VERSION", Some("ircv3_parse")).reply("nick")?.build()?;
+//! assert_eq!("NOTICE nick :\x01VERSION ircv3_parse\x01\r\n", reply);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{
+    compat::{format, String, ToString},
+    de::FromMessage,
+    ser::{MessageSerializer, ToMessage},
+    Commands, DeError, Message, MessageBuilder, SerError,
+};
+
+/// Delimiter around a CTCP message.
+pub const DELIM: char = '\x01';
+
+const QUOTE: char = '\\';
+
+/// A CTCP message in its on-the-wire form.
+///
+/// `params` is kept as sent, use [`Ctcp::text()`] to get the dequoted value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ctcp<'a> {
+    pub command: &'a str,
+    pub params: Option<&'a str>,
+}
+
+impl<'a> Ctcp<'a> {
+    #[inline]
+    pub fn new(command: &'a str, params: Option<&'a str>) -> Self {
+        Self { command, params }
+    }
+
+    /// `ACTION <text>`, i.e. `/me`.
+    #[inline]
+    pub fn action(text: &'a str) -> Self {
+        Self::new("ACTION", Some(text))
+    }
+
+    #[inline]
+    pub fn version() -> Self {
+        Self::new("VERSION", None)
+    }
+
+    #[inline]
+    pub fn ping(token: &'a str) -> Self {
+        Self::new("PING", Some(token))
+    }
+
+    #[inline]
+    pub fn time() -> Self {
+        Self::new("TIME", None)
+    }
+
+    /// Parses the text of a `PRIVMSG` or `NOTICE`.
+    ///
+    /// Returns `None` if `text` does not start with `\x01` or has no command.
+    pub fn parse(text: &'a str) -> Option<Self> {
+        let inner = text.strip_prefix(DELIM)?;
+        let inner = inner.strip_suffix(DELIM).unwrap_or(inner);
+
+        let (command, params) = match inner.split_once(' ') {
+            Some((command, params)) => (command, Some(params)),
+            None => (inner, None),
+        };
+
+        if command.is_empty() {
+            return None;
+        }
+
+        Some(Self { command, params })
+    }
+
+    /// Compares the command case-insensitively.
+    #[inline]
+    pub fn is(&self, command: &str) -> bool {
+        self.command.eq_ignore_ascii_case(command)
+    }
+
+    #[inline]
+    pub fn is_action(&self) -> bool {
+        self.is("ACTION")
+    }
+
+    #[inline]
+    pub fn is_dcc(&self) -> bool {
+        self.is("DCC")
+    }
+
+    /// Returns the parameters with extended-data quoting removed.
+    pub fn text(&self) -> Option<String> {
+        self.params.map(dequote)
+    }
+
+    /// Returns the space-separated parameters, e.g. of a `DCC` request.
+    pub fn args(&self) -> impl Iterator<Item = &'a str> {
+        self.params
+            .unwrap_or_default()
+            .split(' ')
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Returns the message text including both delimiters.
+    pub fn encode(&self) -> String {
+        match self.params {
+            Some(params) => format!("{DELIM}{} {params}{DELIM}", self.command),
+            None => format!("{DELIM}{}{DELIM}", self.command),
+        }
+    }
+
+    /// Builds a request, sent as `PRIVMSG`.
+    pub fn request(&self, target: &str) -> Result<MessageBuilder, SerError> {
+        self.build(Commands::PRIVMSG, target)
+    }
+
+    /// Builds a reply, sent as `NOTICE`.
+    pub fn reply(&self, target: &str) -> Result<MessageBuilder, SerError> {
+        self.build(Commands::NOTICE, target)
+    }
+
+    fn build(&self, command: Commands<'_>, target: &str) -> Result<MessageBuilder, SerError> {
+        let mut builder = MessageBuilder::new();
+        builder
+            .set_command(command)?
+            .add_param(target)?
+            .set_trailing(&self.encode())?;
+        Ok(builder)
+    }
+}
+
+impl<'a> FromMessage<'a> for Ctcp<'a> {
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        let command = msg.command();
+        if !command.is_privmsg() && !command.is_notice() {
+            return Err(DeError::command_mismatch(
+                "PRIVMSG or NOTICE",
+                command.as_str(),
+            ));
+        }
+
+        let params = msg.params();
+        let text = params
+            .count()
+            .checked_sub(1)
+            .and_then(|last| params.get(last))
+            .ok_or_else(DeError::not_found_trailing)?;
+
+        Self::parse(text).ok_or_else(|| {
+            DeError::not_found_with_context("trailing", text, "expected a CTCP message")
+        })
+    }
+}
+
+impl ToMessage for Ctcp<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_trailing(&self.encode())
+    }
+}
+
+/// Applies extended-data quoting: `\` becomes `\\` and `\x01` becomes `\a`.
+pub fn quote(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            QUOTE => result.push_str("\\\\"),
+            DELIM => result.push_str("\\a"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Reverses [`quote()`]; unknown escapes keep the escaped character.
+pub fn dequote(value: &str) -> String {
+    if !value.contains(QUOTE) {
+        return value.to_string();
+    }
+
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != QUOTE {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('a') => result.push(DELIM),
+            Some(other) => result.push(other),
+            None => result.push(QUOTE),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Some(Ctcp::new("ACTION", Some("waves hello"))),
+            Ctcp::parse("\x01ACTION waves hello\x01")
+        );
+        assert_eq!(Some(Ctcp::version()), Ctcp::parse("\x01VERSION\x01"));
+        assert_eq!(Some(Ctcp::action("")), Ctcp::parse("\x01ACTION \x01"));
+        assert_eq!(None, Ctcp::parse("ACTION waves"));
+        assert_eq!(None, Ctcp::parse("\x01\x01"));
+        assert_eq!(None, Ctcp::parse("\x01 text\x01"));
+    }
+
+    #[test]
+    fn missing_closing_delimiter() {
+        let ctcp = Ctcp::parse("\x01ACTION waves").unwrap();
+        assert!(ctcp.is_action());
+        assert_eq!(Some("waves"), ctcp.params);
+
+        assert_eq!(Some(Ctcp::version()), Ctcp::parse("\x01VERSION"));
+    }
+
+    #[test]
+    fn from_message() {
+        let ctcp = Ctcp::from_str(":a!b@c PRIVMSG me :\x01PING 1700000000\x01").unwrap();
+        assert!(ctcp.is("ping"));
+        assert_eq!(Some("1700000000"), ctcp.params);
+
+        let ctcp =
+            Ctcp::from_str(":a!b@c NOTICE me :\x01TIME Mon Jan 1 00:00:00 2024\x01").unwrap();
+        assert_eq!("TIME", ctcp.command);
+
+        // A single-word CTCP may be sent as a middle parameter.
+        let ctcp = Ctcp::from_str(":a!b@c PRIVMSG me \x01VERSION\x01").unwrap();
+        assert_eq!(Ctcp::version(), ctcp);
+
+        let dcc =
+            Ctcp::from_str(":a!b@c PRIVMSG me :\x01DCC SEND file.txt 3232235777 5000 1024\x01")
+                .unwrap();
+        assert!(dcc.is_dcc());
+        assert_eq!(
+            vec!["SEND", "file.txt", "3232235777", "5000", "1024"],
+            dcc.args().collect::<Vec<_>>()
+        );
+
+        let err = Ctcp::from_str(":a!b@c PRIVMSG #rust :hello").unwrap_err();
+        assert!(err.is_not_found_trailing());
+        let err = Ctcp::from_str(":a!b@c JOIN #rust").unwrap_err();
+        assert!(err.is_command_mismatch());
+    }
+
+    #[test]
+    fn build() {
+        let request = Ctcp::action("waves")
+            .request("#rust")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!("PRIVMSG #rust :\x01ACTION waves\x01\r\n", request);
+
+        let reply = Ctcp::ping("123").reply("nick").unwrap().build().unwrap();
+        assert_eq!("NOTICE nick :\x01PING 123\x01\r\n", reply);
+
+        let request = Ctcp::version().request("nick").unwrap().build().unwrap();
+        assert_eq!("PRIVMSG nick :\x01VERSION\x01\r\n", request);
+    }
+
+    #[test]
+    fn quoting() {
+        let raw = "a\\b\x01c";
+        let quoted = quote(raw);
+        assert_eq!("a\\\\b\\ac", quoted);
+        assert_eq!(raw, dequote(&quoted));
+
+        let ctcp = Ctcp::parse("\x01SED x\\ay\\\\z\\q\x01").unwrap();
+        assert_eq!(Some("x\x01y\\zq".to_string()), ctcp.text());
+        assert_eq!("plain", dequote("plain"));
+        assert_eq!("end\\", dequote("end\\"));
+    }
+}
@@ -50,6 +50,15 @@
 /// # }
 /// ```
 pub trait FromMessage<'a>: Sized {
+    /// The command this type parses, if it parses a single one.
+    ///
+    /// A command such as `"PRIVMSG"`, a numeric such as `"353"` or a numeric
+    /// name such as `"RPL_NAMREPLY"`, which is resolved with
+    /// [`Numeric::from_name()`](crate::components::Numeric::from_name()).
+    /// [`Dispatcher`](crate::dispatch::Dispatcher) uses it to skip handlers
+    /// that cannot match. The derive sets it from `#[irc(command = "...")]`.
+    const COMMAND: Option<&'static str> = None;
+
     fn from_str(s: &'a str) -> Result<Self, crate::DeError> {
         let msg = crate::parse(s)?;
         Self::from_message(&msg)
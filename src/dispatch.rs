@@ -0,0 +1,285 @@
+//! Routing incoming messages to typed handlers.
+//!
+//! Instead of matching on [`Message::command()`] and parsing by hand, register
+//! a handler per [`FromMessage`] type with [`Dispatcher::on()`]. A handler is
+//! only tried for messages with the type's [`FromMessage::COMMAND`], so most
+//! messages are never parsed for handlers that cannot match.
+//!
+//! ```rust
+//! use ircv3_parse::{dispatch::Dispatcher, messages::client::{Join, PrivMsg}};
+//!
+//! let mut texts = Vec::new();
+//! let mut joins = 0;
+//! let mut errors = 0;
+//!
+//! let mut dispatcher = Dispatcher::new();
+//! dispatcher
+//!     .on::<PrivMsg>(|msg| texts.push(msg.text.to_owned()))
+//!     .on::<Join>(|join| joins += join.channels.len())
+//!     .on_error(|_, _| errors += 1);
+//!
+//! let lines = [
+//!     ":ferris!f@host JOIN #rust",
+//!     ":ferris!f@host PRIVMSG #rust :hello",
+//!     ":ferris!f@host PRIVMSG #rust",
+//!     ":irc.example.com 353 ferris = #rust :ferris",
+//! ];
+//! for line in lines {
+//!     dispatcher.dispatch(&ircv3_parse::parse(line)?);
+//! }
+//! drop(dispatcher);
+//!
+//! assert_eq!(["hello"], texts.as_slice());
+//! assert_eq!(1, joins);
+//! // The PRIVMSG without text.
+//! assert_eq!(1, errors);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::compat::{Box, Debug, FmtResult, Formatter, Vec};
+
+use crate::{
+    cap::CapMessage,
+    components::{Numeric, ServerTime},
+    ctcp::Ctcp,
+    de::FromMessage,
+    messages::{
+        client::{
+            AnyClientCommand, Away, Invite, Join, Kick, List, Mode, Names, Nick, Notice, Oper,
+            Part, Pass, Ping, Pong, PrivMsg, Quit, Topic, User, Who, Whois,
+        },
+        replies::{
+            BanListEntry, ErrorReply, ListReply, NamReply, TopicReply, TopicWhoTime, WhoReply,
+            WhoisUser,
+        },
+    },
+    Commands, DeError, Message,
+};
+
+/// A [`FromMessage`] type for any message lifetime, so a [`Dispatcher`] can
+/// parse every message into it.
+///
+/// Implemented for the message types of this crate, and by the derive for
+/// types whose only generic parameter is a lifetime.
+pub trait Dispatchable {
+    /// The type borrowing from a message with lifetime `'a`.
+    type Target<'a>: FromMessage<'a>;
+}
+
+macro_rules! dispatchable {
+    ($($ty:ident),* $(,)?) => {
+        $(
+            impl<'x> Dispatchable for $ty<'x> {
+                type Target<'a> = $ty<'a>;
+            }
+        )*
+    };
+}
+
+dispatchable!(
+    Commands,
+    CapMessage,
+    Ctcp,
+    Nick,
+    User,
+    Pass,
+    Oper,
+    Join,
+    Part,
+    Kick,
+    Topic,
+    Invite,
+    Names,
+    List,
+    Who,
+    Whois,
+    Mode,
+    PrivMsg,
+    Notice,
+    Ping,
+    Pong,
+    Quit,
+    Away,
+    AnyClientCommand,
+    NamReply,
+    WhoReply,
+    TopicReply,
+    TopicWhoTime,
+    WhoisUser,
+    ListReply,
+    BanListEntry,
+    ErrorReply,
+);
+
+#[cfg(feature = "twitch")]
+mod twitch {
+    use super::Dispatchable;
+    use crate::twitch::{
+        ClearChat, ClearMsg, GlobalUserState, RoomState, TwitchPrivmsg, TwitchUserNotice,
+        UserState, Whisper,
+    };
+
+    dispatchable!(
+        TwitchPrivmsg,
+        TwitchUserNotice,
+        RoomState,
+        UserState,
+        GlobalUserState,
+        ClearChat,
+        ClearMsg,
+        Whisper,
+    );
+}
+
+impl Dispatchable for ServerTime {
+    type Target<'a> = ServerTime;
+}
+
+type Handler<'h> = Box<dyn FnMut(&Message<'_>) -> Result<(), DeError> + 'h>;
+
+type ErrorHook<'h> = Box<dyn FnMut(&Message<'_>, DeError) + 'h>;
+
+struct Route<'h> {
+    command: Option<Commands<'static>>,
+    handler: Handler<'h>,
+}
+
+/// Calls typed handlers for incoming messages.
+///
+/// Handlers may borrow for `'h`. They run in the order they were registered,
+/// and every handler whose type parses the message is called.
+#[derive(Default)]
+pub struct Dispatcher<'h> {
+    routes: Vec<Route<'h>>,
+    on_error: Option<ErrorHook<'h>>,
+}
+
+impl Debug for Dispatcher<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Dispatcher")
+            .field("handlers", &self.routes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'h> Dispatcher<'h> {
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            on_error: None,
+        }
+    }
+
+    /// Registers a handler for messages that parse as `T`.
+    ///
+    /// If `T` sets [`FromMessage::COMMAND`], the handler is only tried for
+    /// that command.
+    pub fn on<T: Dispatchable + 'h>(
+        &mut self,
+        mut handler: impl for<'a> FnMut(T::Target<'a>) + 'h,
+    ) -> &mut Self {
+        let command = <T::Target<'static> as FromMessage<'static>>::COMMAND.map(|command| {
+            Numeric::from_name(command).map_or_else(|| Commands::from(command), Commands::from)
+        });
+        let handler: Handler<'h> = Box::new(move |msg: &Message<'_>| {
+            handler(<T::Target<'_> as FromMessage<'_>>::from_message(msg)?);
+            Ok(())
+        });
+
+        self.routes.push(Route { command, handler });
+        self
+    }
+
+    /// Sets the hook called when a message of a handler's command fails to
+    /// parse as its type.
+    ///
+    /// Only handlers whose type sets [`FromMessage::COMMAND`] report errors.
+    /// Other handlers are tried for every message, so an error only means the
+    /// message was not meant for them.
+    pub fn on_error(&mut self, hook: impl FnMut(&Message<'_>, DeError) + 'h) -> &mut Self {
+        self.on_error = Some(Box::new(hook));
+        self
+    }
+
+    /// Passes `msg` to every matching handler.
+    ///
+    /// Returns the number of handlers called.
+    pub fn dispatch(&mut self, msg: &Message<'_>) -> usize {
+        let command = msg.command();
+        let mut called = 0;
+
+        for route in &mut self.routes {
+            if route.command.is_some_and(|expected| expected != command) {
+                continue;
+            }
+
+            match (route.handler)(msg) {
+                Ok(()) => called += 1,
+                Err(err) => {
+                    if let (Some(_), Some(hook)) = (route.command, &mut self.on_error) {
+                        hook(msg, err);
+                    }
+                }
+            }
+        }
+        called
+    }
+
+    /// Number of registered handlers.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::compat::{String, ToOwned};
+
+    #[test]
+    fn prefilters_by_command() {
+        let seen: RefCell<Vec<String>> = RefCell::new(Vec::new());
+        let mut errors = Vec::new();
+        let push = |s: &str| seen.borrow_mut().push(s.to_owned());
+
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .on::<NamReply>(|reply| push(reply.channel))
+            .on::<AnyClientCommand>(|cmd| push(cmd.command().as_str()))
+            .on::<Kick>(|kick| push(kick.user))
+            .on_error(|msg, err| errors.push((msg.command().as_str().to_owned(), err)));
+        assert_eq!(3, dispatcher.len());
+
+        let mut dispatch = |line: &str| dispatcher.dispatch(&crate::parse(line).unwrap());
+        assert_eq!(1, dispatch(":irc.example.com 353 ferris = #rust :ferris"));
+        assert_eq!(2, dispatch("kick #rust bob"));
+        // Only AnyClientCommand is tried, and rejects the command.
+        assert_eq!(0, dispatch(":irc.example.com 366 ferris #rust :End"));
+        assert_eq!(0, dispatch("KICK #rust"));
+        drop(dispatcher);
+
+        assert_eq!(["#rust", "KICK", "bob"], seen.borrow().as_slice());
+        // Reported once, by the Kick handler.
+        assert_eq!(1, errors.len());
+        assert_eq!("KICK", errors[0].0);
+        assert!(errors[0].1.is_not_found_param());
+    }
+
+    #[test]
+    fn command_consts() {
+        assert_eq!(Some("PRIVMSG"), PrivMsg::COMMAND);
+        assert_eq!(Some("353"), NamReply::COMMAND);
+        assert_eq!(Some("CAP"), CapMessage::COMMAND);
+        assert_eq!(None, AnyClientCommand::COMMAND);
+        assert_eq!(
+            Commands::from(Numeric::RPL_NAMREPLY),
+            Commands::from(NamReply::COMMAND.unwrap())
+        );
+    }
+}
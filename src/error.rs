@@ -1,4 +1,4 @@
-use crate::compat::{format, Debug, FmtResult, Formatter, String, ToString};
+use crate::compat::{format, Debug, FmtResult, Formatter, String, ToString, Vec};
 
 #[derive(Clone, PartialEq, thiserror::Error)]
 pub enum IRCError {
@@ -56,6 +56,8 @@ pub enum SerError {
     MissingCommand,
     #[error("command already set")]
     DuplicateCommand,
+    #[error("line exceeds maximum length (max {max} bytes, got {actual})")]
+    LineTooLong { max: usize, actual: usize },
 
     #[error(transparent)]
     Tag(#[from] TagError),
@@ -75,6 +77,7 @@ impl SerError {
     pub fn code(&self) -> &'static str {
         match self {
             Self::MissingCommand | Self::DuplicateCommand => "COMMAND",
+            Self::LineTooLong { .. } => "LINE",
             Self::Tag(tag) => tag.code(),
             Self::Source(src) => src.code(),
             Self::Param(param) => param.code(),
@@ -89,6 +92,10 @@ impl SerError {
         matches!(self, Self::DuplicateCommand)
     }
 
+    pub fn is_line_too_long(&self) -> bool {
+        matches!(self, Self::LineTooLong { .. })
+    }
+
     pub(crate) fn missing_nick() -> Self {
         Self::Source(SourceError::MissingNick)
     }
@@ -404,3 +411,274 @@ impl DeError {
         )
     }
 }
+
+#[derive(Clone, PartialEq, thiserror::Error)]
+pub enum CapError {
+    #[error(transparent)]
+    Ser(#[from] SerError),
+    #[error(transparent)]
+    De(#[from] DeError),
+}
+
+impl Debug for CapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "IRC-CAP[{}]: {}", self.code(), self)
+    }
+}
+
+impl CapError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Ser(e) => e.code(),
+            Self::De(e) => e.code(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, thiserror::Error)]
+pub enum SaslError {
+    #[error("invalid base64 character '{char}' at position {position}")]
+    InvalidBase64Char { char: char, position: usize },
+    #[error("base64 length must be a multiple of 4, got {actual}")]
+    InvalidBase64Length { actual: usize },
+
+    #[error("unexpected challenge for {mechanism}: {reason}")]
+    InvalidChallenge {
+        mechanism: &'static str,
+        reason: &'static str,
+    },
+    #[error("server rejected authentication: {0}")]
+    ServerError(String),
+    #[error("server signature does not match")]
+    SignatureMismatch,
+
+    #[error(transparent)]
+    De(#[from] DeError),
+}
+
+impl Debug for SaslError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "IRC-SASL[{}]: {}", self.code(), self)
+    }
+}
+
+impl SaslError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidBase64Char { .. } | Self::InvalidBase64Length { .. } => "BASE64",
+            Self::InvalidChallenge { .. } => "CHALLENGE",
+            Self::ServerError(..) | Self::SignatureMismatch => "SCRAM",
+            Self::De(e) => e.code(),
+        }
+    }
+
+    pub fn is_invalid_base64(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidBase64Char { .. } | Self::InvalidBase64Length { .. }
+        )
+    }
+
+    pub fn is_invalid_challenge(&self) -> bool {
+        matches!(self, Self::InvalidChallenge { .. })
+    }
+
+    pub fn is_signature_mismatch(&self) -> bool {
+        matches!(self, Self::SignatureMismatch)
+    }
+
+    pub(crate) fn invalid_challenge(mechanism: &'static str, reason: &'static str) -> Self {
+        Self::InvalidChallenge { mechanism, reason }
+    }
+}
+
+#[derive(Clone, PartialEq, thiserror::Error)]
+pub enum MultilineError {
+    #[error("multiline content exceeds max-bytes (max {max}, got {actual})")]
+    TooManyBytes { max: usize, actual: usize },
+    #[error("multiline batch exceeds max-lines (max {max}, got {actual})")]
+    TooManyLines { max: usize, actual: usize },
+
+    #[error(transparent)]
+    Ser(#[from] SerError),
+}
+
+impl Debug for MultilineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "IRC-MULTILINE[{}]: {}", self.code(), self)
+    }
+}
+
+impl MultilineError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::TooManyBytes { .. } => "BYTES",
+            Self::TooManyLines { .. } => "LINES",
+            Self::Ser(e) => e.code(),
+        }
+    }
+
+    pub fn is_too_many_bytes(&self) -> bool {
+        matches!(self, Self::TooManyBytes { .. })
+    }
+
+    pub fn is_too_many_lines(&self) -> bool {
+        matches!(self, Self::TooManyLines { .. })
+    }
+}
+
+#[derive(Clone, PartialEq, thiserror::Error)]
+pub enum BatchError {
+    #[error("batch '{reference}' is not open")]
+    UnknownBatch { reference: String },
+    #[error("batch '{reference}' is already open")]
+    DuplicateBatch { reference: String },
+    #[error("batches {references:?} were still open when their parent closed")]
+    Abandoned { references: Vec<String> },
+
+    #[error(transparent)]
+    Parse(#[from] IRCError),
+    #[error(transparent)]
+    De(#[from] DeError),
+}
+
+impl Debug for BatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "IRC-BATCH[{}]: {}", self.code(), self)
+    }
+}
+
+impl BatchError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnknownBatch { .. } => "UNKNOWN",
+            Self::DuplicateBatch { .. } => "DUPLICATE",
+            Self::Abandoned { .. } => "ABANDONED",
+            Self::Parse(e) => e.code(),
+            Self::De(e) => e.code(),
+        }
+    }
+
+    pub fn is_unknown_batch(&self) -> bool {
+        matches!(self, Self::UnknownBatch { .. })
+    }
+
+    pub fn is_duplicate_batch(&self) -> bool {
+        matches!(self, Self::DuplicateBatch { .. })
+    }
+
+    pub fn is_abandoned(&self) -> bool {
+        matches!(self, Self::Abandoned { .. })
+    }
+}
+
+#[derive(Clone, PartialEq, thiserror::Error)]
+pub enum SessionError {
+    #[error("nickname '{nick}' is unavailable and no alternative is left")]
+    NickUnavailable { nick: String },
+
+    #[error(transparent)]
+    Cap(#[from] CapError),
+    #[error(transparent)]
+    Sasl(#[from] SaslError),
+    #[error(transparent)]
+    Ser(#[from] SerError),
+    #[error(transparent)]
+    De(#[from] DeError),
+}
+
+impl Debug for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "IRC-SESSION[{}]: {}", self.code(), self)
+    }
+}
+
+impl SessionError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NickUnavailable { .. } => "NICK",
+            Self::Cap(e) => e.code(),
+            Self::Sasl(e) => e.code(),
+            Self::Ser(e) => e.code(),
+            Self::De(e) => e.code(),
+        }
+    }
+
+    pub fn is_nick_unavailable(&self) -> bool {
+        matches!(self, Self::NickUnavailable { .. })
+    }
+}
+
+#[derive(Clone, PartialEq, thiserror::Error)]
+pub enum FilterError {
+    #[error("unexpected character '{char}' at position {position}")]
+    UnexpectedChar { char: char, position: usize },
+    #[error("unterminated string starting at position {position}")]
+    UnterminatedString { position: usize },
+    #[error("expected {expected} at position {position}")]
+    Expected {
+        expected: &'static str,
+        position: usize,
+    },
+    #[error("unknown field '{name}' at position {position}")]
+    UnknownField { name: String, position: usize },
+    #[error("invalid parameter index '{input}' at position {position}")]
+    InvalidIndex { input: String, position: usize },
+    #[error("expression nested deeper than {max} levels at position {position}")]
+    TooDeep { max: usize, position: usize },
+}
+
+impl Debug for FilterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "IRC-FILTER[{}]: {}", self.code(), self)
+    }
+}
+
+impl FilterError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnexpectedChar { .. } => "CHAR",
+            Self::UnterminatedString { .. } => "STRING",
+            Self::Expected { .. } => "EXPECTED",
+            Self::UnknownField { .. } => "FIELD",
+            Self::InvalidIndex { .. } => "INDEX",
+            Self::TooDeep { .. } => "DEPTH",
+        }
+    }
+
+    /// Byte offset in the filter source where the error was found.
+    pub fn position(&self) -> usize {
+        match self {
+            Self::UnexpectedChar { position, .. }
+            | Self::UnterminatedString { position }
+            | Self::Expected { position, .. }
+            | Self::UnknownField { position, .. }
+            | Self::InvalidIndex { position, .. }
+            | Self::TooDeep { position, .. } => *position,
+        }
+    }
+
+    pub fn is_unexpected_char(&self) -> bool {
+        matches!(self, Self::UnexpectedChar { .. })
+    }
+
+    pub fn is_unterminated_string(&self) -> bool {
+        matches!(self, Self::UnterminatedString { .. })
+    }
+
+    pub fn is_expected(&self) -> bool {
+        matches!(self, Self::Expected { .. })
+    }
+
+    pub fn is_unknown_field(&self) -> bool {
+        matches!(self, Self::UnknownField { .. })
+    }
+
+    pub fn is_invalid_index(&self) -> bool {
+        matches!(self, Self::InvalidIndex { .. })
+    }
+
+    pub fn is_too_deep(&self) -> bool {
+        matches!(self, Self::TooDeep { .. })
+    }
+}
@@ -0,0 +1,578 @@
+//! Message filters written as text.
+//!
+//! A [`Filter`] is parsed once from an expression and then tested against
+//! any number of messages, e.g. to pick what a log or a moderation bot sees:
+//!
+//! ```rust
+//! use ircv3_parse::filter::Filter;
+//!
+//! let filter = Filter::parse(
+//!     r##"command == PRIVMSG && tag(+draft/react) && source ~ "*!*@*.bot.example" && param(0) == "#ops""##,
+//! )?;
+//!
+//! let msg = ircv3_parse::parse("@+draft/react=👍 :bot!b@x.bot.example PRIVMSG #ops :hi")?;
+//! assert!(filter.matches(&msg));
+//!
+//! let msg = ircv3_parse::parse("@+draft/react=👍 :bot!b@x.bot.example PRIVMSG #rust :hi")?;
+//! assert!(!filter.matches(&msg));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! # Syntax
+//!
+//! Fields:
+//! - `command` - the command; `==` also accepts numeric names like `RPL_WELCOME`
+//! - `tag(key)` - the unescaped value of a tag, present if the tag is set at all
+//! - `source` - the whole source, `nick!user@host`
+//! - `param(n)` - the parameter at index `n`, counting the trailing one last
+//! - `trailing` - the trailing parameter, sent after `:`
+//!
+//! A field on its own tests that it is present. Otherwise it is compared with
+//! `==`, `!=`, `~` (a glob where `*` matches any run of characters and `?`
+//! matches one) or `contains`. Comparisons of `command` and `source` ignore
+//! ASCII case. Values are either bare words or double-quoted strings, in
+//! which `\"` and `\\` are escapes.
+//!
+//! Tests combine with `!`, `&&` and `||`, binding in that order, and group
+//! with parentheses, nested at most [`MAX_DEPTH`] deep.
+
+use crate::compat::{Box, String, ToOwned, ToString, Vec};
+
+use crate::{components::Numeric, error::FilterError, Commands, Message};
+
+/// How deeply `!` and parentheses may nest.
+pub const MAX_DEPTH: usize = 64;
+
+/// A compiled filter expression.
+///
+/// See the [module documentation](self) for the syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parses a filter expression.
+    ///
+    /// ```rust
+    /// use ircv3_parse::filter::Filter;
+    ///
+    /// let err = Filter::parse("command == PRIVMSG && param(x)").unwrap_err();
+    /// assert!(err.is_invalid_index());
+    /// assert_eq!(28, err.position());
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens,
+            next: 0,
+            depth: 0,
+        };
+
+        let expr = parser.or()?;
+        let (token, position) = parser.peek();
+        if *token != Token::End {
+            return Err(FilterError::Expected {
+                expected: "'&&' or '||'",
+                position: *position,
+            });
+        }
+        Ok(Self { expr })
+    }
+
+    /// Returns `true` if `msg` passes the filter.
+    pub fn matches(&self, msg: &Message<'_>) -> bool {
+        self.expr.matches(msg)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Test(Field, Op),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    Command,
+    Tag(String),
+    Source,
+    Param(usize),
+    Trailing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Op {
+    Present,
+    Equals(String),
+    Glob(String),
+    Contains(String),
+}
+
+impl Expr {
+    fn matches(&self, msg: &Message<'_>) -> bool {
+        match self {
+            Self::Test(field, op) => field.test(op, msg),
+            Self::Not(expr) => !expr.matches(msg),
+            Self::And(exprs) => exprs.iter().all(|expr| expr.matches(msg)),
+            Self::Or(exprs) => exprs.iter().any(|expr| expr.matches(msg)),
+        }
+    }
+}
+
+impl Field {
+    fn test(&self, op: &Op, msg: &Message<'_>) -> bool {
+        match self {
+            Self::Command => {
+                let command = msg.command();
+                match op {
+                    Op::Equals(value) => Commands::from(value.as_str()) == command,
+                    _ => op.test(command.as_str(), true),
+                }
+            }
+            Self::Tag(key) => {
+                let Some(tags) = msg.tags() else {
+                    return false;
+                };
+                match op {
+                    Op::Present => tags.get(key).is_some(),
+                    _ => tags.get_unescaped(key).is_some_and(|v| op.test(&v, false)),
+                }
+            }
+            Self::Source => msg.source().is_some_and(|s| op.test(s.as_str(), true)),
+            Self::Param(index) => msg.params().get(*index).is_some_and(|p| op.test(p, false)),
+            Self::Trailing => msg
+                .params()
+                .trailing
+                .raw()
+                .is_some_and(|t| op.test(t, false)),
+        }
+    }
+}
+
+impl Op {
+    fn test(&self, value: &str, ignore_case: bool) -> bool {
+        match self {
+            Self::Present => true,
+            Self::Equals(expected) if ignore_case => expected.eq_ignore_ascii_case(value),
+            Self::Equals(expected) => expected == value,
+            Self::Glob(pattern) => glob(pattern, value, ignore_case),
+            Self::Contains(needle) if ignore_case => {
+                needle.is_empty()
+                    || value
+                        .as_bytes()
+                        .windows(needle.len())
+                        .any(|w| w.eq_ignore_ascii_case(needle.as_bytes()))
+            }
+            Self::Contains(needle) => value.contains(needle.as_str()),
+        }
+    }
+}
+
+/// Matches `text` against a pattern where `*` is any run of characters and
+/// `?` is exactly one.
+fn glob(pattern: &str, text: &str, ignore_case: bool) -> bool {
+    let next = |s: &str, at: usize| s[at..].chars().next();
+    let eq = |a: char, b: char| a == b || (ignore_case && a.eq_ignore_ascii_case(&b));
+
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*`: in the pattern, and in the text.
+    let mut star = None;
+
+    while let Some(c) = next(text, t) {
+        match next(pattern, p) {
+            Some('*') => {
+                p += 1;
+                star = Some((p, t));
+            }
+            Some(pc) if pc == '?' || eq(pc, c) => {
+                p += pc.len_utf8();
+                t += c.len_utf8();
+            }
+            _ => {
+                // Let the last `*` swallow one more character.
+                let Some((star_p, star_t)) = star else {
+                    return false;
+                };
+                let skipped = next(text, star_t).map_or(0, char::len_utf8);
+                p = star_p;
+                t = star_t + skipped;
+                star = Some((p, t));
+            }
+        }
+    }
+
+    pattern[p..].chars().all(|c| c == '*')
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Str(String),
+    LParen,
+    RParen,
+    Not,
+    And,
+    Or,
+    Eq,
+    Ne,
+    Glob,
+    End,
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | '"' | '!' | '=' | '~' | '&' | '|')
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token<'_>, usize)>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        let mut pair =
+            |second: char, token: Token<'static>| match chars.next_if(|&(_, n)| n == second) {
+                Some(_) => Ok(token),
+                None => Err(FilterError::UnexpectedChar { char: c, position }),
+            };
+
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '~' => Token::Glob,
+            '&' => pair('&', Token::And)?,
+            '|' => pair('|', Token::Or)?,
+            '=' => pair('=', Token::Eq)?,
+            '!' => match chars.next_if(|&(_, n)| n == '=') {
+                Some(_) => Token::Ne,
+                None => Token::Not,
+            },
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped @ ('"' | '\\'))) => value.push(escaped),
+                            Some((at, other)) => {
+                                return Err(FilterError::UnexpectedChar {
+                                    char: other,
+                                    position: at,
+                                })
+                            }
+                            None => return Err(FilterError::UnterminatedString { position }),
+                        },
+                        Some((_, other)) => value.push(other),
+                        None => return Err(FilterError::UnterminatedString { position }),
+                    }
+                }
+                Token::Str(value)
+            }
+            _ => {
+                let mut end = position + c.len_utf8();
+                while let Some((at, n)) = chars.next_if(|&(_, n)| is_word_char(n)) {
+                    end = at + n.len_utf8();
+                }
+                Token::Word(&input[position..end])
+            }
+        };
+        tokens.push((token, position));
+    }
+
+    tokens.push((Token::End, input.len()));
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token<'a>, usize)>,
+    next: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> &(Token<'a>, usize) {
+        // `End` is always last and never consumed.
+        &self.tokens[self.next]
+    }
+
+    fn bump(&mut self) -> (Token<'a>, usize) {
+        let token = self.tokens[self.next].clone();
+        if token.0 != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, token: Token<'_>) -> bool {
+        let found = self.peek().0 == token;
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: Token<'_>, expected: &'static str) -> Result<(), FilterError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.expected(expected))
+        }
+    }
+
+    fn expected(&self, expected: &'static str) -> FilterError {
+        FilterError::Expected {
+            expected,
+            position: self.peek().1,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut exprs = Vec::from([self.and()?]);
+        while self.eat(Token::Or) {
+            exprs.push(self.and()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.pop().unwrap()
+        } else {
+            Expr::Or(exprs)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut exprs = Vec::from([self.unary()?]);
+        while self.eat(Token::And) {
+            exprs.push(self.unary()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.pop().unwrap()
+        } else {
+            Expr::And(exprs)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        let (token, position) = self.peek();
+        let (not, position) = (*token == Token::Not, *position);
+        if !not && *token != Token::LParen {
+            return self.test();
+        }
+
+        if self.depth == MAX_DEPTH {
+            return Err(FilterError::TooDeep {
+                max: MAX_DEPTH,
+                position,
+            });
+        }
+        self.depth += 1;
+        self.next += 1;
+        let expr = if not {
+            self.unary().map(|expr| Expr::Not(Box::new(expr)))
+        } else {
+            self.or()
+                .and_then(|expr| self.expect(Token::RParen, "')'").map(|_| expr))
+        };
+        self.depth -= 1;
+        expr
+    }
+
+    fn test(&mut self) -> Result<Expr, FilterError> {
+        let Token::Word(name) = self.peek().0 else {
+            return Err(self.expected("field"));
+        };
+        let (_, position) = self.bump();
+
+        let field = match name {
+            "command" => Field::Command,
+            "source" => Field::Source,
+            "trailing" => Field::Trailing,
+            "tag" => Field::Tag(self.argument("tag key")?.to_owned()),
+            "param" => {
+                let position = self.tokens.get(self.next + 1).map_or(0, |(_, at)| *at);
+                let input = self.argument("parameter index")?;
+                Field::Param(input.parse().map_err(|_| FilterError::InvalidIndex {
+                    input: input.to_owned(),
+                    position,
+                })?)
+            }
+            _ => {
+                return Err(FilterError::UnknownField {
+                    name: name.to_owned(),
+                    position,
+                })
+            }
+        };
+
+        let (negate, mut op) = match self.peek().0 {
+            Token::Eq => (false, Op::Equals(self.value()?)),
+            Token::Ne => (true, Op::Equals(self.value()?)),
+            Token::Glob => (false, Op::Glob(self.value()?)),
+            Token::Word("contains") => (false, Op::Contains(self.value()?)),
+            _ if field == Field::Command => return Err(self.expected("comparison")),
+            _ => (false, Op::Present),
+        };
+
+        // Numeric names compare as their code, e.g. `RPL_WELCOME` as `001`.
+        if let (Field::Command, Op::Equals(value)) = (&field, &mut op) {
+            if let Some(numeric) = Numeric::from_name(value) {
+                *value = numeric.code_str().to_owned();
+            }
+        }
+
+        let test = Expr::Test(field, op);
+        Ok(if negate {
+            Expr::Not(Box::new(test))
+        } else {
+            test
+        })
+    }
+
+    /// Parses `(word)` after a field name.
+    fn argument(&mut self, expected: &'static str) -> Result<&'a str, FilterError> {
+        self.expect(Token::LParen, "'('")?;
+        let Token::Word(word) = self.peek().0 else {
+            return Err(self.expected(expected));
+        };
+        self.bump();
+        self.expect(Token::RParen, "')'")?;
+        Ok(word)
+    }
+
+    /// Skips the operator and parses the value after it.
+    fn value(&mut self) -> Result<String, FilterError> {
+        self.bump();
+        match self.bump() {
+            (Token::Word(word), _) => Ok(word.to_string()),
+            (Token::Str(value), _) => Ok(value),
+            (_, position) => Err(FilterError::Expected {
+                expected: "value",
+                position,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(filter: &str, line: &str) -> bool {
+        Filter::parse(filter)
+            .unwrap()
+            .matches(&crate::parse(line).unwrap())
+    }
+
+    #[test]
+    fn fields() {
+        let line = "@+draft/react=\\s;msgid=abc :Nick!u@Host.example PRIVMSG #ops :hello world";
+
+        assert!(matches("command == privmsg", line));
+        assert!(matches("command ~ PRIV*", line));
+        assert!(!matches("command != PRIVMSG", line));
+        assert!(matches(
+            "command == RPL_WELCOME",
+            ":irc.example.com 001 nick :Welcome"
+        ));
+
+        assert!(matches("tag(msgid)", line));
+        assert!(!matches("tag(msg)", line));
+        assert!(matches(r#"tag(+draft/react) == " ""#, line));
+        assert!(!matches("tag(msgid)", "PING x"));
+
+        assert!(matches("source", line));
+        assert!(matches(r#"source ~ "nick!*@*.EXAMPLE""#, line));
+        assert!(matches(r#"source == "nick!u@host.example""#, line));
+        assert!(!matches("source", "PING x"));
+
+        assert!(matches(r##"param(0) == "#ops""##, line));
+        assert!(matches("param(1) contains world", line));
+        assert!(!matches("param(2)", line));
+
+        assert!(matches("trailing ~ h?llo*", line));
+        assert!(!matches("trailing contains World", line));
+        assert!(!matches("trailing", "NICK alice"));
+    }
+
+    #[test]
+    fn precedence() {
+        let line = ":n!u@h PRIVMSG #rust :hi";
+
+        assert!(matches(
+            "command == JOIN || command == PRIVMSG && param(0) == #rust",
+            line
+        ));
+        assert!(!matches(
+            "(command == JOIN || command == PRIVMSG) && param(0) == #ops",
+            line
+        ));
+        assert!(matches("!tag(msgid) && !(source ~ *@x)", line));
+        assert!(!matches("!!trailing contains bye", line));
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob("*", "", false));
+        assert!(glob("a*b*c", "aXbYbZc", false));
+        assert!(!glob("a*b*c", "aXbYbZ", false));
+        assert!(glob("?é*", "aé", false));
+        assert!(glob("**x", "abx", false));
+        assert!(!glob("ab", "abc", false));
+        assert!(glob("AB*", "abc", true));
+    }
+
+    #[test]
+    fn errors() {
+        let err = |filter: &str| Filter::parse(filter).unwrap_err();
+
+        let e = err("command = PRIVMSG");
+        assert!(e.is_unexpected_char());
+        assert_eq!(8, e.position());
+
+        let e = err(r#"trailing ~ "abc"#);
+        assert!(e.is_unterminated_string());
+        assert_eq!(11, e.position());
+
+        let e = err("nick == ferris");
+        assert!(e.is_unknown_field());
+        assert_eq!(0, e.position());
+
+        let nested = format!("{}trailing{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert!(Filter::parse(&nested).is_ok());
+        let e = err(&format!("!{nested}"));
+        assert!(e.is_too_deep());
+        assert_eq!(MAX_DEPTH, e.position());
+        assert!(err(&"!".repeat(100_000)).is_too_deep());
+        assert!(err(&"(".repeat(100_000)).is_too_deep());
+
+        assert_eq!(
+            FilterError::Expected {
+                expected: "comparison",
+                position: 8
+            },
+            err("command && source")
+        );
+        assert_eq!(
+            FilterError::Expected {
+                expected: "')'",
+                position: 10
+            },
+            err("(trailing source")
+        );
+        assert_eq!(
+            FilterError::Expected {
+                expected: "value",
+                position: 12
+            },
+            err("trailing == ")
+        );
+        assert_eq!(
+            FilterError::Expected {
+                expected: "field",
+                position: 0
+            },
+            err("")
+        );
+        assert_eq!("EXPECTED", err("tag()").code());
+    }
+}
@@ -0,0 +1,429 @@
+//! Outgoing flood control.
+//!
+//! Servers disconnect clients that send too fast. [`FloodQueue`] holds
+//! outgoing messages by [`Priority`] and releases them according to a
+//! [`RateLimit`]. Messages are weighed by their serialized length, so long
+//! lines count for more. It does not do any I/O and never reads a clock
+//! itself: the caller passes the current time as a [`Duration`] since any
+//! fixed point.
+//!
+//! ```rust
+//! use core::time::Duration;
+//! use ircv3_parse::{
+//!     flood::{FloodQueue, Priority, RateLimit},
+//!     messages::client::{PrivMsg, Pong},
+//! };
+//!
+//! let mut queue = FloodQueue::new(RateLimit::twitch());
+//! for i in 0..25 {
+//!     let text = format!("message {i}");
+//!     queue.push(&PrivMsg { target: "#rust", text: &text }, Priority::Normal)?;
+//! }
+//! queue.push(&Pong { server: None, token: "tmi.twitch.tv" }, Priority::High)?;
+//!
+//! let now = Duration::ZERO;
+//! assert_eq!("PONG tmi.twitch.tv\r\n", queue.poll_transmit(now).unwrap());
+//! assert_eq!(19, std::iter::from_fn(|| queue.poll_transmit(now)).count());
+//!
+//! // The rest waits until the first messages leave the 30 second window.
+//! assert_eq!(6, queue.len());
+//! assert_eq!(Some(Duration::from_secs(30)), queue.next_deadline());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use core::time::Duration;
+
+use bytes::Bytes;
+
+use crate::compat::VecDeque;
+
+use crate::{
+    ser::{IRCSerializer, ToMessage},
+    SerError,
+};
+
+/// Order in which queued messages are sent. Messages of the same priority
+/// keep their order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    /// e.g. `PONG` and `QUIT`, sent before anything else.
+    High,
+}
+
+/// How fast a [`FloodQueue`] may send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimit {
+    /// A bucket of `burst` tokens, refilled by one every `refill`.
+    ///
+    /// A message costs one token, plus one for every `bytes_per_token` bytes
+    /// if set.
+    TokenBucket {
+        burst: u32,
+        refill: Duration,
+        bytes_per_token: Option<usize>,
+    },
+    /// The ircd penalty model from RFC 1459 section 8.10.
+    ///
+    /// Every message adds `base`, plus `extra` for every `per_bytes` bytes, to
+    /// a timer that never falls behind the current time. Messages are sent
+    /// while the timer is at most `max_ahead` in the future.
+    Penalty {
+        base: Duration,
+        extra: Duration,
+        per_bytes: usize,
+        max_ahead: Duration,
+    },
+    /// At most `max` messages within any `window`, regardless of their length.
+    Window { max: u32, window: Duration },
+}
+
+impl RateLimit {
+    /// Common ircd settings: 2 seconds per message plus 1 second per 120
+    /// bytes, at most 10 seconds ahead.
+    pub const fn ircd() -> Self {
+        Self::Penalty {
+            base: Duration::from_secs(2),
+            extra: Duration::from_secs(1),
+            per_bytes: 120,
+            max_ahead: Duration::from_secs(10),
+        }
+    }
+
+    /// Twitch chat as a regular user: 20 messages per 30 seconds.
+    pub const fn twitch() -> Self {
+        Self::Window {
+            max: 20,
+            window: Duration::from_secs(30),
+        }
+    }
+
+    /// Twitch chat as a moderator, VIP or broadcaster of every channel we
+    /// talk in: 100 messages per 30 seconds.
+    pub const fn twitch_moderator() -> Self {
+        Self::Window {
+            max: 100,
+            window: Duration::from_secs(30),
+        }
+    }
+
+    /// Twitch chat as a verified bot: 7500 messages per 30 seconds.
+    pub const fn twitch_verified() -> Self {
+        Self::Window {
+            max: 7500,
+            window: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Sans-IO outgoing queue.
+///
+/// [`FloodQueue::push()`] messages, send whatever
+/// [`FloodQueue::poll_transmit()`] returns and poll again at
+/// [`FloodQueue::next_deadline()`].
+#[derive(Debug, Clone)]
+pub struct FloodQueue {
+    limit: RateLimit,
+    /// Indexed by [`Priority`].
+    queues: [VecDeque<Bytes>; 3],
+    /// Time the bucket is full again, or the penalty timer.
+    timer: Duration,
+    /// Send times within the last window, oldest first.
+    sent: VecDeque<Duration>,
+}
+
+impl FloodQueue {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            queues: Default::default(),
+            timer: Duration::ZERO,
+            sent: VecDeque::new(),
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Changes the limit, e.g. after becoming a moderator. Queued messages
+    /// are kept.
+    pub fn set_limit(&mut self, limit: RateLimit) {
+        self.limit = limit;
+    }
+
+    /// Serializes and queues a message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SerError`] if the message cannot be serialized.
+    pub fn push<T: ToMessage>(&mut self, msg: &T, priority: Priority) -> Result<(), SerError> {
+        let mut serializer = IRCSerializer::new();
+        msg.to_message(&mut serializer)?;
+        self.push_bytes(serializer.into_bytes(), priority);
+        Ok(())
+    }
+
+    /// Queues an already serialized line, including its `\r\n`.
+    pub fn push_bytes(&mut self, line: Bytes, priority: Priority) {
+        self.queues[priority as usize].push_back(line);
+    }
+
+    /// Returns the next message if it may be sent at `now`.
+    pub fn poll_transmit(&mut self, now: Duration) -> Option<Bytes> {
+        let index = self.queues.iter().rposition(|q| !q.is_empty())?;
+        let len = self.queues[index].front()?.len();
+
+        if self.ready_at(len).is_some_and(|at| at > now) {
+            return None;
+        }
+        let line = self.queues[index].pop_front()?;
+        self.record(len, now);
+        Some(line)
+    }
+
+    /// Returns when the next queued message may be sent, possibly in the
+    /// past, or `None` if the queue is empty.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        let len = self.queues.iter().rev().find_map(|q| q.front())?.len();
+        Some(self.ready_at(len).unwrap_or(Duration::ZERO))
+    }
+
+    /// Number of queued messages.
+    pub fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.iter().all(VecDeque::is_empty)
+    }
+
+    /// Drops all queued messages, e.g. after disconnecting.
+    pub fn clear(&mut self) {
+        self.queues.iter_mut().for_each(VecDeque::clear);
+    }
+
+    /// Earliest time a message of `len` bytes may be sent, `None` if right
+    /// away.
+    fn ready_at(&self, len: usize) -> Option<Duration> {
+        match self.limit {
+            RateLimit::TokenBucket { burst, refill, .. } => {
+                // The bucket holds `burst` tokens once `timer` has passed;
+                // a message too large for it waits for a full bucket.
+                let capacity = refill.saturating_mul(burst);
+                let cost = self.cost(len).min(capacity);
+                Some(self.timer.saturating_add(cost).saturating_sub(capacity))
+            }
+            RateLimit::Penalty { max_ahead, .. } => Some(self.timer.saturating_sub(max_ahead)),
+            RateLimit::Window { max, window } => {
+                let max = max.max(1) as usize;
+                let index = self.sent.len().checked_sub(max)?;
+                Some(self.sent[index].saturating_add(window))
+            }
+        }
+    }
+
+    fn record(&mut self, len: usize, now: Duration) {
+        match self.limit {
+            RateLimit::TokenBucket { .. } | RateLimit::Penalty { .. } => {
+                self.timer = self.timer.max(now).saturating_add(self.cost(len));
+            }
+            RateLimit::Window { max, window } => {
+                while self
+                    .sent
+                    .front()
+                    .is_some_and(|sent| sent.saturating_add(window) <= now)
+                {
+                    self.sent.pop_front();
+                }
+                self.sent.push_back(now);
+                // Only the last `max` sends matter.
+                while self.sent.len() > max.max(1) as usize {
+                    self.sent.pop_front();
+                }
+            }
+        }
+    }
+
+    /// How far a message of `len` bytes advances the timer.
+    fn cost(&self, len: usize) -> Duration {
+        match self.limit {
+            RateLimit::TokenBucket {
+                refill,
+                bytes_per_token,
+                ..
+            } => {
+                let tokens = bytes_per_token
+                    .and_then(|b| len.checked_div(b))
+                    .unwrap_or(0)
+                    .saturating_add(1);
+                refill.saturating_mul(u32::try_from(tokens).unwrap_or(u32::MAX))
+            }
+            RateLimit::Penalty {
+                base,
+                extra,
+                per_bytes,
+                ..
+            } => {
+                let chunks = len.checked_div(per_bytes).unwrap_or(0);
+                base.saturating_add(extra.saturating_mul(u32::try_from(chunks).unwrap_or(u32::MAX)))
+            }
+            RateLimit::Window { .. } => Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::{format, Vec};
+    use crate::messages::client::PrivMsg;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn push(queue: &mut FloodQueue, text: &str, priority: Priority) {
+        queue
+            .push(
+                &PrivMsg {
+                    target: "#rust",
+                    text,
+                },
+                priority,
+            )
+            .unwrap();
+    }
+
+    fn drain(queue: &mut FloodQueue, now: Duration) -> Vec<Bytes> {
+        core::iter::from_fn(|| queue.poll_transmit(now)).collect()
+    }
+
+    #[test]
+    fn token_bucket() {
+        let mut queue = FloodQueue::new(RateLimit::TokenBucket {
+            burst: 3,
+            refill: secs(1),
+            bytes_per_token: Some(100),
+        });
+        assert_eq!(None, queue.next_deadline());
+
+        for i in 0..5 {
+            push(&mut queue, &format!("{i}"), Priority::Normal);
+        }
+        assert_eq!(3, drain(&mut queue, secs(10)).len());
+        assert_eq!(Some(secs(11)), queue.next_deadline());
+        assert_eq!(None, queue.poll_transmit(Duration::from_millis(10_999)));
+        assert_eq!(
+            "PRIVMSG #rust :3\r\n",
+            queue.poll_transmit(secs(11)).unwrap()
+        );
+
+        assert_eq!(1, drain(&mut queue, secs(15)).len());
+
+        // The bucket is full again at 16 seconds; the long line costs 3 tokens.
+        push(&mut queue, &"x".repeat(200), Priority::Normal);
+        push(&mut queue, "short", Priority::Normal);
+        assert_eq!(1, drain(&mut queue, secs(20)).len());
+        assert_eq!(Some(secs(21)), queue.next_deadline());
+        assert!(queue.poll_transmit(secs(21)).is_some());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn penalty() {
+        let mut queue = FloodQueue::new(RateLimit::ircd());
+        for _ in 0..8 {
+            push(&mut queue, "hi", Priority::Normal);
+        }
+
+        // 2 seconds each; the sixth starts at the 10 second allowance.
+        assert_eq!(6, drain(&mut queue, secs(0)).len());
+        assert_eq!(Some(secs(2)), queue.next_deadline());
+        assert_eq!(1, drain(&mut queue, secs(2)).len());
+
+        // 2 extra seconds for a line of over 240 bytes leave room for one
+        // message less.
+        let mut queue = FloodQueue::new(RateLimit::ircd());
+        push(&mut queue, &"x".repeat(250), Priority::Normal);
+        for _ in 0..5 {
+            push(&mut queue, "hi", Priority::Normal);
+        }
+        assert_eq!(5, drain(&mut queue, secs(100)).len());
+        assert_eq!(Some(secs(102)), queue.next_deadline());
+    }
+
+    #[test]
+    fn window_and_priority() {
+        let mut queue = FloodQueue::new(RateLimit::Window {
+            max: 2,
+            window: secs(30),
+        });
+        push(&mut queue, "low", Priority::Low);
+        push(&mut queue, "first", Priority::Normal);
+        push(&mut queue, "second", Priority::Normal);
+        queue.push_bytes(Bytes::from_static(b"QUIT\r\n"), Priority::High);
+        assert_eq!(4, queue.len());
+
+        let sent = drain(&mut queue, secs(5));
+        assert_eq!(["QUIT\r\n", "PRIVMSG #rust :first\r\n"], sent.as_slice());
+        assert_eq!(Some(secs(35)), queue.next_deadline());
+
+        let sent = drain(&mut queue, secs(35));
+        assert_eq!(
+            ["PRIVMSG #rust :second\r\n", "PRIVMSG #rust :low\r\n"],
+            sent.as_slice()
+        );
+
+        push(&mut queue, "a", Priority::Normal);
+        push(&mut queue, "b", Priority::Normal);
+        assert_eq!(None, queue.poll_transmit(secs(35)));
+        assert_eq!(Some(secs(65)), queue.next_deadline());
+
+        queue.set_limit(RateLimit::Window {
+            max: 3,
+            window: secs(30),
+        });
+        assert_eq!(
+            "PRIVMSG #rust :a\r\n",
+            queue.poll_transmit(secs(35)).unwrap()
+        );
+        assert_eq!(Some(secs(65)), queue.next_deadline());
+
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(None, queue.poll_transmit(secs(100)));
+    }
+
+    #[test]
+    fn huge_durations() {
+        let limits = [
+            RateLimit::TokenBucket {
+                burst: 2,
+                refill: Duration::MAX,
+                bytes_per_token: Some(1),
+            },
+            RateLimit::Penalty {
+                base: Duration::MAX,
+                extra: Duration::MAX,
+                per_bytes: 1,
+                max_ahead: secs(10),
+            },
+            RateLimit::Window {
+                max: 1,
+                window: Duration::MAX,
+            },
+        ];
+
+        for limit in limits {
+            let mut queue = FloodQueue::new(limit);
+            push(&mut queue, "first", Priority::Normal);
+            push(&mut queue, "second", Priority::Normal);
+            // Saturates instead of overflowing.
+            assert!(!drain(&mut queue, Duration::MAX).is_empty(), "{limit:?}");
+            queue.next_deadline();
+        }
+    }
+}
@@ -0,0 +1,604 @@
+//! mIRC text formatting.
+//!
+//! Follows the [formatting spec](https://modern.ircdocs.horse/formatting):
+//!
+//! | Code   | Effect                           |
+//! |--------|----------------------------------|
+//! | `\x02` | bold                             |
+//! | `\x03` | color, `\x03<fg>[,<bg>]`         |
+//! | `\x04` | hex color, `\x04RRGGBB[,RRGGBB]` |
+//! | `\x11` | monospace                        |
+//! | `\x16` | reverse                          |
+//! | `\x1d` | italic                           |
+//! | `\x1e` | strikethrough                    |
+//! | `\x1f` | underline                        |
+//! | `\x0f` | reset                            |
+//!
+//! ```rust
+//! use ircv3_parse::formatting::{self, Color, Span};
+//!
+//! let text = "\x02bold\x02 and \x0304,01red";
+//! let spans: Vec<_> = formatting::parse(text).collect();
+//! assert_eq!(3, spans.len());
+//! assert!(spans[0].style.bold);
+//! assert_eq!(Some(Color::RED), spans[2].style.fg);
+//!
+//! assert_eq!("bold and red", formatting::strip(text));
+//!
+//! let built = formatting::render(&[Span::new("hi").bold().fg(Color::GREEN)]);
+//! assert_eq!("\x02\x0303hi", built);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::compat::{format, String};
+
+pub const BOLD: char = '\x02';
+pub const COLOR: char = '\x03';
+pub const HEX_COLOR: char = '\x04';
+pub const RESET: char = '\x0f';
+pub const MONOSPACE: char = '\x11';
+pub const REVERSE: char = '\x16';
+pub const ITALIC: char = '\x1d';
+pub const STRIKETHROUGH: char = '\x1e';
+pub const UNDERLINE: char = '\x1f';
+
+const CODES: [u8; 9] = [
+    BOLD as u8,
+    COLOR as u8,
+    HEX_COLOR as u8,
+    RESET as u8,
+    MONOSPACE as u8,
+    REVERSE as u8,
+    ITALIC as u8,
+    STRIKETHROUGH as u8,
+    UNDERLINE as u8,
+];
+
+/// `99` means the client's default color and is parsed as no color.
+const DEFAULT_COLOR: u8 = 99;
+
+/// A foreground or background color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// One of the `00`-`98` color codes.
+    Code(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    pub const WHITE: Self = Self::Code(0);
+    pub const BLACK: Self = Self::Code(1);
+    pub const BLUE: Self = Self::Code(2);
+    pub const GREEN: Self = Self::Code(3);
+    pub const RED: Self = Self::Code(4);
+    pub const BROWN: Self = Self::Code(5);
+    pub const MAGENTA: Self = Self::Code(6);
+    pub const ORANGE: Self = Self::Code(7);
+    pub const YELLOW: Self = Self::Code(8);
+    pub const LIGHT_GREEN: Self = Self::Code(9);
+    pub const CYAN: Self = Self::Code(10);
+    pub const LIGHT_CYAN: Self = Self::Code(11);
+    pub const LIGHT_BLUE: Self = Self::Code(12);
+    pub const PINK: Self = Self::Code(13);
+    pub const GREY: Self = Self::Code(14);
+    pub const LIGHT_GREY: Self = Self::Code(15);
+
+    #[inline]
+    fn is_rgb(&self) -> bool {
+        matches!(self, Self::Rgb(..))
+    }
+
+    /// Writes the color as a foreground, with its control code.
+    fn write_sequence(&self, out: &mut String) {
+        out.push(if self.is_rgb() { HEX_COLOR } else { COLOR });
+        self.write_code(out);
+    }
+
+    fn write_code(&self, out: &mut String) {
+        match self {
+            Self::Code(code) => out.push_str(&format!("{code:02}")),
+            Self::Rgb(r, g, b) => out.push_str(&format!("{r:02X}{g:02X}{b:02X}")),
+        }
+    }
+}
+
+/// The formatting applied to a [`Span`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub monospace: bool,
+    pub reverse: bool,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+impl Style {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no formatting is applied.
+    #[inline]
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the codes that switch from `prev` to this style.
+    ///
+    /// When anything has to be turned off the output starts with `\x0f` and
+    /// applies this style from scratch.
+    pub fn transition(&self, prev: &Style) -> String {
+        let mut out = String::new();
+
+        let removed = (prev.bold && !self.bold)
+            || (prev.italic && !self.italic)
+            || (prev.underline && !self.underline)
+            || (prev.strikethrough && !self.strikethrough)
+            || (prev.monospace && !self.monospace)
+            || (prev.reverse && !self.reverse)
+            || (prev.fg.is_some() && self.fg.is_none())
+            || (prev.bg.is_some() && self.bg.is_none());
+
+        let prev = if removed {
+            out.push(RESET);
+            Style::default()
+        } else {
+            *prev
+        };
+
+        for (on, was, code) in [
+            (self.bold, prev.bold, BOLD),
+            (self.italic, prev.italic, ITALIC),
+            (self.underline, prev.underline, UNDERLINE),
+            (self.strikethrough, prev.strikethrough, STRIKETHROUGH),
+            (self.monospace, prev.monospace, MONOSPACE),
+            (self.reverse, prev.reverse, REVERSE),
+        ] {
+            if on && !was {
+                out.push(code);
+            }
+        }
+
+        if self.fg != prev.fg || self.bg != prev.bg {
+            self.write_colors(&mut out);
+        }
+
+        out
+    }
+
+    fn write_colors(&self, out: &mut String) {
+        let Some(bg) = self.bg else {
+            if let Some(fg) = self.fg {
+                fg.write_sequence(out);
+            }
+            return;
+        };
+
+        // Codes and hex colors cannot share a sequence: the background goes
+        // with a placeholder foreground of its kind, and a foreground of the
+        // other kind follows on its own.
+        let same_kind = self
+            .fg
+            .map_or(!bg.is_rgb(), |fg| fg.is_rgb() == bg.is_rgb());
+        let fg = match self.fg {
+            Some(fg) if same_kind => fg,
+            // Hex colors have no default, a background needs a foreground.
+            _ if bg.is_rgb() => Color::Rgb(0xff, 0xff, 0xff),
+            _ => Color::Code(DEFAULT_COLOR),
+        };
+
+        fg.write_sequence(out);
+        out.push(',');
+        bg.write_code(out);
+
+        if !same_kind {
+            self.fg
+                .unwrap_or(Color::Code(DEFAULT_COLOR))
+                .write_sequence(out);
+        }
+    }
+}
+
+/// A run of text with the same [`Style`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span<'a> {
+    pub text: &'a str,
+    pub style: Style,
+}
+
+impl<'a> Span<'a> {
+    #[inline]
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            style: Style::default(),
+        }
+    }
+
+    #[inline]
+    pub fn with_style(text: &'a str, style: Style) -> Self {
+        Self { text, style }
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.style.bold = true;
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.style.italic = true;
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.style.underline = true;
+        self
+    }
+
+    pub fn strikethrough(mut self) -> Self {
+        self.style.strikethrough = true;
+        self
+    }
+
+    pub fn monospace(mut self) -> Self {
+        self.style.monospace = true;
+        self
+    }
+
+    pub fn reverse(mut self) -> Self {
+        self.style.reverse = true;
+        self
+    }
+
+    pub fn fg(mut self, color: Color) -> Self {
+        self.style.fg = Some(color);
+        self
+    }
+
+    pub fn bg(mut self, color: Color) -> Self {
+        self.style.bg = Some(color);
+        self
+    }
+}
+
+/// Splits formatted text into styled spans.
+pub fn parse(text: &str) -> Spans<'_> {
+    Spans::new(text)
+}
+
+/// Removes all formatting codes.
+pub fn strip(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for span in parse(text) {
+        out.push_str(span.text);
+    }
+    out
+}
+
+/// Returns `true` if `text` contains any formatting code.
+pub fn is_formatted(text: &str) -> bool {
+    text.bytes().any(|b| CODES.contains(&b))
+}
+
+/// Builds formatted text from spans.
+///
+/// Only the codes needed to switch between consecutive styles are written.
+/// Colors always use two digits, so text that starts with a digit is kept.
+pub fn render(spans: &[Span<'_>]) -> String {
+    let mut out = String::new();
+    let mut prev = Style::default();
+
+    for span in spans {
+        if span.text.is_empty() {
+            continue;
+        }
+
+        push_codes(&mut out, &span.style.transition(&prev), span.text);
+        out.push_str(span.text);
+        prev = span.style;
+    }
+
+    out
+}
+
+/// Appends `codes` that are followed by `next`.
+pub(crate) fn push_codes(out: &mut String, codes: &str, next: &str) {
+    out.push_str(codes);
+
+    // `\x0304` followed by `,5` would read as a background color.
+    if codes.ends_with(|c: char| c.is_ascii_hexdigit()) && next.starts_with(',') {
+        out.push(BOLD);
+        out.push(BOLD);
+    }
+}
+
+/// Iterator over the [`Span`]s of formatted text, see [`parse()`].
+#[derive(Debug, Clone)]
+pub struct Spans<'a> {
+    text: &'a str,
+    pos: usize,
+    style: Style,
+}
+
+impl<'a> Spans<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            pos: 0,
+            style: Style::default(),
+        }
+    }
+
+    /// Returns the style in effect at the current position.
+    #[inline]
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    fn apply(&mut self, code: u8) {
+        let bytes = self.text.as_bytes();
+        let style = &mut self.style;
+
+        match code as char {
+            BOLD => style.bold = !style.bold,
+            ITALIC => style.italic = !style.italic,
+            UNDERLINE => style.underline = !style.underline,
+            STRIKETHROUGH => style.strikethrough = !style.strikethrough,
+            MONOSPACE => style.monospace = !style.monospace,
+            REVERSE => style.reverse = !style.reverse,
+            RESET => *style = Style::default(),
+            COLOR => match read_code(bytes, self.pos) {
+                None => {
+                    style.fg = None;
+                    style.bg = None;
+                }
+                Some((fg, next)) => {
+                    style.fg = fg;
+                    self.pos = next;
+                    if let Some((bg, next)) = read_separator(bytes, next, read_code) {
+                        style.bg = bg;
+                        self.pos = next;
+                    }
+                }
+            },
+            HEX_COLOR => match read_hex(bytes, self.pos) {
+                None => {
+                    style.fg = None;
+                    style.bg = None;
+                }
+                Some((fg, next)) => {
+                    style.fg = fg;
+                    self.pos = next;
+                    if let Some((bg, next)) = read_separator(bytes, next, read_hex) {
+                        style.bg = bg;
+                        self.pos = next;
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+impl<'a> Iterator for Spans<'a> {
+    type Item = Span<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.text.as_bytes();
+
+        while self.pos < bytes.len() {
+            let start = self.pos;
+            let end = bytes[start..]
+                .iter()
+                .position(|b| CODES.contains(b))
+                .map_or(bytes.len(), |i| start + i);
+
+            if end > start {
+                self.pos = end;
+                return Some(Span::with_style(&self.text[start..end], self.style));
+            }
+
+            self.pos += 1;
+            self.apply(bytes[start]);
+        }
+
+        None
+    }
+}
+
+type ColorReader = fn(&[u8], usize) -> Option<(Option<Color>, usize)>;
+
+/// Reads `,<color>`; the comma is text if no color follows it.
+fn read_separator(bytes: &[u8], pos: usize, read: ColorReader) -> Option<(Option<Color>, usize)> {
+    if bytes.get(pos) != Some(&b',') {
+        return None;
+    }
+    read(bytes, pos + 1)
+}
+
+/// Reads one or two digits.
+fn read_code(bytes: &[u8], pos: usize) -> Option<(Option<Color>, usize)> {
+    let digits = bytes[pos.min(bytes.len())..]
+        .iter()
+        .take(2)
+        .take_while(|b| b.is_ascii_digit())
+        .fold((0u8, 0usize), |(value, len), b| {
+            (value * 10 + (b - b'0'), len + 1)
+        });
+
+    match digits {
+        (_, 0) => None,
+        (DEFAULT_COLOR, len) => Some((None, pos + len)),
+        (code, len) => Some((Some(Color::Code(code)), pos + len)),
+    }
+}
+
+/// Reads exactly six hex digits.
+fn read_hex(bytes: &[u8], pos: usize) -> Option<(Option<Color>, usize)> {
+    let hex = bytes.get(pos..pos + 6)?;
+    if !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    let channel = |i: usize| {
+        core::str::from_utf8(&hex[i..i + 2])
+            .ok()
+            .and_then(|s| u8::from_str_radix(s, 16).ok())
+    };
+
+    Some((
+        Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?)),
+        pos + 6,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compat::Vec;
+
+    fn spans(text: &str) -> Vec<(&str, Style)> {
+        parse(text).map(|s| (s.text, s.style)).collect()
+    }
+
+    #[test]
+    fn toggles() {
+        let result = spans("a\x02b\x1dc\x02d\x0fe");
+        let bold = Style {
+            bold: true,
+            ..Style::default()
+        };
+        let bold_italic = Style {
+            italic: true,
+            ..bold
+        };
+        let italic = Style {
+            italic: true,
+            ..Style::default()
+        };
+
+        assert_eq!(
+            result,
+            [
+                ("a", Style::default()),
+                ("b", bold),
+                ("c", bold_italic),
+                ("d", italic),
+                ("e", Style::default()),
+            ]
+        );
+
+        let all = spans("\x1f\x1e\x11\x16x");
+        let style = all[0].1;
+        assert!(style.underline && style.strikethrough && style.monospace && style.reverse);
+    }
+
+    #[test]
+    fn colors() {
+        let result = spans("\x034red\x0304,still red\x0312,01blue on black\x03reset");
+        assert_eq!(4, result.len());
+        assert_eq!(
+            ("red", Some(Color::RED), None),
+            (result[0].0, result[0].1.fg, result[0].1.bg)
+        );
+        // A comma not followed by a digit is text.
+        assert_eq!(",still red", result[1].0);
+        assert_eq!(Some(Color::RED), result[1].1.fg);
+        assert_eq!(
+            (Some(Color::LIGHT_BLUE), Some(Color::BLACK)),
+            (result[2].1.fg, result[2].1.bg)
+        );
+        assert!(result[3].1.is_plain());
+    }
+
+    #[test]
+    fn color_edge_cases() {
+        // Only two digits are read.
+        let result = spans("\x03123");
+        assert_eq!(
+            ("3", Some(Color::LIGHT_BLUE)),
+            (result[0].0, result[0].1.fg)
+        );
+
+        // A foreground alone keeps the background.
+        let result = spans("\x0301,02a\x0303b");
+        assert_eq!(Some(Color::BLUE), result[1].1.bg);
+
+        // A trailing comma stays text.
+        let result = spans("\x0304,");
+        assert_eq!(
+            (",", Some(Color::RED), None),
+            (result[0].0, result[0].1.fg, result[0].1.bg)
+        );
+
+        // 99 is the default color.
+        let result = spans("\x0304,02a\x0399,99b");
+        assert_eq!((None, None), (result[1].1.fg, result[1].1.bg));
+    }
+
+    #[test]
+    fn hex_colors() {
+        let result = spans("\x04FF0000red\x04ff0000,00FF00both\x04ABCx\x04done");
+        assert_eq!(Some(Color::Rgb(0xff, 0, 0)), result[0].1.fg);
+        assert_eq!(Some(Color::Rgb(0, 0xff, 0)), result[1].1.bg);
+        assert_eq!(("ABCx", None), (result[2].0, result[2].1.fg));
+        assert!(result[3].1.is_plain());
+    }
+
+    #[test]
+    fn strip_all() {
+        assert_eq!(
+            "bold red ,hex plain",
+            strip("\x02bold\x02 \x0304,01red\x03 \x03,hex\x04 \x04AABBCC\x0fplain")
+        );
+        assert_eq!("héllo wörld", strip("\x1dhéllo\x1d \x0313wörld"));
+        assert!(is_formatted("\x0fx"));
+        assert!(!is_formatted("plain"));
+    }
+
+    #[test]
+    fn render_roundtrip() {
+        let input = [
+            Span::new("plain "),
+            Span::new("bold").bold(),
+            Span::new(" red").bold().fg(Color::RED),
+            Span::new("5 on black").fg(Color::RED).bg(Color::BLACK),
+            Span::new(",comma").fg(Color::GREEN),
+            Span::new("hex").fg(Color::Rgb(0x12, 0xab, 0xef)),
+            Span::new(" bg only").bg(Color::YELLOW),
+            Span::new(" done"),
+        ];
+
+        let text = render(&input);
+        assert_eq!(
+            "plain \x02bold\x0304 red\x0f\x0304,015 on black\x0f\x0303\x02\x02,comma\x0412ABEFhex\x0f\x0399,08 bg only\x0f done",
+            text
+        );
+
+        let parsed: Vec<_> = parse(&text).collect();
+        assert_eq!(input.as_slice(), parsed.as_slice());
+    }
+
+    #[test]
+    fn render_mixed_colors() {
+        let rgb = Color::Rgb(0x12, 0xab, 0xef);
+        let input = [
+            Span::new("code on hex").fg(Color::RED).bg(rgb),
+            Span::new(" hex on code").fg(rgb).bg(Color::BLACK),
+            Span::new(" default on hex").bg(rgb),
+        ];
+
+        let text = render(&input);
+        assert_eq!(
+            "\x04FFFFFF,12ABEF\x0304code on hex\x0399,01\x0412ABEF hex on code\x0f\x04FFFFFF,12ABEF\x0399 default on hex",
+            text
+        );
+
+        let parsed: Vec<_> = parse(&text).collect();
+        assert_eq!(input.as_slice(), parsed.as_slice());
+    }
+}
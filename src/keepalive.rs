@@ -0,0 +1,342 @@
+//! Dead connection detection and lag measurement.
+//!
+//! [`Keepalive`] sends a `PING` with a unique token after a period without
+//! incoming messages, matches the `PONG` and reports the round-trip time. If no
+//! `PONG` arrives in time it reports a timeout. It does not do any I/O and
+//! never reads a clock itself: the caller passes the current time as a
+//! [`Duration`] since any fixed point.
+//!
+//! Answering the server's own `PING` is left to the caller, e.g.
+//! [`ClientSession`](crate::session::ClientSession).
+//!
+//! ```rust
+//! use core::time::Duration;
+//! use ircv3_parse::keepalive::{Keepalive, KeepaliveEvent};
+//!
+//! let secs = Duration::from_secs;
+//! let mut keepalive = Keepalive::new(secs(60), secs(30));
+//! keepalive.reset(secs(0));
+//!
+//! keepalive.tick(secs(60));
+//! assert_eq!("PING ka1\r\n", keepalive.poll_transmit().unwrap());
+//!
+//! let pong = ircv3_parse::parse(":irc.example.com PONG irc.example.com :ka1")?;
+//! assert!(keepalive.handle(&pong, Duration::from_millis(60_250)));
+//! assert_eq!(
+//!     Some(KeepaliveEvent::Pong { token: "ka1".to_owned(), rtt: Duration::from_millis(250) }),
+//!     keepalive.poll_event()
+//! );
+//! assert_eq!(Some(Duration::from_millis(250)), keepalive.lag());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use core::time::Duration;
+
+use bytes::Bytes;
+
+use crate::compat::{format, String, ToOwned, Vec, VecDeque};
+
+use crate::{
+    de::FromMessage,
+    error::ParamError,
+    messages::client::{Ping, Pong},
+    ser::{IRCSerializer, ToMessage},
+    validators, Message,
+};
+
+/// See [`Keepalive::poll_event()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeepaliveEvent {
+    /// One of our `PING`s was answered after `rtt`.
+    Pong { token: String, rtt: Duration },
+    /// No `PONG` arrived within the timeout; the connection is likely dead.
+    Timeout {
+        token: String,
+        /// Time since the last incoming message.
+        silence: Duration,
+    },
+}
+
+/// Sans-IO keepalive.
+///
+/// Feed every incoming message to [`Keepalive::handle()`], call
+/// [`Keepalive::tick()`] at [`Keepalive::next_deadline()`] and send whatever
+/// [`Keepalive::poll_transmit()`] returns.
+///
+/// After a [`KeepaliveEvent::Timeout`] no more `PING`s are sent until
+/// [`Keepalive::reset()`] is called for the next connection.
+#[derive(Debug, Clone)]
+pub struct Keepalive {
+    interval: Duration,
+    timeout: Duration,
+    prefix: String,
+    count: u64,
+    last_activity: Duration,
+    last_ping: Option<Duration>,
+    outstanding: Vec<(String, Duration)>,
+    lag: Option<Duration>,
+    timed_out: bool,
+    outgoing: VecDeque<Bytes>,
+    events: VecDeque<KeepaliveEvent>,
+}
+
+impl Keepalive {
+    /// Sends a `PING` after `interval` without incoming messages and reports a
+    /// timeout if it is not answered within `timeout`.
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        Self {
+            interval,
+            timeout,
+            prefix: "ka".to_owned(),
+            count: 0,
+            last_activity: Duration::ZERO,
+            last_ping: None,
+            outstanding: Vec::new(),
+            lag: None,
+            timed_out: false,
+            outgoing: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Sets the prefix of `PING` tokens, `ka` by default.
+    ///
+    /// Fails if the prefix contains NUL, CR or LF, which could not be sent.
+    pub fn token_prefix(mut self, prefix: impl Into<String>) -> Result<Self, ParamError> {
+        let prefix = prefix.into();
+        validators::trailing(&prefix)?;
+        self.prefix = prefix;
+        Ok(self)
+    }
+
+    /// Starts over for a new connection established at `now`.
+    pub fn reset(&mut self, now: Duration) {
+        self.last_activity = now;
+        self.last_ping = None;
+        self.outstanding.clear();
+        self.lag = None;
+        self.timed_out = false;
+        self.outgoing.clear();
+    }
+
+    /// Processes an incoming message received at `now`.
+    ///
+    /// Any message delays the next `PING`, but only the matching `PONG`
+    /// answers an outstanding one: [`Keepalive::tick()`] still reports a
+    /// timeout if it is missing, however busy the connection is. Returns
+    /// `true` if it was the `PONG` to one of our `PING`s, which the caller may
+    /// then ignore.
+    pub fn handle(&mut self, msg: &Message<'_>, now: Duration) -> bool {
+        self.last_activity = now;
+
+        if !msg.command().is_pong() {
+            return false;
+        }
+        let Ok(pong) = Pong::from_message(msg) else {
+            return false;
+        };
+        let Some(index) = self.outstanding.iter().position(|(t, _)| t == pong.token) else {
+            return false;
+        };
+
+        // Older PINGs were lost or answered out of order; either way the
+        // connection is alive.
+        let (token, sent) = self.outstanding.drain(..=index).next_back().unwrap();
+        let rtt = now.saturating_sub(sent);
+        self.lag = Some(rtt);
+        self.events.push_back(KeepaliveEvent::Pong { token, rtt });
+        true
+    }
+
+    /// Advances the timer: reports a timeout or queues a `PING` when due.
+    pub fn tick(&mut self, now: Duration) {
+        if self.timed_out {
+            return;
+        }
+
+        if let Some((token, sent)) = self.outstanding.first() {
+            if now >= sent.saturating_add(self.timeout) {
+                self.events.push_back(KeepaliveEvent::Timeout {
+                    token: token.clone(),
+                    silence: now.saturating_sub(self.last_activity),
+                });
+                self.outstanding.clear();
+                self.timed_out = true;
+                return;
+            }
+        }
+
+        if now >= self.next_ping() {
+            self.send_ping(now);
+        }
+    }
+
+    /// Returns when [`Keepalive::tick()`] has something to do, or `None`
+    /// after a timeout.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        if self.timed_out {
+            return None;
+        }
+
+        let ping = self.next_ping();
+        Some(match self.outstanding.first() {
+            Some((_, sent)) => ping.min(sent.saturating_add(self.timeout)),
+            None => ping,
+        })
+    }
+
+    /// Returns the next message to send.
+    pub fn poll_transmit(&mut self) -> Option<Bytes> {
+        self.outgoing.pop_front()
+    }
+
+    /// Returns the next event.
+    pub fn poll_event(&mut self) -> Option<KeepaliveEvent> {
+        self.events.pop_front()
+    }
+
+    /// Round-trip time of the last answered `PING`.
+    pub fn lag(&self) -> Option<Duration> {
+        self.lag
+    }
+
+    /// Tokens of unanswered `PING`s, oldest first.
+    pub fn outstanding(&self) -> impl Iterator<Item = &str> {
+        self.outstanding.iter().map(|(token, _)| token.as_str())
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// A `PING` is due after `interval` of silence, counted from the last
+    /// incoming message or the last `PING`, whichever is later.
+    fn next_ping(&self) -> Duration {
+        let since = match self.last_ping {
+            Some(ping) => ping.max(self.last_activity),
+            None => self.last_activity,
+        };
+        since.saturating_add(self.interval)
+    }
+
+    fn send_ping(&mut self, now: Duration) {
+        self.count += 1;
+        let token = format!("{}{}", self.prefix, self.count);
+
+        let mut serializer = IRCSerializer::new();
+        (Ping { token: &token })
+            .to_message(&mut serializer)
+            .expect("token prefix is validated");
+
+        self.outgoing.push_back(serializer.into_bytes());
+        self.outstanding.push((token, now));
+        self.last_ping = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn pong(token: &str) -> String {
+        format!(":irc.example.com PONG irc.example.com :{token}")
+    }
+
+    #[test]
+    fn activity_delays_ping() {
+        let mut keepalive = Keepalive::new(secs(60), secs(30))
+            .token_prefix("lag")
+            .unwrap();
+        keepalive.reset(secs(100));
+        assert_eq!(Some(secs(160)), keepalive.next_deadline());
+
+        let msg = crate::parse(":nick!u@h PRIVMSG #rust :hi").unwrap();
+        assert!(!keepalive.handle(&msg, secs(150)));
+        keepalive.tick(secs(160));
+        assert_eq!(None, keepalive.poll_transmit());
+        assert_eq!(Some(secs(210)), keepalive.next_deadline());
+
+        keepalive.tick(secs(210));
+        assert_eq!("PING lag1\r\n", keepalive.poll_transmit().unwrap());
+        assert_eq!(
+            ["lag1"],
+            keepalive.outstanding().collect::<Vec<_>>().as_slice()
+        );
+        assert_eq!(Some(secs(240)), keepalive.next_deadline());
+
+        // Not our token.
+        let line = pong("other");
+        let msg = crate::parse(&line).unwrap();
+        assert!(!keepalive.handle(&msg, secs(211)));
+
+        let line = pong("lag1");
+        let msg = crate::parse(&line).unwrap();
+        assert!(keepalive.handle(&msg, secs(212)));
+        assert_eq!(Some(secs(2)), keepalive.lag());
+        assert_eq!(0, keepalive.outstanding().count());
+    }
+
+    #[test]
+    fn invalid_prefix() {
+        for prefix in ["ka\r", "ka\n", "ka\0"] {
+            assert!(Keepalive::new(secs(60), secs(30))
+                .token_prefix(prefix)
+                .is_err());
+        }
+        assert!(Keepalive::new(secs(60), secs(30))
+            .token_prefix("ka ping")
+            .is_ok());
+    }
+
+    #[test]
+    fn timeout() {
+        let mut keepalive = Keepalive::new(secs(10), secs(30));
+        keepalive.reset(secs(0));
+
+        keepalive.tick(secs(10));
+        keepalive.tick(secs(20));
+        keepalive.tick(secs(30));
+        assert_eq!(3, keepalive.outstanding().count());
+
+        // Answering a later PING clears the older ones.
+        let line = pong("ka2");
+        let msg = crate::parse(&line).unwrap();
+        assert!(keepalive.handle(&msg, secs(31)));
+        assert_eq!(
+            ["ka3"],
+            keepalive.outstanding().collect::<Vec<_>>().as_slice()
+        );
+        assert_eq!(
+            Some(KeepaliveEvent::Pong {
+                token: "ka2".to_owned(),
+                rtt: secs(11)
+            }),
+            keepalive.poll_event()
+        );
+
+        keepalive.tick(secs(41));
+        keepalive.tick(secs(60));
+        assert_eq!(
+            Some(KeepaliveEvent::Timeout {
+                token: "ka3".to_owned(),
+                silence: secs(29)
+            }),
+            keepalive.poll_event()
+        );
+        assert!(keepalive.is_timed_out());
+        assert_eq!(None, keepalive.next_deadline());
+
+        keepalive.tick(secs(100));
+        assert_eq!(None, keepalive.poll_event());
+
+        keepalive.reset(secs(100));
+        assert!(!keepalive.is_timed_out());
+        assert_eq!(None, keepalive.poll_transmit());
+        keepalive.tick(secs(110));
+        assert_eq!("PING ka5\r\n", keepalive.poll_transmit().unwrap());
+    }
+}
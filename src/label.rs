@@ -0,0 +1,356 @@
+//! IRCv3 `labeled-response` correlation.
+//!
+//! With `labeled-response` enabled a client may add a `label` tag to a command.
+//! The server then answers with a single message carrying the same label, an
+//! empty `ACK`, or a `BATCH` of type `labeled-response` holding every reply.
+//!
+//! [`LabelCorrelator`] labels outgoing messages and matches the answers back to
+//! the request. It does not do any I/O and never reads a clock itself: the
+//! caller passes the current time as a [`Duration`] since any fixed point.
+//!
+//! ```rust
+//! use core::time::Duration;
+//! use ircv3_parse::{label::{LabelCorrelator, Response}, Commands, MessageBuilder};
+//!
+//! let mut correlator = LabelCorrelator::new(Duration::from_secs(30));
+//!
+//! let mut whois = MessageBuilder::new();
+//! whois.set_command(Commands::WHOIS)?.add_param("ferris")?;
+//! let line = correlator.send(whois, Duration::ZERO)?;
+//! assert_eq!("@label=1 WHOIS ferris\r\n", line);
+//!
+//! let reply = ircv3_parse::parse("@label=1 :irc.example.com BATCH +NMzYSq45x labeled-response")?;
+//! assert!(correlator.handle(&reply).is_none());
+//!
+//! // ... the replies inside the batch ...
+//!
+//! let reply = ircv3_parse::parse(":irc.example.com BATCH -NMzYSq45x")?;
+//! let reply = correlator.handle(&reply).unwrap();
+//! assert_eq!("1", reply.label);
+//! assert_eq!(Response::Batch("NMzYSq45x".to_owned()), reply.response);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use core::time::Duration;
+
+use bytes::Bytes;
+
+use crate::compat::{format, String, ToOwned, Vec};
+
+use crate::{
+    ser::{IRCSerializer, MessageSerializer, ToMessage},
+    Commands, Message, SerError,
+};
+
+/// Name of the capability.
+pub const CAP: &str = "labeled-response";
+
+/// Tag carrying the label.
+pub const LABEL_TAG: &str = "label";
+
+/// Type of the batch wrapping a multi-message response.
+pub const BATCH_TYPE: &str = "labeled-response";
+
+/// Generates unique labels: an optional prefix followed by a counter.
+///
+/// Labels are limited to 64 bytes, so the prefix should stay short.
+#[derive(Debug, Clone, Default)]
+pub struct LabelGenerator {
+    prefix: String,
+    count: u64,
+}
+
+impl LabelGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_prefix(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+            count: 0,
+        }
+    }
+
+    /// Returns the next label, e.g. `1`, `2` or `abc1`, `abc2`.
+    pub fn next_label(&mut self) -> String {
+        self.count += 1;
+        format!("{}{}", self.prefix, self.count)
+    }
+}
+
+/// How the server answered a labeled request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Response {
+    /// An `ACK` without content.
+    Ack,
+    /// A single labeled message, the one passed to [`LabelCorrelator::handle()`].
+    Message,
+    /// A `labeled-response` batch with this reference, which just closed.
+    Batch(String),
+}
+
+/// A request that got an answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply<T> {
+    pub label: String,
+    pub request: T,
+    pub response: Response,
+}
+
+/// A request still waiting for an answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pending<T> {
+    pub label: String,
+    pub request: T,
+    /// When the request was sent.
+    pub sent: Duration,
+    batch: Option<String>,
+}
+
+/// Matches labeled responses to the requests that caused them.
+///
+/// Requests whose answer does not start within the timeout are dropped by
+/// [`LabelCorrelator::expire()`]. Once a response batch has started, the
+/// request no longer expires.
+#[derive(Debug, Clone)]
+pub struct LabelCorrelator<T> {
+    generator: LabelGenerator,
+    timeout: Duration,
+    pending: Vec<Pending<T>>,
+}
+
+impl<T> LabelCorrelator<T> {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            generator: LabelGenerator::new(),
+            timeout,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn generator(mut self, generator: LabelGenerator) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    /// Labels `request` and serializes it.
+    pub fn send(&mut self, request: T, now: Duration) -> Result<Bytes, SerError>
+    where
+        T: ToMessage,
+    {
+        let label = self.generator.next_label();
+
+        let mut serializer = IRCSerializer::new();
+        serializer.tags().insert_tag(LABEL_TAG, Some(&label))?;
+        request.to_message(&mut serializer)?;
+        serializer.end()?;
+        let bytes = serializer.into_bytes();
+
+        self.insert(label, request, now);
+        Ok(bytes)
+    }
+
+    /// Records a request that was labeled by the caller.
+    pub fn insert(&mut self, label: String, request: T, now: Duration) {
+        self.pending.push(Pending {
+            label,
+            request,
+            sent: now,
+            batch: None,
+        });
+    }
+
+    /// Processes an incoming message.
+    ///
+    /// Returns the request once its answer is complete: on a labeled message or
+    /// `ACK`, or on the end of the `labeled-response` batch.
+    pub fn handle(&mut self, msg: &Message<'_>) -> Option<Reply<T>> {
+        let command = msg.command();
+
+        if command.is_batch() {
+            let reference = msg.params().get(0)?;
+
+            if let Some(reference) = reference.strip_prefix('-') {
+                let index = self
+                    .pending
+                    .iter()
+                    .position(|p| p.batch.as_deref() == Some(reference))?;
+                return Some(self.reply(index, Response::Batch(reference.to_owned())));
+            }
+
+            if let Some(reference) = reference.strip_prefix('+') {
+                let label = msg.tags()?.get(LABEL_TAG)?;
+                let pending = self
+                    .pending
+                    .iter_mut()
+                    .find(|p| p.label == label.as_str())?;
+                pending.batch = Some(reference.to_owned());
+                return None;
+            }
+        }
+
+        let label = msg.tags()?.get(LABEL_TAG)?;
+        let index = self
+            .pending
+            .iter()
+            .position(|p| p.label == label.as_str() && p.batch.is_none())?;
+
+        let response = if command == Commands::ACK {
+            Response::Ack
+        } else {
+            Response::Message
+        };
+        Some(self.reply(index, response))
+    }
+
+    /// Removes and returns the requests sent more than the timeout before `now`.
+    pub fn expire(&mut self, now: Duration) -> Vec<Pending<T>> {
+        let mut expired = Vec::new();
+        let mut i = 0;
+        while i < self.pending.len() {
+            if self.is_expired(&self.pending[i], now) {
+                expired.push(self.pending.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        expired
+    }
+
+    /// Returns when the next request expires, to schedule [`LabelCorrelator::expire()`].
+    pub fn next_deadline(&self) -> Option<Duration> {
+        self.pending
+            .iter()
+            .filter(|p| p.batch.is_none())
+            .map(|p| p.sent.saturating_add(self.timeout))
+            .min()
+    }
+
+    pub fn get(&self, label: &str) -> Option<&Pending<T>> {
+        self.pending.iter().find(|p| p.label == label)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn is_expired(&self, pending: &Pending<T>, now: Duration) -> bool {
+        pending.batch.is_none() && now.saturating_sub(pending.sent) >= self.timeout
+    }
+
+    fn reply(&mut self, index: usize, response: Response) -> Reply<T> {
+        let pending = self.pending.remove(index);
+        Reply {
+            label: pending.label,
+            request: pending.request,
+            response,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBuilder;
+
+    fn request(command: Commands<'_>, param: &str) -> MessageBuilder {
+        let mut builder = MessageBuilder::new();
+        builder
+            .set_command(command)
+            .unwrap()
+            .add_param(param)
+            .unwrap();
+        builder
+    }
+
+    fn handle<T>(correlator: &mut LabelCorrelator<T>, line: &str) -> Option<Reply<T>> {
+        correlator.handle(&crate::parse(line).unwrap())
+    }
+
+    #[test]
+    fn generator() {
+        let mut generator = LabelGenerator::with_prefix("x");
+        assert_eq!("x1", generator.next_label());
+        assert_eq!("x2", generator.next_label());
+        assert_eq!("1", LabelGenerator::new().next_label());
+    }
+
+    #[test]
+    fn single_and_ack() {
+        let mut correlator = LabelCorrelator::new(Duration::from_secs(10));
+        let secs = Duration::from_secs;
+
+        let line = correlator
+            .send(request(Commands::MODE, "#rust"), secs(0))
+            .unwrap();
+        assert_eq!("@label=1 MODE #rust\r\n", line);
+        correlator
+            .send(request(Commands::PRIVMSG, "nick"), secs(1))
+            .unwrap();
+        assert_eq!(2, correlator.len());
+
+        assert!(handle(&mut correlator, ":a!b@c PRIVMSG #rust :unrelated").is_none());
+        assert!(handle(&mut correlator, "@label=9 :irc.example.com ACK").is_none());
+
+        let reply = handle(&mut correlator, "@label=2 :irc.example.com ACK").unwrap();
+        assert_eq!("2", reply.label);
+        assert_eq!(Response::Ack, reply.response);
+        assert_eq!("PRIVMSG nick\r\n", reply.request.build().unwrap());
+
+        let reply = handle(
+            &mut correlator,
+            "@label=1 :irc.example.com 324 me #rust +nt",
+        )
+        .unwrap();
+        assert_eq!(Response::Message, reply.response);
+        assert!(correlator.is_empty());
+    }
+
+    #[test]
+    fn batch() {
+        let mut correlator = LabelCorrelator::new(Duration::from_secs(10));
+        correlator.insert("abc".to_owned(), "WHO #rust", Duration::ZERO);
+
+        assert!(handle(
+            &mut correlator,
+            "@label=abc :irc.example.com BATCH +b1 labeled-response"
+        )
+        .is_none());
+        // Replies inside the batch carry no label.
+        assert!(handle(&mut correlator, "@batch=b1 :irc.example.com 352 me #rust").is_none());
+        assert!(handle(&mut correlator, ":irc.example.com BATCH -other").is_none());
+
+        // A started batch does not expire.
+        assert_eq!(None, correlator.next_deadline());
+        assert!(correlator.expire(Duration::from_secs(60)).is_empty());
+
+        let reply = handle(&mut correlator, ":irc.example.com BATCH -b1").unwrap();
+        assert_eq!("WHO #rust", reply.request);
+        assert_eq!(Response::Batch("b1".to_owned()), reply.response);
+        assert!(correlator.is_empty());
+    }
+
+    #[test]
+    fn expiry() {
+        let mut correlator = LabelCorrelator::new(Duration::from_secs(10));
+        correlator.insert("a".to_owned(), 1, Duration::from_secs(5));
+        correlator.insert("b".to_owned(), 2, Duration::from_secs(8));
+
+        assert_eq!(Some(Duration::from_secs(15)), correlator.next_deadline());
+        assert!(correlator.expire(Duration::from_secs(14)).is_empty());
+
+        let expired = correlator.expire(Duration::from_secs(15));
+        assert_eq!(1, expired.len());
+        assert_eq!("a", expired[0].label);
+        assert_eq!(1, expired[0].request);
+
+        assert!(handle(&mut correlator, "@label=a ACK").is_none());
+        assert_eq!(Some(&2), correlator.get("b").map(|p| &p.request));
+        assert_eq!(Some(Duration::from_secs(18)), correlator.next_deadline());
+    }
+}
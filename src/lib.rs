@@ -7,6 +7,20 @@
 //! - **Derive macros**: `FromMessage` and `ToMessage` for easy message extraction and generation
 //! - **Manual implementations**: Full control over parsing and serialization when needed
 //! - **Builder pattern**: Flexible, order-independent message construction with [`MessageBuilder`]
+//! - **Typed messages**: Ready-made command types in [`messages`]
+//! - **Capability negotiation**: Sans-IO `CAP` handling in [`cap`]
+//! - **SASL**: `PLAIN`, `EXTERNAL` and `SCRAM-SHA-256` authentication in [`sasl`]
+//! - **CTCP**: Parse and build `\x01`-delimited messages with [`ctcp::Ctcp`]
+//! - **Formatting**: Parse, strip and build mIRC formatting codes in [`formatting`]
+//! - **Multiline**: Build and reassemble `draft/multiline` batches in [`multiline`]
+//! - **Batches**: Collect nested `BATCH` messages with [`batch::BatchTracker`]
+//! - **Labeled responses**: Match replies to labeled requests with [`label::LabelCorrelator`]
+//! - **Registration**: Connect and register without I/O using [`session::ClientSession`]
+//! - **Keepalive**: Detect dead connections and measure lag with [`keepalive::Keepalive`]
+//! - **Channel state**: Track channel members, hostmasks and accounts with [`state::StateTracker`]
+//! - **Filters**: Select messages with text expressions like `command == PRIVMSG && tag(+draft/react)` in [`filter`]
+//! - **Flood control**: Pace outgoing messages for ircd and Twitch limits with [`flood::FloodQueue`]
+//! - **Dispatching**: Route incoming messages to typed handlers with [`dispatch::Dispatcher`]
 //! - **`no_std` compatible**: Works in embedded and `no_std` environments (requires `alloc`)
 //!
 //! ## Quick Start
@@ -262,6 +276,10 @@
 //! - **`std`** (enabled by default) - Enables standard library support
 //! - **`derive`** - Enables `FromMessage` and `ToMessage` derive macros (recommended)
 //! - **`serde`** - Enables `Serialize` implementation for [`Message`]
+//! - **`scram`** - Enables the `SCRAM-SHA-256` mechanism in [`sasl`]
+//! - **`twitch`** - Enables Twitch chat messages and tags in `twitch` (implies `derive`)
+//! - **`chrono`** - Adds conversions between [`ServerTime`](components::ServerTime) and `chrono::DateTime<Utc>`
+//! - **`unicode`** (enabled by default) - Keeps grapheme clusters together when [splitting](messages::split) text
 //!
 //! ## Using in `no_std` Environments
 //!
@@ -278,16 +296,23 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+// Lets the derive macros, which refer to `ircv3_parse::`, be used inside the crate.
+#[cfg(feature = "derive")]
+extern crate self as ircv3_parse;
+
 pub(crate) mod compat {
     pub use core::{
         fmt::{Debug, Display, Formatter, Result as FmtResult},
-        iter::Map,
+        iter::{Chain, Map},
+        option::IntoIter as OptionIntoIter,
         str::{Split, SplitAsciiWhitespace},
     };
 
     #[cfg(not(feature = "std"))]
     pub use alloc::{
         borrow::ToOwned,
+        boxed::Box,
+        collections::VecDeque,
         format,
         string::{String, ToString},
         vec::Vec,
@@ -296,6 +321,8 @@ pub(crate) mod compat {
     #[cfg(feature = "std")]
     pub use std::{
         borrow::ToOwned,
+        boxed::Box,
+        collections::VecDeque,
         format,
         string::{String, ToString},
         vec::Vec,
@@ -305,10 +332,26 @@ pub(crate) mod compat {
 #[cfg(feature = "derive")]
 pub use ircv3_parse_derive::{FromMessage, ToMessage};
 
+pub mod batch;
+pub mod cap;
 pub mod components;
+pub mod ctcp;
 pub mod de;
+pub mod dispatch;
 pub mod error;
+pub mod filter;
+pub mod flood;
+pub mod formatting;
+pub mod keepalive;
+pub mod label;
+pub mod messages;
+pub mod multiline;
+pub mod sasl;
 pub mod ser;
+pub mod session;
+pub mod state;
+#[cfg(feature = "twitch")]
+pub mod twitch;
 pub mod validators;
 
 mod builder;
@@ -319,7 +362,7 @@ mod unescape;
 
 pub use builder::MessageBuilder;
 pub use components::Commands;
-pub use error::{DeError, IRCError, SerError};
+pub use error::{DeError, IRCError, SaslError, SerError};
 pub use message::Message;
 pub use unescape::unescape;
 
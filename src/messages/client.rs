@@ -0,0 +1,1064 @@
+//! Client-to-server commands from RFC 2812.
+//!
+//! Parameters are read positionally with [`Params::get()`](crate::components::Params::get),
+//! so the last parameter may be sent either as a middle or as a trailing parameter.
+//! The source of a relayed message is not part of these types; read it from
+//! [`Message::source()`] when needed.
+//!
+//! Serialization always ends the message with CRLF.
+
+use crate::compat::Vec;
+
+use crate::{
+    de::FromMessage,
+    error::ParamError,
+    ser::{MessageSerializer, ToMessage},
+    Commands, DeError, Message, SerError,
+};
+
+use super::{expect_command, join_list, push_last, required, split_list};
+
+/// `NICK <nickname>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nick<'a> {
+    pub nickname: &'a str,
+}
+
+impl<'a> FromMessage<'a> for Nick<'a> {
+    const COMMAND: Option<&'static str> = Some("NICK");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::NICK)?;
+        let params = msg.params();
+
+        Ok(Self {
+            nickname: required(&params, 0)?,
+        })
+    }
+}
+
+impl ToMessage for Nick<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::NICK);
+        push_last(serialize, self.nickname)?;
+        serialize.end()
+    }
+}
+
+/// `USER <username> 0 * <realname>`
+///
+/// The mode and unused parameters are ignored when parsing and written as `0 *`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User<'a> {
+    pub username: &'a str,
+    pub realname: &'a str,
+}
+
+impl<'a> FromMessage<'a> for User<'a> {
+    const COMMAND: Option<&'static str> = Some("USER");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::USER)?;
+        let params = msg.params();
+
+        Ok(Self {
+            username: required(&params, 0)?,
+            realname: required(&params, 3)?,
+        })
+    }
+}
+
+impl ToMessage for User<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::USER);
+        serialize.params().extend([self.username, "0", "*"])?;
+        serialize.set_trailing(self.realname)?;
+        serialize.end()
+    }
+}
+
+/// `PASS <password>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pass<'a> {
+    pub password: &'a str,
+}
+
+impl<'a> FromMessage<'a> for Pass<'a> {
+    const COMMAND: Option<&'static str> = Some("PASS");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::PASS)?;
+        let params = msg.params();
+
+        Ok(Self {
+            password: required(&params, 0)?,
+        })
+    }
+}
+
+impl ToMessage for Pass<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::PASS);
+        push_last(serialize, self.password)?;
+        serialize.end()
+    }
+}
+
+/// `OPER <name> <password>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Oper<'a> {
+    pub name: &'a str,
+    pub password: &'a str,
+}
+
+impl<'a> FromMessage<'a> for Oper<'a> {
+    const COMMAND: Option<&'static str> = Some("OPER");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::OPER)?;
+        let params = msg.params();
+
+        Ok(Self {
+            name: required(&params, 0)?,
+            password: required(&params, 1)?,
+        })
+    }
+}
+
+impl ToMessage for Oper<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::OPER);
+        serialize.params().push(self.name)?;
+        push_last(serialize, self.password)?;
+        serialize.end()
+    }
+}
+
+/// `JOIN <channel>{,<channel>} [<key>{,<key>}]`
+///
+/// Keys are matched to channels by position. `JOIN 0` leaves all channels,
+/// see [`Join::is_leave_all()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Join<'a> {
+    pub channels: Vec<&'a str>,
+    pub keys: Vec<&'a str>,
+}
+
+impl<'a> Join<'a> {
+    /// Returns `true` for `JOIN 0`.
+    pub fn is_leave_all(&self) -> bool {
+        self.channels.as_slice() == ["0"]
+    }
+
+    /// Returns each channel together with its key, if any.
+    pub fn pairs(&self) -> impl Iterator<Item = (&'a str, Option<&'a str>)> + '_ {
+        self.channels
+            .iter()
+            .enumerate()
+            .map(|(i, channel)| (*channel, self.keys.get(i).copied()))
+    }
+}
+
+impl<'a> FromMessage<'a> for Join<'a> {
+    const COMMAND: Option<&'static str> = Some("JOIN");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::JOIN)?;
+        let params = msg.params();
+
+        Ok(Self {
+            channels: split_list(required(&params, 0)?),
+            keys: params.get(1).map(split_list).unwrap_or_default(),
+        })
+    }
+}
+
+impl ToMessage for Join<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::JOIN);
+        serialize.params().push(&join_list(&self.channels))?;
+        if !self.keys.is_empty() {
+            serialize.params().push(&join_list(&self.keys))?;
+        }
+        serialize.end()
+    }
+}
+
+/// `PART <channel>{,<channel>} [<reason>]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part<'a> {
+    pub channels: Vec<&'a str>,
+    pub reason: Option<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for Part<'a> {
+    const COMMAND: Option<&'static str> = Some("PART");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::PART)?;
+        let params = msg.params();
+
+        Ok(Self {
+            channels: split_list(required(&params, 0)?),
+            reason: params.get(1),
+        })
+    }
+}
+
+impl ToMessage for Part<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::PART);
+        serialize.params().push(&join_list(&self.channels))?;
+        if let Some(reason) = self.reason {
+            serialize.set_trailing(reason)?;
+        }
+        serialize.end()
+    }
+}
+
+/// `KICK <channel> <user> [<comment>]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Kick<'a> {
+    pub channel: &'a str,
+    pub user: &'a str,
+    pub comment: Option<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for Kick<'a> {
+    const COMMAND: Option<&'static str> = Some("KICK");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::KICK)?;
+        let params = msg.params();
+
+        Ok(Self {
+            channel: required(&params, 0)?,
+            user: required(&params, 1)?,
+            comment: params.get(2),
+        })
+    }
+}
+
+impl ToMessage for Kick<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::KICK);
+        serialize.params().extend([self.channel, self.user])?;
+        if let Some(comment) = self.comment {
+            serialize.set_trailing(comment)?;
+        }
+        serialize.end()
+    }
+}
+
+/// `TOPIC <channel> [<topic>]`
+///
+/// `topic` is `None` when querying the topic and `Some("")` when clearing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Topic<'a> {
+    pub channel: &'a str,
+    pub topic: Option<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for Topic<'a> {
+    const COMMAND: Option<&'static str> = Some("TOPIC");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::TOPIC)?;
+        let params = msg.params();
+
+        Ok(Self {
+            channel: required(&params, 0)?,
+            topic: params.get(1),
+        })
+    }
+}
+
+impl ToMessage for Topic<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::TOPIC);
+        serialize.params().push(self.channel)?;
+        if let Some(topic) = self.topic {
+            serialize.set_trailing(topic)?;
+        }
+        serialize.end()
+    }
+}
+
+/// `INVITE <nickname> <channel>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invite<'a> {
+    pub nickname: &'a str,
+    pub channel: &'a str,
+}
+
+impl<'a> FromMessage<'a> for Invite<'a> {
+    const COMMAND: Option<&'static str> = Some("INVITE");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::INVITE)?;
+        let params = msg.params();
+
+        Ok(Self {
+            nickname: required(&params, 0)?,
+            channel: required(&params, 1)?,
+        })
+    }
+}
+
+impl ToMessage for Invite<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::INVITE);
+        serialize.params().push(self.nickname)?;
+        push_last(serialize, self.channel)?;
+        serialize.end()
+    }
+}
+
+/// `NAMES [<channel>{,<channel>} [<target>]]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Names<'a> {
+    pub channels: Vec<&'a str>,
+    pub target: Option<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for Names<'a> {
+    const COMMAND: Option<&'static str> = Some("NAMES");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::NAMES)?;
+        let params = msg.params();
+
+        Ok(Self {
+            channels: params.get(0).map(split_list).unwrap_or_default(),
+            target: params.get(1),
+        })
+    }
+}
+
+impl ToMessage for Names<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::NAMES);
+        push_channels_target(serialize, &self.channels, self.target)?;
+        serialize.end()
+    }
+}
+
+/// `LIST [<channel>{,<channel>} [<target>]]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct List<'a> {
+    pub channels: Vec<&'a str>,
+    pub target: Option<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for List<'a> {
+    const COMMAND: Option<&'static str> = Some("LIST");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::LIST)?;
+        let params = msg.params();
+
+        Ok(Self {
+            channels: params.get(0).map(split_list).unwrap_or_default(),
+            target: params.get(1),
+        })
+    }
+}
+
+impl ToMessage for List<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::LIST);
+        push_channels_target(serialize, &self.channels, self.target)?;
+        serialize.end()
+    }
+}
+
+/// Pushes the `[<channel>{,<channel>} [<target>]]` of `NAMES` and `LIST`.
+///
+/// A target cannot be sent without a channel, so that is rejected rather
+/// than silently dropped.
+fn push_channels_target<S: MessageSerializer>(
+    serialize: &mut S,
+    channels: &[&str],
+    target: Option<&str>,
+) -> Result<(), SerError> {
+    if channels.is_empty() {
+        return match target {
+            Some(_) => Err(ParamError::EmptyMiddle.into()),
+            None => Ok(()),
+        };
+    }
+
+    serialize.params().push(&join_list(channels))?;
+    if let Some(target) = target {
+        serialize.params().push(target)?;
+    }
+    Ok(())
+}
+
+/// `WHO [<mask> [<options>]]`
+///
+/// `options` holds `o` or a WHOX field list such as `%cnuhraf,42`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Who<'a> {
+    pub mask: Option<&'a str>,
+    pub options: Option<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for Who<'a> {
+    const COMMAND: Option<&'static str> = Some("WHO");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::WHO)?;
+        let params = msg.params();
+
+        Ok(Self {
+            mask: params.get(0),
+            options: params.get(1),
+        })
+    }
+}
+
+impl ToMessage for Who<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::WHO);
+        if let Some(mask) = self.mask {
+            serialize.params().push(mask)?;
+            if let Some(options) = self.options {
+                serialize.params().push(options)?;
+            }
+        }
+        serialize.end()
+    }
+}
+
+/// `WHOIS [<target>] <mask>{,<mask>}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Whois<'a> {
+    pub target: Option<&'a str>,
+    pub masks: Vec<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for Whois<'a> {
+    const COMMAND: Option<&'static str> = Some("WHOIS");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::WHOIS)?;
+        let params = msg.params();
+
+        let (target, masks) = match (params.get(0), params.get(1)) {
+            (Some(target), Some(masks)) => (Some(target), masks),
+            (Some(masks), None) => (None, masks),
+            _ => return Err(DeError::not_found_param(0)),
+        };
+
+        Ok(Self {
+            target,
+            masks: split_list(masks),
+        })
+    }
+}
+
+impl ToMessage for Whois<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::WHOIS);
+        if let Some(target) = self.target {
+            serialize.params().push(target)?;
+        }
+        serialize.params().push(&join_list(&self.masks))?;
+        serialize.end()
+    }
+}
+
+/// `MODE <target> [<modestring> [<mode arguments>...]]`
+///
+/// `modes` is `None` when querying the current modes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mode<'a> {
+    pub target: &'a str,
+    pub modes: Option<ModeChange<'a>>,
+}
+
+/// The `<modestring> [<mode arguments>...]` of a [`Mode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeChange<'a> {
+    pub modestring: &'a str,
+    pub args: Vec<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for Mode<'a> {
+    const COMMAND: Option<&'static str> = Some("MODE");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::MODE)?;
+        let params = msg.params();
+
+        Ok(Self {
+            target: required(&params, 0)?,
+            modes: params.get(1).map(|modestring| ModeChange {
+                modestring,
+                args: params.iter().skip(2).collect(),
+            }),
+        })
+    }
+}
+
+impl ToMessage for Mode<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::MODE);
+        serialize.params().push(self.target)?;
+        if let Some(modes) = &self.modes {
+            serialize.params().push(modes.modestring)?;
+            if let Some((last, args)) = modes.args.split_last() {
+                serialize.params().extend(args)?;
+                push_last(serialize, last)?;
+            }
+        }
+        serialize.end()
+    }
+}
+
+/// `PRIVMSG <target> <text>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivMsg<'a> {
+    pub target: &'a str,
+    pub text: &'a str,
+}
+
+impl<'a> FromMessage<'a> for PrivMsg<'a> {
+    const COMMAND: Option<&'static str> = Some("PRIVMSG");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::PRIVMSG)?;
+        let params = msg.params();
+
+        Ok(Self {
+            target: required(&params, 0)?,
+            text: required(&params, 1)?,
+        })
+    }
+}
+
+impl ToMessage for PrivMsg<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::PRIVMSG);
+        serialize.params().push(self.target)?;
+        serialize.set_trailing(self.text)?;
+        serialize.end()
+    }
+}
+
+/// `NOTICE <target> <text>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notice<'a> {
+    pub target: &'a str,
+    pub text: &'a str,
+}
+
+impl<'a> FromMessage<'a> for Notice<'a> {
+    const COMMAND: Option<&'static str> = Some("NOTICE");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::NOTICE)?;
+        let params = msg.params();
+
+        Ok(Self {
+            target: required(&params, 0)?,
+            text: required(&params, 1)?,
+        })
+    }
+}
+
+impl ToMessage for Notice<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::NOTICE);
+        serialize.params().push(self.target)?;
+        serialize.set_trailing(self.text)?;
+        serialize.end()
+    }
+}
+
+/// `PING <token>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ping<'a> {
+    pub token: &'a str,
+}
+
+impl<'a> FromMessage<'a> for Ping<'a> {
+    const COMMAND: Option<&'static str> = Some("PING");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::PING)?;
+        let params = msg.params();
+
+        Ok(Self {
+            token: required(&params, 0)?,
+        })
+    }
+}
+
+impl ToMessage for Ping<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::PING);
+        push_last(serialize, self.token)?;
+        serialize.end()
+    }
+}
+
+/// `PONG [<server>] <token>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pong<'a> {
+    pub server: Option<&'a str>,
+    pub token: &'a str,
+}
+
+impl<'a> Pong<'a> {
+    /// Creates the reply to a [`Ping`].
+    pub fn reply(ping: &Ping<'a>) -> Self {
+        Self {
+            server: None,
+            token: ping.token,
+        }
+    }
+}
+
+impl<'a> FromMessage<'a> for Pong<'a> {
+    const COMMAND: Option<&'static str> = Some("PONG");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::PONG)?;
+        let params = msg.params();
+
+        match (params.get(0), params.get(1)) {
+            (Some(server), Some(token)) => Ok(Self {
+                server: Some(server),
+                token,
+            }),
+            (Some(token), None) => Ok(Self {
+                server: None,
+                token,
+            }),
+            _ => Err(DeError::not_found_param(0)),
+        }
+    }
+}
+
+impl ToMessage for Pong<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::PONG);
+        if let Some(server) = self.server {
+            serialize.params().push(server)?;
+        }
+        push_last(serialize, self.token)?;
+        serialize.end()
+    }
+}
+
+/// `QUIT [<reason>]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quit<'a> {
+    pub reason: Option<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for Quit<'a> {
+    const COMMAND: Option<&'static str> = Some("QUIT");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::QUIT)?;
+
+        Ok(Self {
+            reason: msg.params().get(0),
+        })
+    }
+}
+
+impl ToMessage for Quit<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::QUIT);
+        if let Some(reason) = self.reason {
+            serialize.set_trailing(reason)?;
+        }
+        serialize.end()
+    }
+}
+
+/// `AWAY [<text>]`
+///
+/// `message` is `None` when marking yourself as no longer away. Servers treat
+/// an empty message the same way, see [`Away::is_away()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Away<'a> {
+    pub message: Option<&'a str>,
+}
+
+impl Away<'_> {
+    /// Returns `false` for no message or an empty one.
+    pub fn is_away(&self) -> bool {
+        self.message.is_some_and(|message| !message.is_empty())
+    }
+}
+
+impl<'a> FromMessage<'a> for Away<'a> {
+    const COMMAND: Option<&'static str> = Some("AWAY");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::AWAY)?;
+
+        Ok(Self {
+            message: msg.params().get(0),
+        })
+    }
+}
+
+impl ToMessage for Away<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        serialize.set_command(Commands::AWAY);
+        if let Some(message) = self.message {
+            serialize.set_trailing(message)?;
+        }
+        serialize.end()
+    }
+}
+
+/// Any of the client commands in this module.
+///
+/// Dispatches on the command, so a single call parses any supported line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnyClientCommand<'a> {
+    Nick(Nick<'a>),
+    User(User<'a>),
+    Pass(Pass<'a>),
+    Oper(Oper<'a>),
+    Join(Join<'a>),
+    Part(Part<'a>),
+    Kick(Kick<'a>),
+    Topic(Topic<'a>),
+    Invite(Invite<'a>),
+    Names(Names<'a>),
+    List(List<'a>),
+    Who(Who<'a>),
+    Whois(Whois<'a>),
+    Mode(Mode<'a>),
+    PrivMsg(PrivMsg<'a>),
+    Notice(Notice<'a>),
+    Ping(Ping<'a>),
+    Pong(Pong<'a>),
+    Quit(Quit<'a>),
+    Away(Away<'a>),
+}
+
+impl AnyClientCommand<'_> {
+    pub const COMMANDS: &'static [&'static str] = &[
+        "NICK", "USER", "PASS", "OPER", "JOIN", "PART", "KICK", "TOPIC", "INVITE", "NAMES", "LIST",
+        "WHO", "WHOIS", "MODE", "PRIVMSG", "NOTICE", "PING", "PONG", "QUIT", "AWAY",
+    ];
+
+    /// Returns the command of the contained message.
+    pub fn command(&self) -> Commands<'static> {
+        match self {
+            Self::Nick(_) => Commands::NICK,
+            Self::User(_) => Commands::USER,
+            Self::Pass(_) => Commands::PASS,
+            Self::Oper(_) => Commands::OPER,
+            Self::Join(_) => Commands::JOIN,
+            Self::Part(_) => Commands::PART,
+            Self::Kick(_) => Commands::KICK,
+            Self::Topic(_) => Commands::TOPIC,
+            Self::Invite(_) => Commands::INVITE,
+            Self::Names(_) => Commands::NAMES,
+            Self::List(_) => Commands::LIST,
+            Self::Who(_) => Commands::WHO,
+            Self::Whois(_) => Commands::WHOIS,
+            Self::Mode(_) => Commands::MODE,
+            Self::PrivMsg(_) => Commands::PRIVMSG,
+            Self::Notice(_) => Commands::NOTICE,
+            Self::Ping(_) => Commands::PING,
+            Self::Pong(_) => Commands::PONG,
+            Self::Quit(_) => Commands::QUIT,
+            Self::Away(_) => Commands::AWAY,
+        }
+    }
+}
+
+impl<'a> FromMessage<'a> for AnyClientCommand<'a> {
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        match msg.command() {
+            Commands::NICK => Nick::from_message(msg).map(Self::Nick),
+            Commands::USER => User::from_message(msg).map(Self::User),
+            Commands::PASS => Pass::from_message(msg).map(Self::Pass),
+            Commands::OPER => Oper::from_message(msg).map(Self::Oper),
+            Commands::JOIN => Join::from_message(msg).map(Self::Join),
+            Commands::PART => Part::from_message(msg).map(Self::Part),
+            Commands::KICK => Kick::from_message(msg).map(Self::Kick),
+            Commands::TOPIC => Topic::from_message(msg).map(Self::Topic),
+            Commands::INVITE => Invite::from_message(msg).map(Self::Invite),
+            Commands::NAMES => Names::from_message(msg).map(Self::Names),
+            Commands::LIST => List::from_message(msg).map(Self::List),
+            Commands::WHO => Who::from_message(msg).map(Self::Who),
+            Commands::WHOIS => Whois::from_message(msg).map(Self::Whois),
+            Commands::MODE => Mode::from_message(msg).map(Self::Mode),
+            Commands::PRIVMSG => PrivMsg::from_message(msg).map(Self::PrivMsg),
+            Commands::NOTICE => Notice::from_message(msg).map(Self::Notice),
+            Commands::PING => Ping::from_message(msg).map(Self::Ping),
+            Commands::PONG => Pong::from_message(msg).map(Self::Pong),
+            Commands::QUIT => Quit::from_message(msg).map(Self::Quit),
+            Commands::AWAY => Away::from_message(msg).map(Self::Away),
+            other => Err(DeError::not_found_variant(
+                "command",
+                other.as_str(),
+                Self::COMMANDS.join(", "),
+            )),
+        }
+    }
+}
+
+impl ToMessage for AnyClientCommand<'_> {
+    fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        match self {
+            Self::Nick(m) => m.to_message(serialize),
+            Self::User(m) => m.to_message(serialize),
+            Self::Pass(m) => m.to_message(serialize),
+            Self::Oper(m) => m.to_message(serialize),
+            Self::Join(m) => m.to_message(serialize),
+            Self::Part(m) => m.to_message(serialize),
+            Self::Kick(m) => m.to_message(serialize),
+            Self::Topic(m) => m.to_message(serialize),
+            Self::Invite(m) => m.to_message(serialize),
+            Self::Names(m) => m.to_message(serialize),
+            Self::List(m) => m.to_message(serialize),
+            Self::Who(m) => m.to_message(serialize),
+            Self::Whois(m) => m.to_message(serialize),
+            Self::Mode(m) => m.to_message(serialize),
+            Self::PrivMsg(m) => m.to_message(serialize),
+            Self::Notice(m) => m.to_message(serialize),
+            Self::Ping(m) => m.to_message(serialize),
+            Self::Pong(m) => m.to_message(serialize),
+            Self::Quit(m) => m.to_message(serialize),
+            Self::Away(m) => m.to_message(serialize),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<'a, T>(input: &'a str) -> T
+    where
+        T: FromMessage<'a> + ToMessage,
+    {
+        let msg = T::from_str(input).unwrap();
+        let output = crate::to_message(&msg).unwrap();
+        assert_eq!(format!("{input}\r\n"), output);
+        msg
+    }
+
+    #[test]
+    fn nick() {
+        let msg: Nick = roundtrip("NICK alice");
+        assert_eq!("alice", msg.nickname);
+
+        let msg = Nick::from_str(":old!u@h NICK :alice").unwrap();
+        assert_eq!("alice", msg.nickname);
+
+        assert!(Nick::from_str("NICK").unwrap_err().is_not_found_param());
+    }
+
+    #[test]
+    fn user() {
+        let msg: User = roundtrip("USER alice 0 * :Alice Liddell");
+        assert_eq!("alice", msg.username);
+        assert_eq!("Alice Liddell", msg.realname);
+
+        let msg = User::from_str("USER alice 8 * alice").unwrap();
+        assert_eq!("alice", msg.realname);
+    }
+
+    #[test]
+    fn join() {
+        let msg: Join = roundtrip("JOIN #a,#b,#c key1,key2");
+        assert_eq!(msg.channels, ["#a", "#b", "#c"]);
+        assert_eq!(
+            msg.pairs().collect::<Vec<_>>(),
+            [("#a", Some("key1")), ("#b", Some("key2")), ("#c", None)]
+        );
+
+        let msg: Join = roundtrip("JOIN #rust");
+        assert!(msg.keys.is_empty());
+        assert!(!msg.is_leave_all());
+
+        let msg = Join::from_str(":nick!u@h JOIN :#rust").unwrap();
+        assert_eq!(msg.channels, ["#rust"]);
+
+        assert!(Join::from_str("JOIN 0").unwrap().is_leave_all());
+    }
+
+    #[test]
+    fn part_kick_topic() {
+        let msg: Part = roundtrip("PART #a,#b :see you");
+        assert_eq!(msg.channels, ["#a", "#b"]);
+        assert_eq!(Some("see you"), msg.reason);
+        assert_eq!(None, Part::from_str("PART #a").unwrap().reason);
+
+        let msg: Kick = roundtrip("KICK #rust bob :spam");
+        assert_eq!(
+            ("#rust", "bob", Some("spam")),
+            (msg.channel, msg.user, msg.comment)
+        );
+        assert_eq!(None, Kick::from_str("KICK #rust bob").unwrap().comment);
+
+        assert_eq!(None, Topic::from_str("TOPIC #rust").unwrap().topic);
+        let msg: Topic = roundtrip("TOPIC #rust :");
+        assert_eq!(Some(""), msg.topic);
+        let msg: Topic = roundtrip("TOPIC #rust :Rust talk");
+        assert_eq!(Some("Rust talk"), msg.topic);
+    }
+
+    #[test]
+    fn queries() {
+        let msg: Names = roundtrip("NAMES");
+        assert!(msg.channels.is_empty());
+        let msg: Names = roundtrip("NAMES #a,#b");
+        assert_eq!(msg.channels, ["#a", "#b"]);
+
+        let msg: List = roundtrip("LIST #a irc.example.com");
+        assert_eq!(Some("irc.example.com"), msg.target);
+
+        let names = Names {
+            channels: Vec::new(),
+            target: Some("irc.example.com"),
+        };
+        assert_eq!(
+            SerError::Param(ParamError::EmptyMiddle),
+            crate::to_message(&names).unwrap_err()
+        );
+        let list = List {
+            channels: Vec::new(),
+            target: Some("irc.example.com"),
+        };
+        assert_eq!(
+            SerError::Param(ParamError::EmptyMiddle),
+            crate::to_message(&list).unwrap_err()
+        );
+
+        let msg: Who = roundtrip("WHO #rust %cnuhraf,42");
+        assert_eq!(
+            (Some("#rust"), Some("%cnuhraf,42")),
+            (msg.mask, msg.options)
+        );
+        assert_eq!(None, Who::from_str("WHO").unwrap().mask);
+
+        let msg: Whois = roundtrip("WHOIS alice");
+        assert_eq!((None, vec!["alice"]), (msg.target, msg.masks));
+        let msg: Whois = roundtrip("WHOIS irc.example.com alice,bob");
+        assert_eq!(
+            (Some("irc.example.com"), vec!["alice", "bob"]),
+            (msg.target, msg.masks)
+        );
+    }
+
+    #[test]
+    fn mode() {
+        let msg: Mode = roundtrip("MODE #rust +ov alice bob");
+        assert_eq!("#rust", msg.target);
+        let modes = msg.modes.unwrap();
+        assert_eq!("+ov", modes.modestring);
+        assert_eq!(modes.args, ["alice", "bob"]);
+
+        let msg: Mode = roundtrip("MODE #rust +m");
+        assert!(msg.modes.unwrap().args.is_empty());
+
+        let msg: Mode = roundtrip("MODE alice");
+        assert_eq!(None, msg.modes);
+    }
+
+    #[test]
+    fn messages() {
+        let msg: PrivMsg = roundtrip("PRIVMSG #rust :hello world");
+        assert_eq!(("#rust", "hello world"), (msg.target, msg.text));
+
+        let msg = PrivMsg::from_str("PRIVMSG #rust hello").unwrap();
+        assert_eq!("hello", msg.text);
+
+        let msg: Notice = roundtrip("NOTICE alice :hi");
+        assert_eq!("hi", msg.text);
+
+        assert!(PrivMsg::from_str("NOTICE alice :hi")
+            .unwrap_err()
+            .is_command_mismatch());
+    }
+
+    #[test]
+    fn ping_pong() {
+        let ping: Ping = roundtrip("PING token");
+        assert_eq!("token", ping.token);
+        assert_eq!(
+            "PONG token\r\n",
+            crate::to_message(&Pong::reply(&ping)).unwrap()
+        );
+
+        let ping = Ping::from_str("PING :irc.example.com").unwrap();
+        assert_eq!("irc.example.com", ping.token);
+
+        let pong: Pong = roundtrip("PONG irc.example.com token");
+        assert_eq!(
+            (Some("irc.example.com"), "token"),
+            (pong.server, pong.token)
+        );
+
+        let ping: Ping = roundtrip("PING :two words");
+        assert_eq!("two words", ping.token);
+    }
+
+    #[test]
+    fn quit_away() {
+        assert_eq!(None, roundtrip::<Quit>("QUIT").reason);
+        assert_eq!(Some("bye"), roundtrip::<Quit>("QUIT :bye").reason);
+
+        assert_eq!(None, roundtrip::<Away>("AWAY").message);
+        let away: Away = roundtrip("AWAY :");
+        assert_eq!(Some(""), away.message);
+        assert!(!away.is_away());
+        let away: Away = roundtrip("AWAY :lunch");
+        assert_eq!(Some("lunch"), away.message);
+        assert!(away.is_away());
+    }
+
+    #[test]
+    fn any_client_command() {
+        let lines = [
+            "NICK alice",
+            "USER alice 0 * :Alice",
+            "PASS secret",
+            "OPER admin secret",
+            "JOIN #a,#b",
+            "PART #a",
+            "KICK #a bob",
+            "TOPIC #a",
+            "INVITE bob #a",
+            "NAMES #a",
+            "LIST",
+            "WHO #a",
+            "WHOIS bob",
+            "MODE #a +m",
+            "PRIVMSG #a :hi",
+            "NOTICE #a :hi",
+            "PING token",
+            "PONG token",
+            "QUIT :bye",
+            "AWAY :lunch",
+        ];
+
+        for line in lines {
+            let cmd: AnyClientCommand = roundtrip(line);
+            assert!(line.starts_with(cmd.command().as_str()));
+        }
+
+        assert_eq!(AnyClientCommand::COMMANDS.len(), lines.len());
+
+        let cmd = AnyClientCommand::from_str("join #rust").unwrap();
+        assert!(matches!(cmd, AnyClientCommand::Join(_)));
+
+        let err = AnyClientCommand::from_str("TAGMSG #rust").unwrap_err();
+        assert!(err.is_not_found_command());
+    }
+}
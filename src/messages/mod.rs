@@ -0,0 +1,157 @@
+//! Ready-made message types.
+//!
+//! Every message type implements [`FromMessage`](crate::de::FromMessage). The
+//! [`client`] commands also implement [`ToMessage`](crate::ser::ToMessage), so
+//! the same struct can be used to read a line and to write one; [`replies`]
+//! are only parsed, and are built with [`Reply`](replies::Reply) instead.
+//!
+//! - [`client`] - commands sent from a client to a server (RFC 2812)
+//! - [`replies`] - numeric replies sent from a server to a client
+//! - [`split`] - splitting long text into several messages
+//!
+//! ```rust
+//! use ircv3_parse::messages::client::{AnyClientCommand, Join};
+//!
+//! let join: Join = ircv3_parse::from_str("JOIN #rust,#irc key")?;
+//! assert_eq!(join.channels, ["#rust", "#irc"]);
+//! assert_eq!(join.keys, ["key"]);
+//!
+//! let cmd: AnyClientCommand = ircv3_parse::from_str("PRIVMSG #rust :hello")?;
+//! assert!(matches!(cmd, AnyClientCommand::PrivMsg(_)));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod client;
+pub mod replies;
+pub mod split;
+
+use crate::compat::{format, String, ToString, Vec};
+
+use core::str::FromStr;
+
+use crate::{
+    components::Params, ser::MessageSerializer, validators, Commands, DeError, Message, SerError,
+};
+
+/// Maximum length of a message in bytes, including CRLF and excluding tags.
+pub const MAX_LINE_LEN: usize = 512;
+
+#[inline]
+pub(crate) fn expect_command(msg: &Message<'_>, expected: Commands<'_>) -> Result<(), DeError> {
+    let command = msg.command();
+    if command != expected {
+        return Err(DeError::command_mismatch(
+            expected.as_str(),
+            command.as_str(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub(crate) fn required<'a>(params: &Params<'a>, index: usize) -> Result<&'a str, DeError> {
+    params
+        .get(index)
+        .ok_or_else(|| DeError::not_found_param(index))
+}
+
+/// Parses the parameter at `index`, `expected` describes the value for the error.
+#[inline]
+fn parse_param<T: FromStr>(
+    params: &Params<'_>,
+    index: usize,
+    expected: &str,
+) -> Result<T, DeError> {
+    let value = required(params, index)?;
+    value.parse().map_err(|_| {
+        DeError::not_found_with_context(
+            "param",
+            index.to_string(),
+            format!("expected {expected}, got `{value}`"),
+        )
+    })
+}
+
+/// Splits a comma-separated list, skipping empty entries.
+#[inline]
+fn split_list(value: &str) -> Vec<&str> {
+    value.split(',').filter(|s| !s.is_empty()).collect()
+}
+
+#[inline]
+fn join_list(values: &[&str]) -> String {
+    values.join(",")
+}
+
+/// Writes the last parameter of a message.
+///
+/// Uses a plain middle parameter when possible and falls back to the trailing
+/// parameter for empty values or values containing spaces or a `:`.
+fn push_last<S: MessageSerializer>(serialize: &mut S, value: &str) -> Result<(), SerError> {
+    if validators::param(value).is_ok() {
+        serialize.params().push(value)?;
+    } else {
+        serialize.set_trailing(value)?;
+    }
+
+    Ok(())
+}
+
+/// Groups `items` so the space-joined items of each group fit in `budget` bytes.
+///
+/// An item that is longer than `budget` on its own gets a group of its own.
+pub(crate) fn chunk<'i, 'a>(
+    items: &'i [&'a str],
+    budget: usize,
+    max_items: usize,
+) -> Vec<&'i [&'a str]> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut len = 0;
+
+    for (i, item) in items.iter().enumerate() {
+        let count = i - start;
+        let added = if count == 0 {
+            item.len()
+        } else {
+            1 + item.len()
+        };
+
+        if count > 0 && (len + added > budget || count >= max_items) {
+            chunks.push(&items[start..i]);
+            start = i;
+            len = item.len();
+        } else {
+            len += added;
+        }
+    }
+
+    if start < items.len() {
+        chunks.push(&items[start..]);
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chunk;
+
+    #[test]
+    fn chunk_budget() {
+        assert_eq!(
+            chunk(&["aa", "bb", "cc"], 5, usize::MAX),
+            [&["aa", "bb"][..], &["cc"][..]]
+        );
+        assert_eq!(
+            chunk(&["toolong", "a"], 3, usize::MAX),
+            [&["toolong"][..], &["a"][..]]
+        );
+        assert_eq!(
+            chunk(&["a", "b", "c"], 100, 2),
+            [&["a", "b"][..], &["c"][..]]
+        );
+        assert!(chunk(&[], 10, 1).is_empty());
+    }
+}
@@ -0,0 +1,453 @@
+//! Numeric replies sent from a server to a client.
+//!
+//! Every numeric starts with a `<client>` parameter, the nickname (or `*`) the
+//! reply is addressed to. It is kept as the `client` field of each type.
+//!
+//! ```rust
+//! use ircv3_parse::messages::replies::{ChannelSymbol, NamReply};
+//!
+//! let input = ":irc.example.com 353 alice = #rust :@bob +carol dave";
+//! let reply: NamReply = ircv3_parse::from_str(input)?;
+//!
+//! assert_eq!(ChannelSymbol::Public, reply.symbol);
+//! assert_eq!("#rust", reply.channel);
+//!
+//! let ops: Vec<_> = reply
+//!     .members()
+//!     .filter(|member| member.prefixes.contains('@'))
+//!     .map(|member| member.nick)
+//!     .collect();
+//! assert_eq!(ops, ["bob"]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod reply;
+
+pub use reply::Reply;
+
+use crate::compat::{format, Display, FmtResult, Formatter, Vec};
+
+use crate::{components::Numeric, de::FromMessage, DeError, Message};
+
+use super::{expect_command, parse_param, required};
+
+/// Membership prefixes used when the server did not advertise `PREFIX`,
+/// ordered from highest to lowest.
+pub const DEFAULT_PREFIXES: &str = "~&@%+";
+
+/// Channel type symbol of [`NamReply`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelSymbol {
+    /// `=`
+    Public,
+    /// `@`
+    Secret,
+    /// `*`
+    Private,
+}
+
+impl ChannelSymbol {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '=' => Some(Self::Public),
+            '@' => Some(Self::Secret),
+            '*' => Some(Self::Private),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::Public => '=',
+            Self::Secret => '@',
+            Self::Private => '*',
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Public => "=",
+            Self::Secret => "@",
+            Self::Private => "*",
+        }
+    }
+}
+
+impl Display for ChannelSymbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A member listed in [`NamReply`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Member<'a> {
+    /// All membership prefixes (`multi-prefix` may send several), highest first.
+    pub prefixes: &'a str,
+    pub nick: &'a str,
+    /// Present with the `userhost-in-names` capability.
+    pub user: Option<&'a str>,
+    /// Present with the `userhost-in-names` capability.
+    pub host: Option<&'a str>,
+}
+
+impl<'a> Member<'a> {
+    /// Parses a single `[prefixes]nick[!user@host]` entry.
+    ///
+    /// `prefixes` lists the prefix characters the server uses, see [`DEFAULT_PREFIXES`].
+    pub fn parse(entry: &'a str, prefixes: &str) -> Self {
+        let split = entry.find(|c| !prefixes.contains(c)).unwrap_or(entry.len());
+        let (prefix, rest) = entry.split_at(split);
+
+        let (rest, host) = match rest.split_once('@') {
+            Some((rest, host)) => (rest, Some(host)),
+            None => (rest, None),
+        };
+        let (nick, user) = match rest.split_once('!') {
+            Some((nick, user)) => (nick, Some(user)),
+            None => (rest, None),
+        };
+
+        Self {
+            prefixes: prefix,
+            nick,
+            user,
+            host,
+        }
+    }
+
+    /// Returns the highest membership prefix.
+    pub fn highest_prefix(&self) -> Option<char> {
+        self.prefixes.chars().next()
+    }
+}
+
+/// `RPL_NAMREPLY` (353): `<client> <symbol> <channel> :[prefix]<nick>{ [prefix]<nick>}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamReply<'a> {
+    pub client: &'a str,
+    pub symbol: ChannelSymbol,
+    pub channel: &'a str,
+    /// The raw, space-separated member list.
+    pub names: &'a str,
+}
+
+impl<'a> NamReply<'a> {
+    /// Returns the members using [`DEFAULT_PREFIXES`].
+    pub fn members(&self) -> impl Iterator<Item = Member<'a>> {
+        self.members_with_prefixes(DEFAULT_PREFIXES)
+    }
+
+    /// Returns the members using the prefix characters from `ISUPPORT PREFIX`.
+    pub fn members_with_prefixes<'p>(
+        &self,
+        prefixes: &'p str,
+    ) -> impl Iterator<Item = Member<'a>> + 'p
+    where
+        'a: 'p,
+    {
+        let names = self.names;
+        names
+            .split(' ')
+            .filter(|s| !s.is_empty())
+            .map(move |entry| Member::parse(entry, prefixes))
+    }
+}
+
+impl<'a> FromMessage<'a> for NamReply<'a> {
+    const COMMAND: Option<&'static str> = Some("353");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Numeric::RPL_NAMREPLY.into())?;
+        let params = msg.params();
+
+        let symbol = required(&params, 1)?;
+        let symbol = single_char(symbol)
+            .and_then(ChannelSymbol::from_char)
+            .ok_or_else(|| {
+                DeError::not_found_with_context(
+                    "param",
+                    "1",
+                    format!("expected one of `=`, `@`, `*`, got `{symbol}`"),
+                )
+            })?;
+
+        Ok(Self {
+            client: required(&params, 0)?,
+            symbol,
+            channel: required(&params, 2)?,
+            names: params.get(3).unwrap_or_default(),
+        })
+    }
+}
+
+/// `RPL_WHOREPLY` (352):
+/// `<client> <channel> <username> <host> <server> <nick> <flags> :<hopcount> <realname>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhoReply<'a> {
+    pub client: &'a str,
+    /// `None` when the server sent `*`.
+    pub channel: Option<&'a str>,
+    pub username: &'a str,
+    pub host: &'a str,
+    pub server: &'a str,
+    pub nick: &'a str,
+    /// `H` (here) or `G` (gone), optionally followed by `*` and membership prefixes.
+    pub flags: &'a str,
+    pub hopcount: u32,
+    pub realname: &'a str,
+}
+
+impl<'a> WhoReply<'a> {
+    pub fn is_away(&self) -> bool {
+        self.flags.starts_with('G')
+    }
+
+    pub fn is_oper(&self) -> bool {
+        self.flags.contains('*')
+    }
+
+    /// Returns the membership prefixes using [`DEFAULT_PREFIXES`].
+    pub fn prefixes(&self) -> &'a str {
+        let flags = self.flags;
+        let start = flags.find(|c| DEFAULT_PREFIXES.contains(c));
+        match start {
+            Some(start) => {
+                let rest = &flags[start..];
+                let end = rest
+                    .find(|c| !DEFAULT_PREFIXES.contains(c))
+                    .unwrap_or(rest.len());
+                &rest[..end]
+            }
+            None => "",
+        }
+    }
+}
+
+impl<'a> FromMessage<'a> for WhoReply<'a> {
+    const COMMAND: Option<&'static str> = Some("352");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Numeric::RPL_WHOREPLY.into())?;
+        let params = msg.params();
+
+        let last = required(&params, 7)?;
+        let (hopcount, realname) = last.split_once(' ').unwrap_or((last, ""));
+        let hopcount = hopcount.parse().map_err(|_| {
+            DeError::not_found_with_context(
+                "param",
+                "7",
+                format!("expected a hopcount, got `{hopcount}`"),
+            )
+        })?;
+
+        Ok(Self {
+            client: required(&params, 0)?,
+            channel: Some(required(&params, 1)?).filter(|c| *c != "*"),
+            username: required(&params, 2)?,
+            host: required(&params, 3)?,
+            server: required(&params, 4)?,
+            nick: required(&params, 5)?,
+            flags: required(&params, 6)?,
+            hopcount,
+            realname,
+        })
+    }
+}
+
+/// `RPL_TOPIC` (332): `<client> <channel> :<topic>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicReply<'a> {
+    pub client: &'a str,
+    pub channel: &'a str,
+    pub topic: &'a str,
+}
+
+impl<'a> FromMessage<'a> for TopicReply<'a> {
+    const COMMAND: Option<&'static str> = Some("332");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Numeric::RPL_TOPIC.into())?;
+        let params = msg.params();
+
+        Ok(Self {
+            client: required(&params, 0)?,
+            channel: required(&params, 1)?,
+            topic: params.get(2).unwrap_or_default(),
+        })
+    }
+}
+
+/// `RPL_TOPICWHOTIME` (333): `<client> <channel> <setter> <setat>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicWhoTime<'a> {
+    pub client: &'a str,
+    pub channel: &'a str,
+    /// A nickname or a full `nick!user@host` mask.
+    pub setter: &'a str,
+    /// Unix timestamp in seconds.
+    pub set_at: u64,
+}
+
+impl<'a> FromMessage<'a> for TopicWhoTime<'a> {
+    const COMMAND: Option<&'static str> = Some("333");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Numeric::RPL_TOPICWHOTIME.into())?;
+        let params = msg.params();
+
+        Ok(Self {
+            client: required(&params, 0)?,
+            channel: required(&params, 1)?,
+            setter: required(&params, 2)?,
+            set_at: parse_param(&params, 3, "a timestamp")?,
+        })
+    }
+}
+
+/// `RPL_WHOISUSER` (311): `<client> <nick> <username> <host> * :<realname>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhoisUser<'a> {
+    pub client: &'a str,
+    pub nick: &'a str,
+    pub username: &'a str,
+    pub host: &'a str,
+    pub realname: &'a str,
+}
+
+impl<'a> FromMessage<'a> for WhoisUser<'a> {
+    const COMMAND: Option<&'static str> = Some("311");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Numeric::RPL_WHOISUSER.into())?;
+        let params = msg.params();
+
+        Ok(Self {
+            client: required(&params, 0)?,
+            nick: required(&params, 1)?,
+            username: required(&params, 2)?,
+            host: required(&params, 3)?,
+            realname: required(&params, 5)?,
+        })
+    }
+}
+
+/// `RPL_LIST` (322): `<client> <channel> <visible> :<topic>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListReply<'a> {
+    pub client: &'a str,
+    pub channel: &'a str,
+    /// Number of visible users.
+    pub visible: u32,
+    pub topic: &'a str,
+}
+
+impl<'a> FromMessage<'a> for ListReply<'a> {
+    const COMMAND: Option<&'static str> = Some("322");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Numeric::RPL_LIST.into())?;
+        let params = msg.params();
+
+        Ok(Self {
+            client: required(&params, 0)?,
+            channel: required(&params, 1)?,
+            visible: parse_param(&params, 2, "a user count")?,
+            topic: params.get(3).unwrap_or_default(),
+        })
+    }
+}
+
+/// `RPL_BANLIST` (367): `<client> <channel> <mask> [<who> <set-ts>]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BanListEntry<'a> {
+    pub client: &'a str,
+    pub channel: &'a str,
+    pub mask: &'a str,
+    pub setter: Option<&'a str>,
+    /// Unix timestamp in seconds.
+    pub set_at: Option<u64>,
+}
+
+impl<'a> FromMessage<'a> for BanListEntry<'a> {
+    const COMMAND: Option<&'static str> = Some("367");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Numeric::RPL_BANLIST.into())?;
+        let params = msg.params();
+
+        let set_at = match params.get(4) {
+            Some(_) => Some(parse_param(&params, 4, "a timestamp")?),
+            None => None,
+        };
+
+        Ok(Self {
+            client: required(&params, 0)?,
+            channel: required(&params, 1)?,
+            mask: required(&params, 2)?,
+            setter: params.get(3),
+            set_at,
+        })
+    }
+}
+
+/// Any error numeric: `<client> [<args>...] :<description>`
+///
+/// Known numerics count as errors if their name starts with `ERR_`, unknown
+/// ones if they are in `400`-`599`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorReply<'a> {
+    /// The three-digit code.
+    pub code: &'a str,
+    pub client: &'a str,
+    /// Parameters between the client and the description.
+    pub args: Vec<&'a str>,
+    /// Human-readable text, sent as the trailing parameter.
+    pub description: Option<&'a str>,
+}
+
+impl ErrorReply<'_> {
+    /// Returns the named [`Numeric`], if known.
+    pub fn numeric(&self) -> Option<Numeric> {
+        Numeric::from_code_str(self.code)
+    }
+}
+
+impl<'a> FromMessage<'a> for ErrorReply<'a> {
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        let command = msg.command();
+        let code = command.as_str();
+        // Known numerics go by name, which covers errors outside 400-599
+        // such as `ERR_SASLFAIL` (904).
+        let is_error = match Numeric::from_code_str(code) {
+            Some(numeric) => numeric.is_error(),
+            None => matches!(code.parse::<u16>(), Ok(400..=599)),
+        };
+        if !is_error {
+            return Err(DeError::command_mismatch("ERR_*", code));
+        }
+
+        let params = msg.params();
+        let mut middles = params.middles.iter();
+        let client = middles
+            .next()
+            .or(params.trailing.raw())
+            .ok_or_else(|| DeError::not_found_param(0))?;
+
+        Ok(Self {
+            code,
+            client,
+            args: middles.collect(),
+            description: params.trailing.raw().filter(|_| params.middles.count() > 0),
+        })
+    }
+}
+
+#[inline]
+fn single_char(value: &str) -> Option<char> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
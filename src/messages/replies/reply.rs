@@ -0,0 +1,525 @@
+use crate::compat::{format, Vec};
+
+use crate::{components::Numeric, Commands, MessageBuilder, SerError};
+
+use super::ChannelSymbol;
+use crate::messages::{chunk, MAX_LINE_LEN};
+
+/// Most tokens sent in a single `RPL_ISUPPORT` line.
+const ISUPPORT_MAX_TOKENS: usize = 13;
+
+/// Constructors for numeric replies sent by a server.
+///
+/// Every reply gets the server as its source and `client` as the first parameter,
+/// followed by the parameters of the numeric and its standard description.
+/// Lists that may not fit a single line are split so each line stays within
+/// [`MAX_LINE_LEN`] bytes, tags excluded; an item that does not fit even on a
+/// line of its own is a [`SerError::LineTooLong`].
+///
+/// The returned builders can still be extended, e.g. with a `label` tag.
+///
+/// ```rust
+/// use ircv3_parse::messages::replies::Reply;
+///
+/// let reply = Reply::nick_in_use("irc.example.com", "*", "alice")?;
+/// assert_eq!(
+///     ":irc.example.com 433 * alice :Nickname is already in use\r\n",
+///     reply.build()?
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct Reply;
+
+impl Reply {
+    /// Starts a reply with the source, numeric and `client` parameter set.
+    pub fn numeric(
+        server: &str,
+        client: &str,
+        numeric: Numeric,
+    ) -> Result<MessageBuilder, SerError> {
+        let mut builder = MessageBuilder::new();
+        builder
+            .set_source_name(server)?
+            .set_command(Commands::from(numeric))?
+            .add_param(client)?;
+        Ok(builder)
+    }
+
+    /// `RPL_WELCOME` (001)
+    pub fn welcome(server: &str, nick: &str, network: &str) -> Result<MessageBuilder, SerError> {
+        let mut builder = Self::numeric(server, nick, Numeric::RPL_WELCOME)?;
+        builder.set_trailing(&format!("Welcome to the {network} Network, {nick}"))?;
+        Ok(builder)
+    }
+
+    /// `RPL_YOURHOST` (002)
+    pub fn your_host(server: &str, nick: &str, version: &str) -> Result<MessageBuilder, SerError> {
+        let mut builder = Self::numeric(server, nick, Numeric::RPL_YOURHOST)?;
+        builder.set_trailing(&format!("Your host is {server}, running version {version}"))?;
+        Ok(builder)
+    }
+
+    /// `RPL_CREATED` (003)
+    pub fn created(server: &str, nick: &str, date: &str) -> Result<MessageBuilder, SerError> {
+        let mut builder = Self::numeric(server, nick, Numeric::RPL_CREATED)?;
+        builder.set_trailing(&format!("This server was created {date}"))?;
+        Ok(builder)
+    }
+
+    /// `RPL_MYINFO` (004)
+    pub fn my_info(
+        server: &str,
+        nick: &str,
+        version: &str,
+        user_modes: &str,
+        channel_modes: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        let mut builder = Self::numeric(server, nick, Numeric::RPL_MYINFO)?;
+        builder.add_params([server, version, user_modes, channel_modes])?;
+        Ok(builder)
+    }
+
+    /// `RPL_ISUPPORT` (005), split into as many lines as needed.
+    ///
+    /// Each line holds at most 13 tokens.
+    pub fn isupport(
+        server: &str,
+        nick: &str,
+        tokens: &[&str],
+    ) -> Result<Vec<MessageBuilder>, SerError> {
+        const TEXT: &str = "are supported by this server";
+
+        // ":server 005 nick" + " :" + text + CRLF
+        let fixed = line_len(server, Numeric::RPL_ISUPPORT, &[nick]) + 2 + TEXT.len() + 2;
+
+        chunk(tokens, budget(fixed, tokens)?, ISUPPORT_MAX_TOKENS)
+            .into_iter()
+            .map(|tokens| {
+                let mut builder = Self::numeric(server, nick, Numeric::RPL_ISUPPORT)?;
+                builder.add_params(tokens)?.set_trailing(TEXT)?;
+                Ok(builder)
+            })
+            .collect()
+    }
+
+    /// `RPL_NAMREPLY` (353) lines followed by `RPL_ENDOFNAMES` (366).
+    ///
+    /// `members` are sent as given, including their membership prefixes.
+    pub fn names(
+        server: &str,
+        client: &str,
+        symbol: ChannelSymbol,
+        channel: &str,
+        members: &[&str],
+    ) -> Result<Vec<MessageBuilder>, SerError> {
+        // ":server 353 client symbol channel" + " :" + CRLF
+        let fixed = line_len(
+            server,
+            Numeric::RPL_NAMREPLY,
+            &[client, symbol.as_str(), channel],
+        ) + 2
+            + 2;
+
+        let mut replies = chunk(members, budget(fixed, members)?, usize::MAX)
+            .into_iter()
+            .map(|members| {
+                let mut builder = Self::numeric(server, client, Numeric::RPL_NAMREPLY)?;
+                builder
+                    .add_params([symbol.as_str(), channel])?
+                    .set_trailing(&members.join(" "))?;
+                Ok(builder)
+            })
+            .collect::<Result<Vec<_>, SerError>>()?;
+
+        replies.push(Self::end_of_names(server, client, channel)?);
+        Ok(replies)
+    }
+
+    /// `RPL_ENDOFNAMES` (366)
+    pub fn end_of_names(
+        server: &str,
+        client: &str,
+        channel: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::RPL_ENDOFNAMES,
+            &[channel],
+            "End of /NAMES list",
+        )
+    }
+
+    /// `RPL_NOTOPIC` (331)
+    pub fn no_topic(server: &str, client: &str, channel: &str) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::RPL_NOTOPIC,
+            &[channel],
+            "No topic is set",
+        )
+    }
+
+    /// `RPL_TOPIC` (332)
+    pub fn topic(
+        server: &str,
+        client: &str,
+        channel: &str,
+        topic: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        Self::with_args(server, client, Numeric::RPL_TOPIC, &[channel], topic)
+    }
+
+    /// `RPL_TOPICWHOTIME` (333), `set_at` is a Unix timestamp in seconds.
+    pub fn topic_who_time(
+        server: &str,
+        client: &str,
+        channel: &str,
+        setter: &str,
+        set_at: u64,
+    ) -> Result<MessageBuilder, SerError> {
+        let mut builder = Self::numeric(server, client, Numeric::RPL_TOPICWHOTIME)?;
+        builder.add_params([channel, setter, &format!("{set_at}")])?;
+        Ok(builder)
+    }
+
+    /// `RPL_MOTDSTART` (375), one `RPL_MOTD` (372) per line and `RPL_ENDOFMOTD` (376).
+    pub fn motd(
+        server: &str,
+        client: &str,
+        lines: &[&str],
+    ) -> Result<Vec<MessageBuilder>, SerError> {
+        let mut replies = Vec::with_capacity(lines.len() + 2);
+
+        let mut start = Self::numeric(server, client, Numeric::RPL_MOTDSTART)?;
+        start.set_trailing(&format!("- {server} Message of the day - "))?;
+        replies.push(start);
+
+        for line in lines {
+            let mut motd = Self::numeric(server, client, Numeric::RPL_MOTD)?;
+            motd.set_trailing(&format!("- {line}"))?;
+            replies.push(motd);
+        }
+
+        let mut end = Self::numeric(server, client, Numeric::RPL_ENDOFMOTD)?;
+        end.set_trailing("End of /MOTD command.")?;
+        replies.push(end);
+
+        Ok(replies)
+    }
+
+    /// `ERR_NOSUCHNICK` (401)
+    pub fn no_such_nick(
+        server: &str,
+        client: &str,
+        nick: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_NOSUCHNICK,
+            &[nick],
+            "No such nick/channel",
+        )
+    }
+
+    /// `ERR_NOSUCHCHANNEL` (403)
+    pub fn no_such_channel(
+        server: &str,
+        client: &str,
+        channel: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_NOSUCHCHANNEL,
+            &[channel],
+            "No such channel",
+        )
+    }
+
+    /// `ERR_UNKNOWNCOMMAND` (421)
+    pub fn unknown_command(
+        server: &str,
+        client: &str,
+        command: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_UNKNOWNCOMMAND,
+            &[command],
+            "Unknown command",
+        )
+    }
+
+    /// `ERR_ERRONEUSNICKNAME` (432)
+    pub fn erroneous_nickname(
+        server: &str,
+        client: &str,
+        nick: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_ERRONEUSNICKNAME,
+            &[nick],
+            "Erroneous nickname",
+        )
+    }
+
+    /// `ERR_NICKNAMEINUSE` (433)
+    pub fn nick_in_use(server: &str, client: &str, nick: &str) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_NICKNAMEINUSE,
+            &[nick],
+            "Nickname is already in use",
+        )
+    }
+
+    /// `ERR_NOTONCHANNEL` (442)
+    pub fn not_on_channel(
+        server: &str,
+        client: &str,
+        channel: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_NOTONCHANNEL,
+            &[channel],
+            "You're not on that channel",
+        )
+    }
+
+    /// `ERR_NOTREGISTERED` (451)
+    pub fn not_registered(server: &str, client: &str) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_NOTREGISTERED,
+            &[],
+            "You have not registered",
+        )
+    }
+
+    /// `ERR_NEEDMOREPARAMS` (461)
+    pub fn need_more_params(
+        server: &str,
+        client: &str,
+        command: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_NEEDMOREPARAMS,
+            &[command],
+            "Not enough parameters",
+        )
+    }
+
+    /// `ERR_ALREADYREGISTERED` (462)
+    pub fn already_registered(server: &str, client: &str) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_ALREADYREGISTERED,
+            &[],
+            "You may not reregister",
+        )
+    }
+
+    /// `ERR_CHANOPRIVSNEEDED` (482)
+    pub fn chanop_privs_needed(
+        server: &str,
+        client: &str,
+        channel: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        Self::with_args(
+            server,
+            client,
+            Numeric::ERR_CHANOPRIVSNEEDED,
+            &[channel],
+            "You're not channel operator",
+        )
+    }
+
+    fn with_args(
+        server: &str,
+        client: &str,
+        numeric: Numeric,
+        args: &[&str],
+        text: &str,
+    ) -> Result<MessageBuilder, SerError> {
+        let mut builder = Self::numeric(server, client, numeric)?;
+        builder.add_params(args)?.set_trailing(text)?;
+        Ok(builder)
+    }
+}
+
+/// Length of `:server NNN param...` without the trailing parameter and CRLF.
+fn line_len(server: &str, numeric: Numeric, params: &[&str]) -> usize {
+    1 + server.len()
+        + 1
+        + numeric.code_str().len()
+        + params.iter().map(|p| 1 + p.len()).sum::<usize>()
+}
+
+/// Bytes left for list items after `fixed`, if every item fits a line alone.
+fn budget(fixed: usize, items: &[&str]) -> Result<usize, SerError> {
+    let budget = MAX_LINE_LEN.saturating_sub(fixed);
+    match items.iter().map(|item| item.len()).max() {
+        Some(longest) if longest > budget => Err(SerError::LineTooLong {
+            max: MAX_LINE_LEN,
+            actual: fixed + longest,
+        }),
+        _ => Ok(budget),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(replies: Vec<MessageBuilder>) -> Vec<String> {
+        replies
+            .into_iter()
+            .map(|r| String::from_utf8(r.build().unwrap().to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn registration() {
+        let welcome = Reply::welcome("irc.example.com", "alice", "ExampleNet").unwrap();
+        assert_eq!(
+            ":irc.example.com 001 alice :Welcome to the ExampleNet Network, alice\r\n",
+            welcome.build().unwrap()
+        );
+
+        let info =
+            Reply::my_info("irc.example.com", "alice", "ircd-1.0", "iow", "beIklmnst").unwrap();
+        assert_eq!(
+            ":irc.example.com 004 alice irc.example.com ircd-1.0 iow beIklmnst\r\n",
+            info.build().unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let reply = Reply::need_more_params("irc.example.com", "alice", "JOIN").unwrap();
+        assert_eq!(
+            ":irc.example.com 461 alice JOIN :Not enough parameters\r\n",
+            reply.build().unwrap()
+        );
+
+        let reply = Reply::not_registered("irc.example.com", "*").unwrap();
+        assert_eq!(
+            ":irc.example.com 451 * :You have not registered\r\n",
+            reply.build().unwrap()
+        );
+    }
+
+    #[test]
+    fn names_chunked() {
+        let members: Vec<String> = (0..200).map(|i| format!("@user{i:03}")).collect();
+        let members: Vec<&str> = members.iter().map(|s| s.as_str()).collect();
+
+        let replies = Reply::names(
+            "irc.example.com",
+            "alice",
+            ChannelSymbol::Public,
+            "#big",
+            &members,
+        )
+        .unwrap();
+        let lines = lines(replies);
+
+        assert!(lines.len() > 2);
+        assert_eq!(
+            ":irc.example.com 366 alice #big :End of /NAMES list\r\n",
+            lines.last().unwrap()
+        );
+
+        let mut seen = Vec::new();
+        for line in &lines[..lines.len() - 1] {
+            assert!(line.len() <= MAX_LINE_LEN, "{} bytes", line.len());
+            assert!(line.starts_with(":irc.example.com 353 alice = #big :"));
+            let names = line.split_once(" :").unwrap().1.trim_end();
+            seen.extend(names.split(' ').map(|s| s.to_string()));
+        }
+        assert_eq!(members, seen);
+
+        // all but the last line should be reasonably full
+        assert!(lines[0].len() > MAX_LINE_LEN - 10);
+    }
+
+    #[test]
+    fn too_long() {
+        let server = "s".repeat(600);
+        let err = Reply::isupport(&server, "alice", &["NICKLEN=16"]).unwrap_err();
+        assert!(err.is_line_too_long());
+
+        let err = Reply::names(&server, "alice", ChannelSymbol::Public, "#c", &["bob"]);
+        assert!(err.unwrap_err().is_line_too_long());
+
+        let member = "m".repeat(MAX_LINE_LEN);
+        let err = Reply::names(
+            "irc.example.com",
+            "alice",
+            ChannelSymbol::Public,
+            "#c",
+            &[&member],
+        );
+        assert!(err.unwrap_err().is_line_too_long());
+    }
+
+    #[test]
+    fn names_empty() {
+        let replies =
+            Reply::names("irc.example.com", "alice", ChannelSymbol::Secret, "#c", &[]).unwrap();
+        assert_eq!(1, replies.len());
+    }
+
+    #[test]
+    fn isupport_chunked() {
+        let tokens = [
+            "AWAYLEN=200",
+            "CASEMAPPING=rfc1459",
+            "CHANLIMIT=#:250",
+            "CHANMODES=IXZbegw,k,FJLdfjl,ACDKMNOPQRSTUcimnprstuz",
+            "CHANNELLEN=64",
+            "CHANTYPES=#",
+            "ELIST=CTU",
+            "ETRACE",
+            "EXCEPTS",
+            "EXTBAN=$,ajrxz",
+            "FNC",
+            "INVEX",
+            "KICKLEN=255",
+            "KNOCK",
+            "MAXLIST=bqeI:100",
+            "MONITOR=100",
+            "NETWORK=Libera.Chat",
+            "NICKLEN=16",
+            "PREFIX=(ov)@+",
+            "SAFELIST",
+        ];
+
+        let lines = lines(Reply::isupport("irc.example.com", "alice", &tokens).unwrap());
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with(":irc.example.com 005 alice AWAYLEN=200 "));
+        assert!(lines[0].ends_with(" KICKLEN=255 :are supported by this server\r\n"));
+        assert!(lines[1].starts_with(":irc.example.com 005 alice KNOCK "));
+
+        let long: Vec<String> = (0..40)
+            .map(|i| format!("TOKEN{i:02}={}", "x".repeat(30)))
+            .collect();
+        let long: Vec<&str> = long.iter().map(|s| s.as_str()).collect();
+        for line in self::lines(Reply::isupport("irc.example.com", "alice", &long).unwrap()) {
+            assert!(line.len() <= MAX_LINE_LEN);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! Splitting long text into several `PRIVMSG` or `NOTICE` lines.
+//!
+//! The server prepends `:nick!user@host ` when relaying a message, so the
+//! text of each line must leave room for it as well as for the command and
+//! target. Splits happen at spaces when possible and otherwise between
+//! grapheme clusters (characters without the `unicode` feature). Only a
+//! cluster that is longer than a whole line is split between its characters.
+//! Formatting such as bold or colors is restored at the start of every
+//! continuation line.
+//!
+//! ```rust
+//! use ircv3_parse::messages::split::Splitter;
+//!
+//! let splitter = Splitter::privmsg("#rust").source("ferris", "~ferris", "user/ferris");
+//! let text = "word ".repeat(200);
+//!
+//! let lines = splitter.split(&text)?;
+//! assert_eq!(3, lines.len());
+//!
+//! for line in lines {
+//!     let line = line.build()?;
+//!     assert!(line.len() + ":ferris!~ferris@user/ferris ".len() <= 512);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+#[cfg(feature = "unicode")]
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::MAX_LINE_LEN;
+use crate::{
+    compat::{String, Vec},
+    formatting::{self, Style},
+    Commands, MessageBuilder, SerError,
+};
+
+/// Length reserved for the source when it is unknown, enough for a 30 byte
+/// nick, a 10 byte username and a 63 byte host.
+pub const DEFAULT_SOURCE_LEN: usize = 1 + 30 + 1 + 10 + 1 + 63 + 1;
+
+/// Splits text into lines that fit in [`MAX_LINE_LEN`].
+///
+/// Tags are repeated on every line. They are limited separately from the rest
+/// of the message and do not count toward the line length.
+#[derive(Debug, Clone)]
+pub struct Splitter<'a> {
+    command: Commands<'a>,
+    target: &'a str,
+    tags: Vec<(&'a str, Option<&'a str>)>,
+    source_len: usize,
+    max_len: usize,
+}
+
+impl<'a> Splitter<'a> {
+    pub fn privmsg(target: &'a str) -> Self {
+        Self::new(Commands::PRIVMSG, target)
+    }
+
+    pub fn notice(target: &'a str) -> Self {
+        Self::new(Commands::NOTICE, target)
+    }
+
+    fn new(command: Commands<'a>, target: &'a str) -> Self {
+        Self {
+            command,
+            target,
+            tags: Vec::new(),
+            source_len: DEFAULT_SOURCE_LEN,
+            max_len: MAX_LINE_LEN,
+        }
+    }
+
+    /// Reserves room for `:nick!user@host `, as the server will send it.
+    pub fn source(mut self, nick: &str, user: &str, host: &str) -> Self {
+        self.source_len = 1 + nick.len() + 1 + user.len() + 1 + host.len() + 1;
+        self
+    }
+
+    /// Reserves `len` bytes for the source, including `:` and the space.
+    pub fn source_len(mut self, len: usize) -> Self {
+        self.source_len = len;
+        self
+    }
+
+    pub fn tag(mut self, key: &'a str, value: Option<&'a str>) -> Self {
+        self.tags.push((key, value));
+        self
+    }
+
+    /// Overrides the line limit, including CRLF.
+    pub fn max_len(mut self, len: usize) -> Self {
+        self.max_len = len;
+        self
+    }
+
+    /// Returns the number of bytes available for the text of each line.
+    pub fn budget(&self) -> usize {
+        // "<command> <target> :<text>\r\n"
+        let overhead = self.source_len + self.command.len() + 1 + self.target.len() + 2 + 2;
+        self.max_len.saturating_sub(overhead).max(1)
+    }
+
+    /// Splits `text` into the text of each line, see [`split_text()`].
+    pub fn split_text(&self, text: &str) -> Vec<String> {
+        split_text(text, self.budget())
+    }
+
+    /// Splits `text` into messages.
+    pub fn split(&self, text: &str) -> Result<Vec<MessageBuilder>, SerError> {
+        self.split_text(text)
+            .into_iter()
+            .map(|line| {
+                let mut builder = MessageBuilder::new();
+                builder.set_command(self.command)?;
+                for (key, value) in &self.tags {
+                    builder.add_tag(key, *value)?;
+                }
+                builder.add_param(self.target)?.set_trailing(&line)?;
+                Ok(builder)
+            })
+            .collect()
+    }
+}
+
+/// Splits `text` into pieces of at most `budget` bytes.
+///
+/// Each line of `text` is split on its own and empty lines are skipped.
+/// Pieces end at a space when possible; the space itself is dropped. Grapheme
+/// clusters longer than `budget` are split between characters.
+pub fn split_text(text: &str, budget: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        split_line(line, budget, &mut pieces);
+    }
+    pieces
+}
+
+/// A grapheme cluster and the style it is displayed with.
+struct Atom {
+    start: usize,
+    end: usize,
+    style: Style,
+    space: bool,
+}
+
+fn atoms(line: &str, budget: usize) -> Vec<Atom> {
+    let base = line.as_ptr() as usize;
+    let mut atoms = Vec::new();
+
+    for span in formatting::parse(line) {
+        let offset = span.text.as_ptr() as usize - base;
+
+        for (i, grapheme) in graphemes(span.text) {
+            let start = offset + i;
+
+            // A cluster that cannot fit a line is split between characters.
+            if grapheme.len() > budget {
+                atoms.extend(grapheme.char_indices().map(|(j, c)| Atom {
+                    start: start + j,
+                    end: start + j + c.len_utf8(),
+                    style: span.style,
+                    space: false,
+                }));
+            } else {
+                atoms.push(Atom {
+                    start,
+                    end: start + grapheme.len(),
+                    style: span.style,
+                    space: grapheme == " ",
+                });
+            }
+        }
+    }
+
+    atoms
+}
+
+#[cfg(feature = "unicode")]
+fn graphemes(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.grapheme_indices(true)
+}
+
+#[cfg(not(feature = "unicode"))]
+fn graphemes(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.char_indices()
+        .map(|(i, c)| (i, &text[i..i + c.len_utf8()]))
+}
+
+fn split_line(line: &str, budget: usize, pieces: &mut Vec<String>) {
+    let atoms = atoms(line, budget);
+    let mut i = 0;
+
+    loop {
+        while atoms.get(i).is_some_and(|a| a.space) {
+            i += 1;
+        }
+        let Some(first) = atoms.get(i) else {
+            break;
+        };
+
+        let codes = first.style.transition(&Style::default());
+        let mut piece = String::new();
+        formatting::push_codes(&mut piece, &codes, &line[first.start..]);
+
+        let mut end = i;
+        let mut last_space = None;
+        while let Some(atom) = atoms.get(end) {
+            if end > i && piece.len() + atom.end - first.start > budget {
+                break;
+            }
+            if atom.space {
+                last_space = Some(end);
+            }
+            end += 1;
+        }
+
+        let (stop, next) = match last_space {
+            Some(space) if end < atoms.len() => (space, space + 1),
+            _ => (end, end),
+        };
+
+        let text = &line[first.start..atoms[stop - 1].end];
+        piece.push_str(text.trim_end_matches(' '));
+        pieces.push(piece);
+
+        i = next;
+    }
+}
+
+/// Splits `line` into slices of at most `budget` bytes that concatenate back
+/// to `line`.
+///
+/// Slices end after a space when possible and formatting codes are left where
+/// they are, so each slice only makes sense as a continuation of the previous.
+pub(crate) fn split_raw(line: &str, budget: usize) -> Vec<&str> {
+    let atoms = atoms(line, budget);
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < atoms.len() {
+        let mut end = i;
+        let mut last_space = None;
+        while let Some(atom) = atoms.get(end) {
+            if end > i && atom.end - start > budget {
+                break;
+            }
+            if atom.space {
+                last_space = Some(end);
+            }
+            end += 1;
+        }
+
+        if end == atoms.len() {
+            break;
+        }
+
+        let stop = last_space.map_or(end, |space| space + 1);
+        let cut = atoms[stop - 1].end;
+        pieces.push(&line[start..cut]);
+        start = cut;
+        i = stop;
+    }
+
+    if start < line.len() || pieces.is_empty() {
+        pieces.push(&line[start..]);
+    }
+
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits() {
+        assert_eq!(["hello world"], split_text("hello world", 100).as_slice());
+        assert!(split_text("", 100).is_empty());
+        assert!(split_text("   ", 100).is_empty());
+    }
+
+    #[test]
+    fn word_boundaries() {
+        assert_eq!(
+            ["the quick", "brown fox", "jumps"],
+            split_text("the quick brown fox jumps", 10).as_slice()
+        );
+        assert_eq!(
+            ["abcdefghij", "klm end"],
+            split_text("abcdefghijklm end", 10).as_slice()
+        );
+        assert_eq!(
+            ["first", "second"],
+            split_text("first\r\n\nsecond", 10).as_slice()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn unicode() {
+        // Every piece is valid UTF-8 by construction, check the clusters.
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let text = format!("é{family}{family}é");
+        let pieces = split_text(&text, 20);
+        assert_eq!(["é", family, family, "é"].concat(), pieces.concat());
+        assert!(pieces.iter().all(|p| p.len() <= 20));
+        assert!(pieces.iter().any(|p| p.ends_with(family)));
+        assert!(pieces
+            .iter()
+            .all(|p| !p.starts_with('\u{200d}') && !p.ends_with('\u{200d}')));
+
+        // e + combining acute stays together.
+        let pieces = split_text("aaaae\u{301}", 6);
+        assert_eq!(["aaaa", "e\u{301}"], pieces.as_slice());
+
+        // Only a cluster longer than a line is split.
+        assert_eq!([family], split_text(family, family.len()).as_slice());
+        let pieces = split_text(family, 10);
+        assert!(pieces.len() > 1);
+        assert!(pieces.iter().all(|p| p.len() <= 10));
+        assert_eq!(family, pieces.concat());
+    }
+
+    #[test]
+    fn keeps_formatting() {
+        let pieces = split_text("\x02bold words \x0304red\x02 words here", 12);
+        assert_eq!(
+            [
+                "\x02bold words",
+                "\x02\x0304red",
+                "\x0304words",
+                "\x0304here"
+            ],
+            pieces.as_slice()
+        );
+
+        for piece in &pieces {
+            assert!(piece.len() <= 12);
+        }
+
+        let stripped: Vec<_> = pieces.iter().map(|p| formatting::strip(p)).collect();
+        assert_eq!(["bold words", "red", "words", "here"], stripped.as_slice());
+
+        // A color restored before a comma is guarded.
+        let pieces = split_text("\x0304aaaa ,5", 8);
+        assert_eq!(["\x0304aaaa", "\x0304\x02\x02,5"], pieces.as_slice());
+    }
+
+    #[test]
+    fn splitter() {
+        let splitter = Splitter::notice("nick")
+            .source("bot", "bot", "example.com")
+            .tag("+draft/reply", Some("abc"));
+        let source_len = ":bot!bot@example.com ".len();
+        assert_eq!(
+            MAX_LINE_LEN - source_len - "NOTICE nick :\r\n".len(),
+            splitter.budget()
+        );
+
+        let text = "lorem ipsum ".repeat(100);
+        let lines = splitter.split(&text).unwrap();
+        assert_eq!(3, lines.len());
+
+        let mut joined = Vec::new();
+        for line in lines {
+            let line = line.build().unwrap();
+            let line = core::str::from_utf8(&line).unwrap();
+            let (tags, rest) = line.split_once(' ').unwrap();
+            assert_eq!("@+draft/reply=abc", tags);
+            assert!(rest.len() + source_len <= MAX_LINE_LEN);
+
+            let msg = crate::parse(line.trim_end()).unwrap();
+            joined.push(msg.params().trailing.as_str().to_string());
+        }
+        assert_eq!(text.trim_end(), joined.join(" "));
+    }
+
+    #[test]
+    fn raw() {
+        let line = "the \x02quick\x02 brown fox";
+        let pieces = split_raw(line, 12);
+        assert_eq!(["the \x02quick\x02 ", "brown fox"], pieces.as_slice());
+        assert_eq!(line, pieces.concat());
+
+        assert_eq!(["abcde", "fgh"], split_raw("abcdefgh", 5).as_slice());
+        assert_eq!([""], split_raw("", 5).as_slice());
+    }
+}
@@ -0,0 +1,491 @@
+//! IRCv3 `draft/multiline` batches.
+//!
+//! A message with several lines, or one too long for a single line, is sent as
+//! a `BATCH` of `PRIVMSG`s (or `NOTICE`s) to the same target. Lines tagged with
+//! `draft/multiline-concat` continue the previous line, every other line starts
+//! a new one.
+//!
+//! - [`MultilineBuilder`] turns text into the batched messages
+//! - [`MultilineAssembler`] reconstructs the text from received messages
+//!
+//! ```rust
+//! use ircv3_parse::multiline::{MultilineAssembler, MultilineBuilder, MultilineLimits};
+//!
+//! let limits = MultilineLimits::parse("max-bytes=4096,max-lines=24").unwrap();
+//! let lines = MultilineBuilder::privmsg("abc", "#rust")
+//!     .limits(limits)
+//!     .build("first line\nsecond line")?;
+//!
+//! let lines: Vec<_> = lines.into_iter().map(|l| l.build()).collect::<Result<_, _>>()?;
+//! assert_eq!("BATCH +abc draft/multiline #rust\r\n", lines[0]);
+//! assert_eq!("@batch=abc PRIVMSG #rust :first line\r\n", lines[1]);
+//! assert_eq!("@batch=abc PRIVMSG #rust :second line\r\n", lines[2]);
+//! assert_eq!("BATCH -abc\r\n", lines[3]);
+//!
+//! let mut assembler = MultilineAssembler::new();
+//! for line in &lines {
+//!     let line = core::str::from_utf8(line)?;
+//!     assembler.handle(&ircv3_parse::parse(line.trim_end())?)?;
+//! }
+//!
+//! let multiline = assembler.poll_complete().unwrap();
+//! assert_eq!("first line\nsecond line", multiline.text);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::compat::{String, ToOwned, Vec, VecDeque};
+
+use crate::{
+    components::Tags,
+    error::MultilineError,
+    messages::split::{split_raw, Splitter},
+    Commands, DeError, Message, MessageBuilder,
+};
+
+/// Name of the capability and of the batch type.
+pub const BATCH_TYPE: &str = "draft/multiline";
+
+/// Tag marking a line that continues the previous one.
+pub const CONCAT_TAG: &str = "draft/multiline-concat";
+
+const BATCH_TAG: &str = "batch";
+
+/// Limits advertised in the value of the `draft/multiline` capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MultilineLimits {
+    /// Maximum length of the combined text in bytes.
+    pub max_bytes: usize,
+    /// Maximum number of messages in a batch.
+    pub max_lines: Option<usize>,
+}
+
+impl MultilineLimits {
+    #[inline]
+    pub fn new(max_bytes: usize, max_lines: Option<usize>) -> Self {
+        Self {
+            max_bytes,
+            max_lines,
+        }
+    }
+
+    /// Parses a capability value such as `max-bytes=4096,max-lines=24`.
+    ///
+    /// Returns `None` if `max-bytes`, which is mandatory, is missing or invalid.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut max_bytes = None;
+        let mut max_lines = None;
+        for entry in value.split(',') {
+            match entry.split_once('=') {
+                Some(("max-bytes", v)) => max_bytes = Some(v.parse().ok()?),
+                Some(("max-lines", v)) => max_lines = v.parse().ok(),
+                _ => {}
+            }
+        }
+
+        max_bytes.map(|max_bytes| Self::new(max_bytes, max_lines))
+    }
+}
+
+/// Builds the messages of a `draft/multiline` batch.
+///
+/// Each line of the text becomes one message, or several joined with
+/// [`CONCAT_TAG`] if it does not fit. Tags are sent on the opening `BATCH`.
+#[derive(Debug, Clone)]
+pub struct MultilineBuilder<'a> {
+    reference: &'a str,
+    command: Commands<'a>,
+    target: &'a str,
+    tags: Vec<(&'a str, Option<&'a str>)>,
+    limits: Option<MultilineLimits>,
+    splitter: Splitter<'a>,
+}
+
+impl<'a> MultilineBuilder<'a> {
+    pub fn privmsg(reference: &'a str, target: &'a str) -> Self {
+        Self::new(
+            reference,
+            Commands::PRIVMSG,
+            target,
+            Splitter::privmsg(target),
+        )
+    }
+
+    pub fn notice(reference: &'a str, target: &'a str) -> Self {
+        Self::new(
+            reference,
+            Commands::NOTICE,
+            target,
+            Splitter::notice(target),
+        )
+    }
+
+    fn new(
+        reference: &'a str,
+        command: Commands<'a>,
+        target: &'a str,
+        splitter: Splitter<'a>,
+    ) -> Self {
+        Self {
+            reference,
+            command,
+            target,
+            tags: Vec::new(),
+            limits: None,
+            splitter,
+        }
+    }
+
+    /// Checks the text against `limits` when building.
+    pub fn limits(mut self, limits: MultilineLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Reserves room for `:nick!user@host `, see [`Splitter::source()`].
+    pub fn source(mut self, nick: &str, user: &str, host: &str) -> Self {
+        self.splitter = self.splitter.source(nick, user, host);
+        self
+    }
+
+    /// Reserves `len` bytes for the source, see [`Splitter::source_len()`].
+    pub fn source_len(mut self, len: usize) -> Self {
+        self.splitter = self.splitter.source_len(len);
+        self
+    }
+
+    pub fn tag(mut self, key: &'a str, value: Option<&'a str>) -> Self {
+        self.tags.push((key, value));
+        self
+    }
+
+    /// Builds the opening `BATCH`, the messages and the closing `BATCH`.
+    ///
+    /// `\r\n` and `\n` both separate lines.
+    pub fn build(&self, text: &str) -> Result<Vec<MessageBuilder>, MultilineError> {
+        let budget = self.splitter.budget();
+        let mut bytes = 0;
+        let mut pieces = Vec::new();
+
+        for (i, line) in text.split('\n').enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            bytes += line.len() + usize::from(i > 0);
+
+            for (j, piece) in split_raw(line, budget).into_iter().enumerate() {
+                pieces.push((piece, j > 0));
+            }
+        }
+
+        if let Some(limits) = self.limits {
+            if bytes > limits.max_bytes {
+                return Err(MultilineError::TooManyBytes {
+                    max: limits.max_bytes,
+                    actual: bytes,
+                });
+            }
+            if let Some(max) = limits.max_lines.filter(|max| pieces.len() > *max) {
+                return Err(MultilineError::TooManyLines {
+                    max,
+                    actual: pieces.len(),
+                });
+            }
+        }
+
+        let mut messages = Vec::with_capacity(pieces.len() + 2);
+
+        let mut open = MessageBuilder::new();
+        open.add_tags(self.tags.iter().copied())?
+            .set_command(Commands::BATCH)?
+            .add_param(&["+", self.reference].concat())?
+            .add_param(BATCH_TYPE)?
+            .add_param(self.target)?;
+        messages.push(open);
+
+        for (piece, concat) in pieces {
+            let mut builder = MessageBuilder::new();
+            builder.add_tag(BATCH_TAG, Some(self.reference))?;
+            if concat {
+                builder.add_tag_flag(CONCAT_TAG)?;
+            }
+            builder
+                .set_command(self.command)?
+                .add_param(self.target)?
+                .set_trailing(piece)?;
+            messages.push(builder);
+        }
+
+        let mut close = MessageBuilder::new();
+        close
+            .set_command(Commands::BATCH)?
+            .add_param(&["-", self.reference].concat())?;
+        messages.push(close);
+
+        Ok(messages)
+    }
+}
+
+/// A reassembled `draft/multiline` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multiline {
+    pub reference: String,
+    pub target: String,
+    /// Source of the opening `BATCH`.
+    pub source: Option<String>,
+    pub notice: bool,
+    pub text: String,
+    /// Number of messages in the batch.
+    pub lines: usize,
+    tags: String,
+}
+
+impl Multiline {
+    /// Returns the tags of the opening `BATCH`, e.g. `msgid`.
+    pub fn tags(&self) -> Tags<'_> {
+        Tags::new(&self.tags)
+    }
+}
+
+/// Collects the messages of `draft/multiline` batches.
+///
+/// Messages are fed with [`MultilineAssembler::handle()`] and finished batches
+/// are taken with [`MultilineAssembler::poll_complete()`]. Several batches can
+/// be open at the same time.
+#[derive(Debug, Clone, Default)]
+pub struct MultilineAssembler {
+    open: Vec<Multiline>,
+    complete: VecDeque<Multiline>,
+}
+
+impl MultilineAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes an incoming message.
+    ///
+    /// Returns `Ok(true)` if the message belongs to a `draft/multiline` batch.
+    pub fn handle(&mut self, msg: &Message<'_>) -> Result<bool, DeError> {
+        let command = msg.command();
+        let params = msg.params();
+
+        if command.is_batch() {
+            let reference = params.get(0).ok_or_else(|| DeError::not_found_param(0))?;
+
+            if let Some(reference) = reference.strip_prefix('+') {
+                if params.get(1) != Some(BATCH_TYPE) {
+                    return Ok(false);
+                }
+
+                let target = params.get(2).ok_or_else(|| DeError::not_found_param(2))?;
+                self.open.push(Multiline {
+                    reference: reference.to_owned(),
+                    target: target.to_owned(),
+                    source: msg.source().map(|s| s.as_str().to_owned()),
+                    notice: false,
+                    text: String::new(),
+                    lines: 0,
+                    tags: msg
+                        .tags()
+                        .map_or_else(String::new, |t| t.as_str().to_owned()),
+                });
+                return Ok(true);
+            }
+
+            if let Some(reference) = reference.strip_prefix('-') {
+                return match self.open.iter().position(|m| m.reference == reference) {
+                    Some(i) => {
+                        let multiline = self.open.remove(i);
+                        self.complete.push_back(multiline);
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                };
+            }
+
+            return Ok(false);
+        }
+
+        if !command.is_privmsg() && !command.is_notice() {
+            return Ok(false);
+        }
+
+        let Some(tags) = msg.tags() else {
+            return Ok(false);
+        };
+        let Some(reference) = tags.get(BATCH_TAG) else {
+            return Ok(false);
+        };
+        let Some(multiline) = self
+            .open
+            .iter_mut()
+            .find(|m| m.reference == reference.as_str())
+        else {
+            return Ok(false);
+        };
+
+        // A single word may be sent as a middle parameter.
+        let text = match params.count() {
+            0 | 1 => "",
+            count => params.get(count - 1).unwrap_or_default(),
+        };
+        if multiline.lines > 0 && !tags.contains(CONCAT_TAG) {
+            multiline.text.push('\n');
+        }
+        multiline.text.push_str(text);
+        multiline.notice = command.is_notice();
+        multiline.lines += 1;
+
+        Ok(true)
+    }
+
+    /// Returns the next batch that was closed.
+    pub fn poll_complete(&mut self) -> Option<Multiline> {
+        self.complete.pop_front()
+    }
+
+    /// Returns `true` if a batch is still open.
+    pub fn is_pending(&self) -> bool {
+        !self.open.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(builders: Vec<MessageBuilder>) -> Vec<String> {
+        builders
+            .into_iter()
+            .map(|b| {
+                let line = b.build().unwrap();
+                core::str::from_utf8(&line)
+                    .unwrap()
+                    .strip_suffix("\r\n")
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect()
+    }
+
+    fn assemble(lines: &[String]) -> Multiline {
+        let mut assembler = MultilineAssembler::new();
+        for line in lines {
+            assert!(assembler.handle(&crate::parse(line).unwrap()).unwrap());
+        }
+        assert!(!assembler.is_pending());
+        assembler.poll_complete().unwrap()
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(
+            Some(MultilineLimits::new(4096, Some(24))),
+            MultilineLimits::parse("max-bytes=4096,max-lines=24")
+        );
+        assert_eq!(
+            Some(MultilineLimits::new(40000, None)),
+            MultilineLimits::parse("max-bytes=40000")
+        );
+        assert_eq!(None, MultilineLimits::parse("max-lines=24"));
+        assert_eq!(None, MultilineLimits::parse("max-bytes=lots"));
+    }
+
+    #[test]
+    fn build() {
+        let builders = MultilineBuilder::notice("ref1", "nick")
+            .tag("+draft/reply", Some("abc"))
+            .build("hello\r\n\nworld")
+            .unwrap();
+        assert_eq!(
+            [
+                "@+draft/reply=abc BATCH +ref1 draft/multiline nick",
+                "@batch=ref1 NOTICE nick :hello",
+                "@batch=ref1 NOTICE nick :",
+                "@batch=ref1 NOTICE nick :world",
+                "BATCH -ref1",
+            ],
+            lines(builders).as_slice()
+        );
+    }
+
+    #[test]
+    fn concat() {
+        let builder = MultilineBuilder::privmsg("xyz", "#rust").source_len(400);
+        let budget = crate::messages::MAX_LINE_LEN - 400 - "PRIVMSG #rust :\r\n".len();
+        let text = format!("{}\nshort", "word ".repeat(budget / 5 * 3));
+
+        let lines = lines(builder.build(&text).unwrap());
+        assert_eq!(6, lines.len());
+        assert!(!lines[1].contains(CONCAT_TAG));
+        assert!(lines[2].contains(CONCAT_TAG));
+        assert!(lines[3].contains(CONCAT_TAG));
+        assert_eq!("@batch=xyz PRIVMSG #rust :short", lines[4]);
+
+        let multiline = assemble(&lines);
+        assert_eq!(text, multiline.text);
+        assert_eq!("#rust", multiline.target);
+        assert_eq!(4, multiline.lines);
+        assert!(!multiline.notice);
+    }
+
+    #[test]
+    fn too_long() {
+        let builder =
+            MultilineBuilder::privmsg("a", "#rust").limits(MultilineLimits::new(10, Some(2)));
+        assert_eq!(
+            MultilineError::TooManyBytes {
+                max: 10,
+                actual: 11
+            },
+            builder.build("hello\nworld").unwrap_err()
+        );
+        assert_eq!(
+            MultilineError::TooManyLines { max: 2, actual: 3 },
+            builder.build("a\nb\nc").unwrap_err()
+        );
+        assert_eq!(4, builder.build("a\nb").unwrap().len());
+
+        let err = builder.build("a\nb\nc").unwrap_err();
+        assert!(err.is_too_many_lines());
+        assert_eq!(
+            "IRC-MULTILINE[LINES]: multiline batch exceeds max-lines (max 2, got 3)",
+            format!("{err:?}")
+        );
+        assert!(builder
+            .build("hello\nworld")
+            .unwrap_err()
+            .is_too_many_bytes());
+    }
+
+    #[test]
+    fn assembler() {
+        let mut assembler = MultilineAssembler::new();
+        let mut feed = |line: &str| assembler.handle(&crate::parse(line).unwrap()).unwrap();
+
+        assert!(feed("@msgid=1 :n!u@h BATCH +a draft/multiline #rust"));
+        assert!(feed(":n!u@h BATCH +b draft/multiline #irc"));
+        assert!(!feed(":irc.example.com BATCH +c netsplit a b"));
+        assert!(feed("@batch=a :n!u@h PRIVMSG #rust :one "));
+        assert!(feed("@batch=b :n!u@h PRIVMSG #irc :other"));
+        assert!(feed(
+            "@batch=a;draft/multiline-concat :n!u@h PRIVMSG #rust :two"
+        ));
+        assert!(!feed("@batch=c :n!u@h PRIVMSG #rust :unrelated"));
+        assert!(!feed(":n!u@h PRIVMSG #rust :plain"));
+        assert!(feed("@batch=a :n!u@h PRIVMSG #rust three"));
+        assert!(feed("BATCH -a"));
+        assert!(!feed("BATCH -c"));
+
+        assert!(assembler.is_pending());
+        let multiline = assembler.poll_complete().unwrap();
+        assert_eq!("a", multiline.reference);
+        assert_eq!("one two\nthree", multiline.text);
+        assert_eq!(Some("n!u@h"), multiline.source.as_deref());
+        assert_eq!(Some("1"), multiline.tags().get("msgid").map(|v| v.as_str()));
+        assert!(assembler.poll_complete().is_none());
+
+        let err = assembler
+            .handle(&crate::parse("BATCH +d draft/multiline").unwrap())
+            .unwrap_err();
+        assert!(err.is_not_found_param());
+    }
+}
@@ -0,0 +1,118 @@
+//! Standard base64 with padding, as required for `AUTHENTICATE` payloads.
+
+use crate::{
+    compat::{String, Vec},
+    error::SaslError,
+};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const PAD: u8 = b'=';
+
+pub fn encode(input: &[u8]) -> String {
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+
+    for chunk in input.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push(PAD as char);
+            }
+        }
+    }
+
+    out
+}
+
+pub fn decode(input: &str) -> Result<Vec<u8>, SaslError> {
+    let bytes = input.as_bytes();
+    if bytes.len() % 4 != 0 {
+        return Err(SaslError::InvalidBase64Length {
+            actual: bytes.len(),
+        });
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+
+    for (i, chunk) in bytes.chunks(4).enumerate() {
+        let last = (i + 1) * 4 == bytes.len();
+        let mut n = 0u32;
+        let mut pad = 0;
+
+        for (j, &c) in chunk.iter().enumerate() {
+            let value = match c {
+                b'A'..=b'Z' if pad == 0 => c - b'A',
+                b'a'..=b'z' if pad == 0 => c - b'a' + 26,
+                b'0'..=b'9' if pad == 0 => c - b'0' + 52,
+                b'+' if pad == 0 => 62,
+                b'/' if pad == 0 => 63,
+                PAD if last && j >= 2 => {
+                    pad += 1;
+                    0
+                }
+                _ => {
+                    return Err(SaslError::InvalidBase64Char {
+                        char: input[i * 4 + j..].chars().next().unwrap_or_default(),
+                        position: i * 4 + j,
+                    })
+                }
+            };
+            n = n << 6 | value as u32;
+        }
+
+        out.push((n >> 16) as u8);
+        if pad < 2 {
+            out.push((n >> 8) as u8);
+        }
+        if pad < 1 {
+            out.push(n as u8);
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (plain, encoded) in vectors {
+            assert_eq!(encoded, encode(plain.as_bytes()));
+            assert_eq!(plain.as_bytes(), decode(encoded).unwrap());
+        }
+
+        let binary: Vec<u8> = (0..=255).collect();
+        assert_eq!(binary, decode(&encode(&binary)).unwrap());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(decode("Zm9").unwrap_err().is_invalid_base64());
+        assert_eq!(
+            SaslError::InvalidBase64Char {
+                char: '*',
+                position: 2
+            },
+            decode("Zm*v").unwrap_err()
+        );
+        assert!(decode("Zg==Zm9v").is_err());
+        assert!(decode("Z===").is_err());
+        assert!(decode("Zg=v").is_err());
+    }
+}
@@ -0,0 +1,708 @@
+//! SASL authentication over `AUTHENTICATE`.
+//!
+//! Payloads are base64-encoded and sent in chunks of [`CHUNK_LEN`] bytes; a
+//! payload whose encoding is a multiple of [`CHUNK_LEN`] (including an empty
+//! one) is terminated with `AUTHENTICATE +`. [`Sasl`] takes care of both
+//! directions and maps the `900`-`908` numerics to a [`SaslOutcome`].
+//!
+//! Supported mechanisms are `PLAIN`, `EXTERNAL` and, with the `scram` feature,
+//! `SCRAM-SHA-256`.
+//!
+//! ```rust
+//! use ircv3_parse::sasl::{Mechanism, Sasl, SaslOutcome};
+//!
+//! let mut sasl = Sasl::new(Mechanism::plain("jilles", "sesame"));
+//! sasl.start();
+//! assert_eq!("AUTHENTICATE PLAIN\r\n", sasl.poll_transmit().unwrap());
+//!
+//! sasl.handle(&ircv3_parse::parse("AUTHENTICATE +")?)?;
+//! assert_eq!(
+//!     "AUTHENTICATE AGppbGxlcwBzZXNhbWU=\r\n",
+//!     sasl.poll_transmit().unwrap()
+//! );
+//!
+//! sasl.handle(&ircv3_parse::parse(
+//!     ":irc.example.com 900 jilles jilles!jilles@localhost jilles :You are now logged in as jilles",
+//! )?)?;
+//! sasl.handle(&ircv3_parse::parse(
+//!     ":irc.example.com 903 jilles :SASL authentication successful",
+//! )?)?;
+//!
+//! assert_eq!(Some(SaslOutcome::Success), sasl.outcome());
+//! assert_eq!(Some("jilles"), sasl.account());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod base64;
+#[cfg(feature = "scram")]
+mod scram;
+
+use bytes::Bytes;
+
+use crate::{
+    compat::{Debug, FmtResult, Formatter, String, ToOwned, Vec, VecDeque},
+    components::Numeric,
+    error::SaslError,
+    Commands, DeError, Message, MessageBuilder,
+};
+
+/// Maximum length of a single `AUTHENTICATE` chunk.
+pub const CHUNK_LEN: usize = 400;
+
+/// Credentials for a SASL mechanism.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Mechanism {
+    Plain {
+        authzid: Option<String>,
+        authcid: String,
+        password: String,
+    },
+    /// Authenticates with the TLS client certificate.
+    External { authzid: Option<String> },
+    /// `nonce` must be unique per attempt and should come from a secure random
+    /// source; it may contain any printable ASCII character except `,`.
+    #[cfg(feature = "scram")]
+    ScramSha256 {
+        authzid: Option<String>,
+        authcid: String,
+        password: String,
+        nonce: String,
+    },
+}
+
+impl Mechanism {
+    pub fn plain(authcid: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::Plain {
+            authzid: None,
+            authcid: authcid.into(),
+            password: password.into(),
+        }
+    }
+
+    pub fn external() -> Self {
+        Self::External { authzid: None }
+    }
+
+    #[cfg(feature = "scram")]
+    pub fn scram_sha256(
+        authcid: impl Into<String>,
+        password: impl Into<String>,
+        nonce: impl Into<String>,
+    ) -> Self {
+        Self::ScramSha256 {
+            authzid: None,
+            authcid: authcid.into(),
+            password: password.into(),
+            nonce: nonce.into(),
+        }
+    }
+
+    /// Sets the identity to act as, if different from the authenticated one.
+    pub fn with_authzid(mut self, authzid: impl Into<String>) -> Self {
+        match &mut self {
+            Self::Plain { authzid: a, .. } | Self::External { authzid: a } => {
+                *a = Some(authzid.into())
+            }
+            #[cfg(feature = "scram")]
+            Self::ScramSha256 { authzid: a, .. } => *a = Some(authzid.into()),
+        }
+        self
+    }
+
+    /// Returns the mechanism name sent with `AUTHENTICATE`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Plain { .. } => "PLAIN",
+            Self::External { .. } => "EXTERNAL",
+            #[cfg(feature = "scram")]
+            Self::ScramSha256 { .. } => "SCRAM-SHA-256",
+        }
+    }
+}
+
+impl Debug for Mechanism {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Plain {
+                authzid, authcid, ..
+            } => f
+                .debug_struct("Plain")
+                .field("authzid", authzid)
+                .field("authcid", authcid)
+                .finish_non_exhaustive(),
+            Self::External { authzid } => f
+                .debug_struct("External")
+                .field("authzid", authzid)
+                .finish(),
+            #[cfg(feature = "scram")]
+            Self::ScramSha256 {
+                authzid, authcid, ..
+            } => f
+                .debug_struct("ScramSha256")
+                .field("authzid", authzid)
+                .field("authcid", authcid)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Final result of an authentication attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SaslOutcome {
+    /// `903 RPL_SASLSUCCESS`
+    Success,
+    /// `902 ERR_NICKLOCKED`
+    NickLocked,
+    /// `904 ERR_SASLFAIL`
+    Failed,
+    /// `905 ERR_SASLTOOLONG`
+    TooLong,
+    /// `906 ERR_SASLABORTED`
+    Aborted,
+    /// `907 ERR_SASLALREADY`
+    AlreadyAuthenticated,
+}
+
+impl SaslOutcome {
+    pub fn from_numeric(numeric: Numeric) -> Option<Self> {
+        match numeric {
+            Numeric::RPL_SASLSUCCESS => Some(Self::Success),
+            Numeric::ERR_NICKLOCKED => Some(Self::NickLocked),
+            Numeric::ERR_SASLFAIL => Some(Self::Failed),
+            Numeric::ERR_SASLTOOLONG => Some(Self::TooLong),
+            Numeric::ERR_SASLABORTED => Some(Self::Aborted),
+            Numeric::ERR_SASLALREADY => Some(Self::AlreadyAuthenticated),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success)
+    }
+}
+
+/// Progress of a [`Sasl`] exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SaslState {
+    /// [`Sasl::start()`] has not been called yet.
+    Idle,
+    Authenticating,
+    /// A [`SaslOutcome`] was received.
+    Done,
+}
+
+/// Encodes `payload` into `AUTHENTICATE` lines.
+pub fn authenticate(payload: &[u8]) -> Vec<Bytes> {
+    let encoded = base64::encode(payload);
+
+    let mut lines: Vec<Bytes> = encoded
+        .as_bytes()
+        .chunks(CHUNK_LEN)
+        .filter_map(|chunk| authenticate_line(core::str::from_utf8(chunk).ok()?))
+        .collect();
+
+    if encoded.len() % CHUNK_LEN == 0 {
+        lines.extend(authenticate_line("+"));
+    }
+
+    lines
+}
+
+fn authenticate_line(param: &str) -> Option<Bytes> {
+    let mut builder = MessageBuilder::new();
+    builder
+        .set_command(Commands::AUTHENTICATE)
+        .and_then(|b| b.add_param(param))
+        .ok()?;
+    builder.build().ok()
+}
+
+/// Reassembles chunked `AUTHENTICATE` payloads sent by the server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChallengeBuffer {
+    buffer: String,
+}
+
+impl ChallengeBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a chunk, returning the decoded payload once it is complete.
+    pub fn push(&mut self, chunk: &str) -> Result<Option<Vec<u8>>, SaslError> {
+        if chunk != "+" {
+            self.buffer.push_str(chunk);
+            if chunk.len() == CHUNK_LEN {
+                return Ok(None);
+            }
+        }
+
+        let encoded = core::mem::take(&mut self.buffer);
+        base64::decode(&encoded).map(Some)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+#[derive(Clone)]
+enum Client {
+    Plain {
+        sent: bool,
+    },
+    External {
+        sent: bool,
+    },
+    #[cfg(feature = "scram")]
+    ScramSha256(scram::ScramSha256),
+}
+
+/// Sans-IO SASL authentication.
+///
+/// Call [`Sasl::start()`] once the `sasl` capability is enabled, feed every
+/// incoming message to [`Sasl::handle()`] and send whatever
+/// [`Sasl::poll_transmit()`] returns.
+///
+/// If a server challenge cannot be answered the exchange is aborted with
+/// `AUTHENTICATE *` and [`Sasl::handle()`] returns the error; the server then
+/// replies with `906 ERR_SASLABORTED`.
+#[derive(Clone)]
+pub struct Sasl {
+    mechanism: Mechanism,
+    client: Client,
+    state: SaslState,
+    buffer: ChallengeBuffer,
+    outcome: Option<SaslOutcome>,
+    account: Option<String>,
+    mechanisms: Vec<String>,
+    outgoing: VecDeque<Bytes>,
+}
+
+impl Debug for Sasl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Sasl")
+            .field("mechanism", &self.mechanism)
+            .field("state", &self.state)
+            .field("outcome", &self.outcome)
+            .field("account", &self.account)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Sasl {
+    pub fn new(mechanism: Mechanism) -> Self {
+        let client = Self::client(&mechanism);
+        Self {
+            mechanism,
+            client,
+            state: SaslState::Idle,
+            buffer: ChallengeBuffer::new(),
+            outcome: None,
+            account: None,
+            mechanisms: Vec::new(),
+            outgoing: VecDeque::new(),
+        }
+    }
+
+    /// Queues `AUTHENTICATE <mechanism>`, restarting any previous attempt.
+    pub fn start(&mut self) {
+        self.client = Self::client(&self.mechanism);
+        self.buffer = ChallengeBuffer::new();
+        self.outcome = None;
+        self.state = SaslState::Authenticating;
+        self.outgoing
+            .extend(authenticate_line(self.mechanism.name()));
+    }
+
+    /// Queues `AUTHENTICATE *`.
+    pub fn abort(&mut self) {
+        if self.state == SaslState::Authenticating {
+            self.outgoing.extend(authenticate_line("*"));
+        }
+    }
+
+    /// Processes an incoming message.
+    ///
+    /// Returns `Ok(true)` if the message was part of the exchange.
+    pub fn handle(&mut self, msg: &Message<'_>) -> Result<bool, SaslError> {
+        let command = msg.command();
+
+        if command == Commands::AUTHENTICATE {
+            if self.state != SaslState::Authenticating {
+                return Ok(false);
+            }
+
+            let chunk = msg
+                .params()
+                .get(0)
+                .ok_or_else(|| DeError::not_found_param(0))?;
+
+            let result = self
+                .buffer
+                .push(chunk)
+                .and_then(|payload| payload.map(|p| self.respond(&p)).transpose());
+
+            return match result {
+                Ok(Some(response)) => {
+                    self.outgoing.extend(authenticate(&response));
+                    Ok(true)
+                }
+                Ok(None) => Ok(true),
+                Err(e) => {
+                    self.buffer = ChallengeBuffer::new();
+                    self.abort();
+                    Err(e)
+                }
+            };
+        }
+
+        let Some(numeric) = command.numeric() else {
+            return Ok(false);
+        };
+
+        let params = msg.params();
+        match numeric {
+            Numeric::RPL_LOGGEDIN => {
+                self.account = params.get(2).map(|a| a.to_owned());
+            }
+            Numeric::RPL_LOGGEDOUT => {
+                self.account = None;
+            }
+            Numeric::RPL_SASLMECHS => {
+                self.mechanisms = params
+                    .get(1)
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|m| !m.is_empty())
+                    .map(|m| m.to_owned())
+                    .collect();
+            }
+            numeric => match SaslOutcome::from_numeric(numeric) {
+                // A server that skips the final challenge has not proven it knows the password.
+                Some(SaslOutcome::Success) if !self.is_verified() => {
+                    self.outcome = Some(SaslOutcome::Failed);
+                    self.state = SaslState::Done;
+                    return Err(SaslError::SignatureMismatch);
+                }
+                Some(outcome) => {
+                    self.outcome = Some(outcome);
+                    self.state = SaslState::Done;
+                }
+                None => return Ok(false),
+            },
+        }
+
+        Ok(true)
+    }
+
+    /// Returns the next message to send.
+    pub fn poll_transmit(&mut self) -> Option<Bytes> {
+        self.outgoing.pop_front()
+    }
+
+    pub fn mechanism(&self) -> &Mechanism {
+        &self.mechanism
+    }
+
+    pub fn state(&self) -> SaslState {
+        self.state
+    }
+
+    pub fn outcome(&self) -> Option<SaslOutcome> {
+        self.outcome
+    }
+
+    /// Account name from `900 RPL_LOGGEDIN`.
+    pub fn account(&self) -> Option<&str> {
+        self.account.as_deref()
+    }
+
+    /// Mechanisms the server offered in `908 RPL_SASLMECHS`.
+    pub fn mechanisms(&self) -> impl Iterator<Item = &str> {
+        self.mechanisms.iter().map(|m| m.as_str())
+    }
+
+    fn client(mechanism: &Mechanism) -> Client {
+        match mechanism {
+            Mechanism::Plain { .. } => Client::Plain { sent: false },
+            Mechanism::External { .. } => Client::External { sent: false },
+            #[cfg(feature = "scram")]
+            Mechanism::ScramSha256 {
+                authzid,
+                authcid,
+                password,
+                nonce,
+            } => Client::ScramSha256(scram::ScramSha256::new(
+                authzid.as_deref(),
+                authcid,
+                password,
+                nonce,
+            )),
+        }
+    }
+
+    fn is_verified(&self) -> bool {
+        match &self.client {
+            Client::Plain { .. } | Client::External { .. } => true,
+            #[cfg(feature = "scram")]
+            Client::ScramSha256(scram) => scram.is_verified(),
+        }
+    }
+
+    fn respond(&mut self, challenge: &[u8]) -> Result<Vec<u8>, SaslError> {
+        match (&mut self.client, &self.mechanism) {
+            (
+                Client::Plain { sent },
+                Mechanism::Plain {
+                    authzid,
+                    authcid,
+                    password,
+                },
+            ) => {
+                if *sent || !challenge.is_empty() {
+                    return Err(SaslError::invalid_challenge(
+                        "PLAIN",
+                        "expected a single empty challenge",
+                    ));
+                }
+                *sent = true;
+
+                let mut response = Vec::new();
+                response.extend_from_slice(authzid.as_deref().unwrap_or_default().as_bytes());
+                response.push(crate::NUL);
+                response.extend_from_slice(authcid.as_bytes());
+                response.push(crate::NUL);
+                response.extend_from_slice(password.as_bytes());
+                Ok(response)
+            }
+            (Client::External { sent }, Mechanism::External { authzid }) => {
+                if *sent || !challenge.is_empty() {
+                    return Err(SaslError::invalid_challenge(
+                        "EXTERNAL",
+                        "expected a single empty challenge",
+                    ));
+                }
+                *sent = true;
+
+                Ok(authzid.as_deref().unwrap_or_default().as_bytes().to_vec())
+            }
+            #[cfg(feature = "scram")]
+            (Client::ScramSha256(scram), _) => scram.step(challenge),
+            _ => unreachable!("client is created from the mechanism"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(sasl: &mut Sasl, line: &str) -> Result<bool, SaslError> {
+        sasl.handle(&crate::parse(line).unwrap())
+    }
+
+    fn drain(sasl: &mut Sasl) -> Vec<String> {
+        core::iter::from_fn(|| sasl.poll_transmit())
+            .map(|b| String::from_utf8(b.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn chunking() {
+        assert_eq!(["AUTHENTICATE +\r\n"], authenticate(b"").as_slice());
+
+        // 300 bytes encode to exactly 400 characters.
+        let lines = authenticate(&[b'a'; 300]);
+        assert_eq!(2, lines.len());
+        assert_eq!(b"AUTHENTICATE YWFh", &lines[0][..17]);
+        assert_eq!(CHUNK_LEN + "AUTHENTICATE \r\n".len(), lines[0].len());
+        assert_eq!("AUTHENTICATE +\r\n", lines[1]);
+
+        let lines = authenticate(&[b'a'; 301]);
+        assert_eq!(2, lines.len());
+        assert_eq!("AUTHENTICATE YQ==\r\n", lines[1]);
+    }
+
+    #[test]
+    fn reassembly() {
+        let payload = [7u8; 450];
+        let mut buffer = ChallengeBuffer::new();
+        let mut decoded = None;
+        for line in authenticate(&payload) {
+            let line = core::str::from_utf8(&line).unwrap();
+            let msg = crate::parse(line).unwrap();
+            assert!(decoded.is_none());
+            decoded = buffer.push(msg.params().get(0).unwrap()).unwrap();
+        }
+        assert_eq!(Some(payload.to_vec()), decoded);
+        assert!(buffer.is_empty());
+
+        let mut buffer = ChallengeBuffer::new();
+        assert_eq!(Some(Vec::new()), buffer.push("+").unwrap());
+        assert!(buffer.push("Zm9").unwrap_err().is_invalid_base64());
+    }
+
+    #[test]
+    fn plain_exchange() {
+        let mut sasl = Sasl::new(Mechanism::plain("jilles", "sesame").with_authzid("jilles"));
+        sasl.start();
+        assert_eq!(["AUTHENTICATE PLAIN\r\n"], drain(&mut sasl).as_slice());
+
+        assert!(feed(&mut sasl, "AUTHENTICATE +").unwrap());
+        assert_eq!(
+            ["AUTHENTICATE amlsbGVzAGppbGxlcwBzZXNhbWU=\r\n"],
+            drain(&mut sasl).as_slice()
+        );
+
+        feed(&mut sasl, ":irc.example.com 900 jilles jilles!jilles@localhost.stack.nl jilles :You are now logged in as jilles").unwrap();
+        feed(
+            &mut sasl,
+            ":irc.example.com 903 jilles :SASL authentication successful",
+        )
+        .unwrap();
+        assert_eq!(SaslState::Done, sasl.state());
+        assert_eq!(Some(SaslOutcome::Success), sasl.outcome());
+        assert_eq!(Some("jilles"), sasl.account());
+    }
+
+    #[test]
+    fn external_exchange() {
+        let mut sasl = Sasl::new(Mechanism::external());
+        sasl.start();
+        feed(&mut sasl, "AUTHENTICATE +").unwrap();
+        assert_eq!(
+            ["AUTHENTICATE EXTERNAL\r\n", "AUTHENTICATE +\r\n"],
+            drain(&mut sasl).as_slice()
+        );
+
+        feed(
+            &mut sasl,
+            ":irc.example.com 904 * :SASL authentication failed",
+        )
+        .unwrap();
+        assert_eq!(Some(SaslOutcome::Failed), sasl.outcome());
+    }
+
+    #[test]
+    fn unsupported_mechanism() {
+        let mut sasl = Sasl::new(Mechanism::external());
+        sasl.start();
+        feed(
+            &mut sasl,
+            ":irc.example.com 908 * PLAIN,SCRAM-SHA-256 :are available SASL mechanisms",
+        )
+        .unwrap();
+        feed(
+            &mut sasl,
+            ":irc.example.com 904 * :SASL authentication failed",
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec!["PLAIN", "SCRAM-SHA-256"],
+            sasl.mechanisms().collect::<Vec<_>>()
+        );
+        assert!(!sasl.outcome().unwrap().is_success());
+    }
+
+    #[test]
+    fn unexpected_challenge() {
+        let mut sasl = Sasl::new(Mechanism::plain("jilles", "sesame"));
+        sasl.start();
+        drain(&mut sasl);
+
+        assert!(feed(&mut sasl, "AUTHENTICATE Zm9v")
+            .unwrap_err()
+            .is_invalid_challenge());
+        assert_eq!(["AUTHENTICATE *\r\n"], drain(&mut sasl).as_slice());
+
+        feed(
+            &mut sasl,
+            ":irc.example.com 906 * :SASL authentication aborted",
+        )
+        .unwrap();
+        assert_eq!(Some(SaslOutcome::Aborted), sasl.outcome());
+        assert!(!feed(&mut sasl, "AUTHENTICATE +").unwrap());
+    }
+
+    #[test]
+    fn numeric_outcomes() {
+        for (code, outcome) in [
+            ("902", SaslOutcome::NickLocked),
+            ("903", SaslOutcome::Success),
+            ("904", SaslOutcome::Failed),
+            ("905", SaslOutcome::TooLong),
+            ("906", SaslOutcome::Aborted),
+            ("907", SaslOutcome::AlreadyAuthenticated),
+        ] {
+            let numeric = Numeric::from_code_str(code).unwrap();
+            assert_eq!(Some(outcome), SaslOutcome::from_numeric(numeric));
+        }
+        assert_eq!(None, SaslOutcome::from_numeric(Numeric::RPL_LOGGEDIN));
+    }
+
+    #[cfg(feature = "scram")]
+    #[test]
+    fn scram_exchange() {
+        let mut sasl = Sasl::new(Mechanism::scram_sha256(
+            "user",
+            "pencil",
+            "rOprNGfwEbeRWgbNEkqO",
+        ));
+        sasl.start();
+        feed(&mut sasl, "AUTHENTICATE +").unwrap();
+        assert_eq!(
+            [
+                "AUTHENTICATE SCRAM-SHA-256\r\n",
+                "AUTHENTICATE biwsbj11c2VyLHI9ck9wck5HZndFYmVSV2diTkVrcU8=\r\n"
+            ],
+            drain(&mut sasl).as_slice()
+        );
+
+        feed(&mut sasl, "AUTHENTICATE cj1yT3ByTkdmd0ViZVJXZ2JORWtxTyVodllEcFdVYTJSYVRDQWZ1eEZJbGopaE5sRiRrMCxzPVcyMlphSjBTTlk3c29Fc1VFamI2Z1E9PSxpPTQwOTY=").unwrap();
+        assert_eq!(
+            ["AUTHENTICATE Yz1iaXdzLHI9ck9wck5HZndFYmVSV2diTkVrcU8laHZZRHBXVWEyUmFUQ0FmdXhGSWxqKWhObEYkazAscD1kSHpiWmFwV0lrNGpVaE4rVXRlOXl0YWc5empmTUhnc3FtbWl6N0FuZFZRPQ==\r\n"],
+            drain(&mut sasl).as_slice()
+        );
+
+        feed(
+            &mut sasl,
+            "AUTHENTICATE dj02cnJpVFJCaTIzV3BSUi93dHVwK21NaFVaVW4vZEI1bkxUSlJzamw5NUc0PQ==",
+        )
+        .unwrap();
+        assert_eq!(["AUTHENTICATE +\r\n"], drain(&mut sasl).as_slice());
+
+        feed(
+            &mut sasl,
+            ":irc.example.com 903 user :SASL authentication successful",
+        )
+        .unwrap();
+        assert_eq!(Some(SaslOutcome::Success), sasl.outcome());
+    }
+
+    #[cfg(feature = "scram")]
+    #[test]
+    fn scram_unverified_success() {
+        let mut sasl = Sasl::new(Mechanism::scram_sha256(
+            "user",
+            "pencil",
+            "rOprNGfwEbeRWgbNEkqO",
+        ));
+        sasl.start();
+        feed(&mut sasl, "AUTHENTICATE +").unwrap();
+        feed(&mut sasl, "AUTHENTICATE cj1yT3ByTkdmd0ViZVJXZ2JORWtxTyVodllEcFdVYTJSYVRDQWZ1eEZJbGopaE5sRiRrMCxzPVcyMlphSjBTTlk3c29Fc1VFamI2Z1E9PSxpPTQwOTY=").unwrap();
+        assert_eq!(3, drain(&mut sasl).len());
+
+        // The server-final-message carrying `v=` was never sent.
+        assert!(feed(
+            &mut sasl,
+            ":irc.example.com 903 user :SASL authentication successful",
+        )
+        .unwrap_err()
+        .is_signature_mismatch());
+        assert_eq!(SaslState::Done, sasl.state());
+        assert_eq!(Some(SaslOutcome::Failed), sasl.outcome());
+    }
+}
@@ -0,0 +1,251 @@
+//! SCRAM-SHA-256 client ([RFC 5802], [RFC 7677]).
+//!
+//! [RFC 5802]: https://www.rfc-editor.org/rfc/rfc5802
+//! [RFC 7677]: https://www.rfc-editor.org/rfc/rfc7677
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use super::base64;
+use crate::{
+    compat::{format, String, ToOwned, Vec},
+    error::SaslError,
+};
+
+const NAME: &str = "SCRAM-SHA-256";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Step {
+    ClientFirst,
+    ClientFinal,
+    Verify,
+    Done,
+}
+
+#[derive(Clone)]
+pub struct ScramSha256 {
+    gs2_header: String,
+    client_first_bare: String,
+    nonce: String,
+    password: String,
+    server_signature: [u8; 32],
+    step: Step,
+}
+
+impl ScramSha256 {
+    pub fn new(authzid: Option<&str>, authcid: &str, password: &str, nonce: &str) -> Self {
+        let gs2_header = match authzid {
+            Some(authzid) => format!("n,a={},", escape(authzid)),
+            None => "n,,".to_owned(),
+        };
+
+        Self {
+            gs2_header,
+            client_first_bare: format!("n={},r={}", escape(authcid), nonce),
+            nonce: nonce.to_owned(),
+            password: password.to_owned(),
+            server_signature: [0; 32],
+            step: Step::ClientFirst,
+        }
+    }
+
+    pub fn step(&mut self, challenge: &[u8]) -> Result<Vec<u8>, SaslError> {
+        match self.step {
+            Step::ClientFirst => {
+                if !challenge.is_empty() {
+                    return Err(SaslError::invalid_challenge(
+                        NAME,
+                        "expected empty challenge",
+                    ));
+                }
+                self.step = Step::ClientFinal;
+                Ok(format!("{}{}", self.gs2_header, self.client_first_bare).into_bytes())
+            }
+            Step::ClientFinal => {
+                let response = self.client_final(challenge)?;
+                self.step = Step::Verify;
+                Ok(response)
+            }
+            Step::Verify => {
+                self.verify(challenge)?;
+                self.step = Step::Done;
+                Ok(Vec::new())
+            }
+            Step::Done => Err(SaslError::invalid_challenge(
+                NAME,
+                "authentication already completed",
+            )),
+        }
+    }
+
+    /// Returns `true` once the server signature has been verified.
+    pub fn is_verified(&self) -> bool {
+        self.step == Step::Done
+    }
+
+    fn client_final(&mut self, challenge: &[u8]) -> Result<Vec<u8>, SaslError> {
+        let server_first = core::str::from_utf8(challenge)
+            .map_err(|_| SaslError::invalid_challenge(NAME, "server-first-message is not UTF-8"))?;
+
+        let mut nonce = None;
+        let mut salt = None;
+        let mut iterations = None;
+        for attr in server_first.split(',') {
+            match attr.split_once('=') {
+                Some(("r", value)) => nonce = Some(value),
+                Some(("s", value)) => salt = Some(value),
+                Some(("i", value)) => iterations = Some(value),
+                Some(("e", value)) => return Err(SaslError::ServerError(value.to_owned())),
+                Some(("m", _)) => {
+                    return Err(SaslError::invalid_challenge(
+                        NAME,
+                        "unsupported mandatory extension",
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        let nonce = nonce
+            .filter(|n| n.len() > self.nonce.len() && n.starts_with(self.nonce.as_str()))
+            .ok_or_else(|| SaslError::invalid_challenge(NAME, "invalid server nonce"))?;
+        let salt = base64::decode(
+            salt.ok_or_else(|| SaslError::invalid_challenge(NAME, "missing salt"))?,
+        )?;
+        let iterations = iterations
+            .and_then(|i| i.parse::<u32>().ok())
+            .filter(|i| *i > 0)
+            .ok_or_else(|| SaslError::invalid_challenge(NAME, "invalid iteration count"))?;
+
+        let without_proof = format!(
+            "c={},r={}",
+            base64::encode(self.gs2_header.as_bytes()),
+            nonce
+        );
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_bare, server_first, without_proof
+        );
+
+        let mut salted_password = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(
+            self.password.as_bytes(),
+            &salt,
+            iterations,
+            &mut salted_password,
+        );
+
+        let client_key = hmac(&salted_password, b"Client Key");
+        let stored_key: [u8; 32] = Sha256::digest(client_key).into();
+        let client_signature = hmac(&stored_key, auth_message.as_bytes());
+
+        let mut proof = client_key;
+        for (p, s) in proof.iter_mut().zip(client_signature) {
+            *p ^= s;
+        }
+
+        let server_key = hmac(&salted_password, b"Server Key");
+        self.server_signature = hmac(&server_key, auth_message.as_bytes());
+
+        Ok(format!("{},p={}", without_proof, base64::encode(&proof)).into_bytes())
+    }
+
+    fn verify(&self, challenge: &[u8]) -> Result<(), SaslError> {
+        let server_final = core::str::from_utf8(challenge)
+            .map_err(|_| SaslError::invalid_challenge(NAME, "server-final-message is not UTF-8"))?;
+
+        match server_final
+            .split(',')
+            .next()
+            .and_then(|a| a.split_once('='))
+        {
+            Some(("v", signature)) => {
+                if constant_time_eq(&base64::decode(signature)?, &self.server_signature) {
+                    Ok(())
+                } else {
+                    Err(SaslError::SignatureMismatch)
+                }
+            }
+            Some(("e", error)) => Err(SaslError::ServerError(error.to_owned())),
+            _ => Err(SaslError::invalid_challenge(
+                NAME,
+                "invalid server-final-message",
+            )),
+        }
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Compares without returning early, so the timing does not leak how many bytes matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Escapes `=` and `,` in a `saslname`.
+fn escape(name: &str) -> String {
+    name.replace('=', "=3D").replace(',', "=2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // https://www.rfc-editor.org/rfc/rfc7677#section-3
+    #[test]
+    fn rfc7677_exchange() {
+        let mut scram = ScramSha256::new(None, "user", "pencil", "rOprNGfwEbeRWgbNEkqO");
+
+        assert_eq!(
+            b"n,,n=user,r=rOprNGfwEbeRWgbNEkqO",
+            &scram.step(b"").unwrap()[..]
+        );
+
+        let server_first =
+            b"r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+        assert_eq!(
+            &b"c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="[..],
+            &scram.step(server_first).unwrap()[..]
+        );
+
+        let server_final = b"v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=";
+        assert!(scram.step(server_final).unwrap().is_empty());
+        assert!(scram.step(b"").unwrap_err().is_invalid_challenge());
+    }
+
+    #[test]
+    fn rejects_bad_server() {
+        let mut scram = ScramSha256::new(Some("admin"), "us=er,1", "pencil", "abc");
+        assert_eq!(
+            b"n,a=admin,n=us=3Der=2C1,r=abc",
+            &scram.step(b"").unwrap()[..]
+        );
+        assert!(scram
+            .step(b"r=xyz,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096")
+            .unwrap_err()
+            .is_invalid_challenge());
+
+        let mut scram = ScramSha256::new(None, "user", "pencil", "abc");
+        scram.step(b"").unwrap();
+        scram
+            .step(b"r=abcdef,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=1")
+            .unwrap();
+        assert!(scram
+            .step(b"v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=")
+            .unwrap_err()
+            .is_signature_mismatch());
+
+        let mut scram = ScramSha256::new(None, "user", "pencil", "abc");
+        scram.step(b"").unwrap();
+        assert_eq!(
+            SaslError::ServerError("unknown-user".to_owned()),
+            scram.step(b"e=unknown-user").unwrap_err()
+        );
+    }
+}
@@ -231,6 +231,7 @@ impl IRCTagsSerializer {
         Ok(())
     }
 
+    /// Returns `true` if no tag was added.
     pub fn is_empty(&self) -> bool {
         self.tags.is_empty()
     }
@@ -335,6 +336,11 @@ impl IRCSourceSerializer {
         Ok(())
     }
 
+    /// Returns `true` if no part of the source was set.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.user.is_none() && self.host.is_none()
+    }
+
     pub fn validate(&self) -> Result<(), SourceError> {
         if (self.user.is_some() || self.host.is_some()) && self.name.is_none() {
             Err(SourceError::MissingNick)
@@ -367,6 +373,11 @@ impl IRCSourceSerializer {
 
 impl ToMessage for IRCSourceSerializer {
     fn to_message<S: MessageSerializer>(&self, serialize: &mut S) -> Result<(), SerError> {
+        // Messages sent by a client carry no source.
+        if self.is_empty() {
+            return Ok(());
+        }
+
         self.validate()?;
 
         let source = serialize.source();
@@ -0,0 +1,754 @@
+//! Client connection registration.
+//!
+//! [`ClientSession`] performs the registration every client repeats: capability
+//! negotiation, optional SASL, `PASS`/`NICK`/`USER`, alternative nicknames on
+//! collisions and waiting for `001 RPL_WELCOME`. It also answers `PING` and
+//! records `005 RPL_ISUPPORT`. It does not do any I/O.
+//!
+//! ```rust
+//! use ircv3_parse::session::{ClientSession, SessionEvent};
+//!
+//! let mut session = ClientSession::new("ferris").alt_nicks(["ferris_"]);
+//! session.start()?;
+//! assert_eq!("CAP LS 302\r\n", session.poll_transmit().unwrap());
+//! assert_eq!("NICK ferris\r\n", session.poll_transmit().unwrap());
+//! assert_eq!("USER ferris 0 * :ferris\r\n", session.poll_transmit().unwrap());
+//!
+//! let lines = [
+//!     ":irc.example.com CAP * LS :multi-prefix",
+//!     ":irc.example.com 433 * ferris :Nickname is already in use",
+//!     ":irc.example.com 001 ferris_ :Welcome",
+//!     ":irc.example.com 005 ferris_ CHANTYPES=# NICKLEN=30 :are supported by this server",
+//!     "PING :irc.example.com",
+//! ];
+//! for line in lines {
+//!     session.handle(&ircv3_parse::parse(line)?)?;
+//! }
+//!
+//! let sent: Vec<_> = std::iter::from_fn(|| session.poll_transmit()).collect();
+//! assert_eq!(["CAP END\r\n", "NICK ferris_\r\n", "PONG irc.example.com\r\n"], sent.as_slice());
+//! assert_eq!(
+//!     Some(SessionEvent::Registered { nick: "ferris_".to_string() }),
+//!     session.poll_event()
+//! );
+//! assert_eq!(Some("30"), session.isupport().get("NICKLEN"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use bytes::Bytes;
+
+use crate::compat::{Debug, FmtResult, Formatter, String, ToOwned, Vec, VecDeque};
+
+use crate::{
+    cap::{CapNegotiator, CapState},
+    components::Numeric,
+    de::FromMessage,
+    error::SessionError,
+    messages::client::{Nick, Pass, Ping, Pong, User},
+    sasl::{Mechanism, Sasl, SaslOutcome, SaslState},
+    ser::{IRCSerializer, ToMessage},
+    Commands, Message, SerError,
+};
+
+/// Progress of a [`ClientSession`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionState {
+    /// [`ClientSession::start()`] has not been called yet.
+    Idle,
+    /// Waiting for `001 RPL_WELCOME`.
+    Registering,
+    Registered,
+    /// The server sent `ERROR`.
+    Closed,
+}
+
+/// Something the caller may want to react to, see [`ClientSession::poll_event()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// `001 RPL_WELCOME` was received with our final nickname.
+    Registered { nick: String },
+    /// SASL succeeded, with the account from `900 RPL_LOGGEDIN`.
+    LoggedIn { account: Option<String> },
+    /// SASL failed, or `None` if the server did not offer the `sasl`
+    /// capability. Registration continues without an account.
+    SaslFailed { outcome: Option<SaslOutcome> },
+    /// Our nickname changed after registration.
+    NickChanged { old: String, new: String },
+    /// The server closed the connection with `ERROR`.
+    Closed { reason: String },
+}
+
+/// Tokens of `005 RPL_ISUPPORT`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ISupport {
+    tokens: Vec<(String, Option<String>)>,
+}
+
+impl ISupport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the tokens of an `RPL_ISUPPORT` line; `-TOKEN` removes one.
+    pub fn extend<'a>(&mut self, tokens: impl IntoIterator<Item = &'a str>) {
+        for token in tokens {
+            if let Some(name) = token.strip_prefix('-') {
+                self.tokens.retain(|(n, _)| n != name);
+                continue;
+            }
+
+            let (name, value) = match token.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_owned())),
+                None => (token, None),
+            };
+            match self.tokens.iter_mut().find(|(n, _)| n == name) {
+                Some(existing) => existing.1 = value,
+                None => self.tokens.push((name.to_owned(), value)),
+            }
+        }
+    }
+
+    /// Adds the tokens of an `RPL_ISUPPORT` reply,
+    /// `<client> <tokens>... :are supported by this server`.
+    pub fn extend_reply(&mut self, msg: &Message<'_>) {
+        let params = msg.params();
+        let count = params.count();
+        self.extend((1..count.saturating_sub(1)).filter_map(|i| params.get(i)));
+    }
+
+    /// Returns the value of `name`, an empty string for a token without one.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.tokens
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_deref().unwrap_or_default())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tokens.iter().any(|(n, _)| n == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.tokens
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_deref()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+/// Sans-IO client registration.
+///
+/// Call [`ClientSession::start()`] once connected, feed every incoming
+/// message to [`ClientSession::handle()`], send whatever
+/// [`ClientSession::poll_transmit()`] returns and react to
+/// [`ClientSession::poll_event()`].
+///
+/// With [`ClientSession::sasl()`] `CAP END` is held back until
+/// authentication finishes. A `PASS` is sent before `NICK`, as Twitch
+/// expects its OAuth token.
+#[derive(Clone)]
+pub struct ClientSession {
+    nick: String,
+    alt_nicks: VecDeque<String>,
+    username: Option<String>,
+    realname: Option<String>,
+    password: Option<String>,
+    caps: Vec<String>,
+    mechanism: Option<Mechanism>,
+    state: SessionState,
+    cap: CapNegotiator,
+    sasl: Option<Sasl>,
+    isupport: ISupport,
+    outgoing: VecDeque<Bytes>,
+    events: VecDeque<SessionEvent>,
+}
+
+impl Debug for ClientSession {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ClientSession")
+            .field("nick", &self.nick)
+            .field("state", &self.state)
+            .field("cap", &self.cap.state())
+            .field("sasl", &self.sasl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientSession {
+    pub fn new(nick: impl Into<String>) -> Self {
+        Self {
+            nick: nick.into(),
+            alt_nicks: VecDeque::new(),
+            username: None,
+            realname: None,
+            password: None,
+            caps: Vec::new(),
+            mechanism: None,
+            state: SessionState::Idle,
+            cap: CapNegotiator::new(Vec::<String>::new()),
+            sasl: None,
+            isupport: ISupport::new(),
+            outgoing: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Nicknames tried in order when the current one is taken.
+    pub fn alt_nicks<I, S>(mut self, nicks: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.alt_nicks = nicks.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Defaults to the nickname.
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Defaults to the nickname.
+    pub fn realname(mut self, realname: impl Into<String>) -> Self {
+        self.realname = Some(realname.into());
+        self
+    }
+
+    /// Sends `PASS`, e.g. `oauth:<token>` on Twitch.
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Capabilities to request if the server offers them.
+    pub fn caps<I, S>(mut self, caps: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.caps = caps.into_iter().map(|c| c.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// Authenticates with SASL; the `sasl` capability is requested as well.
+    pub fn sasl(mut self, mechanism: Mechanism) -> Self {
+        self.mechanism = Some(mechanism);
+        self
+    }
+
+    /// Queues `CAP LS`, `PASS`, `NICK` and `USER`.
+    ///
+    /// Nothing is queued if the nickname, username, realname or password
+    /// cannot be serialized.
+    pub fn start(&mut self) -> Result<(), SerError> {
+        let pass = match &self.password {
+            Some(password) => Some(to_bytes(&Pass { password })?),
+            None => None,
+        };
+        let nick = to_bytes(&Nick {
+            nickname: &self.nick,
+        })?;
+        let username = self.username.as_deref().unwrap_or(&self.nick);
+        let user = to_bytes(&User {
+            username,
+            realname: self.realname.as_deref().unwrap_or(username),
+        })?;
+
+        let mut wanted = self.caps.clone();
+        if self.mechanism.is_some() && !wanted.iter().any(|c| c == "sasl") {
+            wanted.push("sasl".to_owned());
+        }
+
+        self.cap = CapNegotiator::new(wanted).hold_end(self.mechanism.is_some());
+        self.sasl = self.mechanism.clone().map(Sasl::new);
+        self.state = SessionState::Registering;
+
+        self.cap.start();
+        self.flush();
+
+        self.outgoing.extend(pass);
+        self.outgoing.push_back(nick);
+        self.outgoing.push_back(user);
+        Ok(())
+    }
+
+    /// Processes an incoming message.
+    ///
+    /// Returns `Ok(true)` if the message was part of registration or a `PING`.
+    ///
+    /// # Errors
+    ///
+    /// - [`SessionError::NickUnavailable`] if the nickname is taken during
+    ///   registration and no alternative is left
+    /// - [`SessionError::Ser`] if a reply cannot be serialized, e.g. an
+    ///   invalid alternative nickname
+    /// - [`SessionError::Cap`] if a wanted capability cannot be requested; the
+    ///   others are still negotiated
+    /// - [`SessionError::Sasl`] if a SASL challenge cannot be answered; the
+    ///   exchange is aborted and registration continues
+    pub fn handle(&mut self, msg: &Message<'_>) -> Result<bool, SessionError> {
+        let command = msg.command();
+
+        if command == Commands::PING {
+            let ping = Ping::from_message(msg)?;
+            self.outgoing.push_back(to_bytes(&Pong::reply(&ping))?);
+            return Ok(true);
+        }
+
+        let result = self.cap.handle(msg);
+        self.flush();
+        // A capability that could not be requested still moves the
+        // negotiation on.
+        if !matches!(result, Ok(false)) {
+            if self.cap.state() == CapState::Ready {
+                self.authenticate();
+            }
+            return Ok(result?);
+        }
+
+        if let Some(sasl) = &mut self.sasl {
+            let result = sasl.handle(msg);
+            self.flush();
+            if result? {
+                self.finish_sasl();
+                return Ok(true);
+            }
+        }
+
+        if command == Commands::NICK {
+            return Ok(self.handle_nick(msg));
+        }
+
+        if command == Commands::ERROR {
+            let reason = msg.params().get(0).unwrap_or_default();
+            self.state = SessionState::Closed;
+            self.events.push_back(SessionEvent::Closed {
+                reason: reason.to_owned(),
+            });
+            return Ok(true);
+        }
+
+        let params = msg.params();
+        match command.numeric() {
+            Some(Numeric::RPL_WELCOME) => {
+                if let Some(nick) = params.get(0) {
+                    self.nick = nick.to_owned();
+                }
+                self.state = SessionState::Registered;
+                self.events.push_back(SessionEvent::Registered {
+                    nick: self.nick.clone(),
+                });
+                Ok(true)
+            }
+            Some(Numeric::RPL_ISUPPORT) => {
+                self.isupport.extend_reply(msg);
+                Ok(true)
+            }
+            Some(
+                Numeric::ERR_NICKNAMEINUSE
+                | Numeric::ERR_ERRONEUSNICKNAME
+                | Numeric::ERR_UNAVAILRESOURCE,
+            ) if self.state == SessionState::Registering => {
+                let Some(next) = self.alt_nicks.pop_front() else {
+                    return Err(SessionError::NickUnavailable {
+                        nick: self.nick.clone(),
+                    });
+                };
+                self.nick = next;
+                self.send_nick()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Returns the next message to send.
+    pub fn poll_transmit(&mut self) -> Option<Bytes> {
+        self.outgoing.pop_front()
+    }
+
+    /// Returns the next event.
+    pub fn poll_event(&mut self) -> Option<SessionEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn is_registered(&self) -> bool {
+        self.state == SessionState::Registered
+    }
+
+    /// Our current nickname, or the one being tried during registration.
+    pub fn nick(&self) -> &str {
+        &self.nick
+    }
+
+    pub fn isupport(&self) -> &ISupport {
+        &self.isupport
+    }
+
+    /// The capability negotiation, e.g. to check enabled capabilities.
+    pub fn cap(&self) -> &CapNegotiator {
+        &self.cap
+    }
+
+    pub fn authentication(&self) -> Option<&Sasl> {
+        self.sasl.as_ref()
+    }
+
+    fn handle_nick(&mut self, msg: &Message<'_>) -> bool {
+        let is_own = msg
+            .source()
+            .is_some_and(|s| s.name.eq_ignore_ascii_case(&self.nick));
+        if !is_own {
+            return false;
+        }
+
+        let Ok(nick) = Nick::from_message(msg) else {
+            return false;
+        };
+        let old = core::mem::replace(&mut self.nick, nick.nickname.to_owned());
+        self.events.push_back(SessionEvent::NickChanged {
+            old,
+            new: self.nick.clone(),
+        });
+        true
+    }
+
+    /// Starts SASL once capabilities are negotiated, or ends negotiation if
+    /// the server lacks `sasl`.
+    fn authenticate(&mut self) {
+        let Some(sasl) = &mut self.sasl else {
+            self.cap.end();
+            self.flush();
+            return;
+        };
+
+        if sasl.state() != SaslState::Idle {
+            return;
+        }
+
+        if self.cap.is_enabled("sasl") {
+            sasl.start();
+        } else {
+            self.events
+                .push_back(SessionEvent::SaslFailed { outcome: None });
+            self.cap.end();
+        }
+        self.flush();
+    }
+
+    fn finish_sasl(&mut self) {
+        let Some(sasl) = &self.sasl else {
+            return;
+        };
+        let Some(outcome) = sasl.outcome() else {
+            return;
+        };
+        if self.cap.state() != CapState::Ready {
+            return;
+        }
+
+        let event = match outcome {
+            SaslOutcome::Success => SessionEvent::LoggedIn {
+                account: sasl.account().map(ToOwned::to_owned),
+            },
+            outcome => SessionEvent::SaslFailed {
+                outcome: Some(outcome),
+            },
+        };
+        self.events.push_back(event);
+        self.cap.end();
+        self.flush();
+    }
+
+    fn send_nick(&mut self) -> Result<(), SerError> {
+        let nick = Nick {
+            nickname: &self.nick,
+        };
+        self.outgoing.push_back(to_bytes(&nick)?);
+        Ok(())
+    }
+
+    /// Moves queued lines of the negotiation and SASL into `outgoing`,
+    /// keeping their order.
+    fn flush(&mut self) {
+        while let Some(bytes) = self.cap.poll_transmit() {
+            self.outgoing.push_back(bytes);
+        }
+        if let Some(sasl) = &mut self.sasl {
+            while let Some(bytes) = sasl.poll_transmit() {
+                self.outgoing.push_back(bytes);
+            }
+        }
+    }
+}
+
+fn to_bytes<T: ToMessage>(msg: &T) -> Result<Bytes, SerError> {
+    let mut serializer = IRCSerializer::new();
+    msg.to_message(&mut serializer)?;
+    Ok(serializer.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(session: &mut ClientSession, lines: &[&str]) {
+        for line in lines {
+            session.handle(&crate::parse(line).unwrap()).unwrap();
+        }
+    }
+
+    fn drain(session: &mut ClientSession) -> Vec<String> {
+        core::iter::from_fn(|| session.poll_transmit())
+            .map(|b| String::from_utf8(b.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn sasl_registration() {
+        let mut session = ClientSession::new("jilles")
+            .username("j")
+            .realname("Jilles")
+            .caps(["multi-prefix"])
+            .sasl(Mechanism::plain("jilles", "sesame"));
+        session.start().unwrap();
+        assert_eq!(
+            [
+                "CAP LS 302\r\n",
+                "NICK jilles\r\n",
+                "USER j 0 * :Jilles\r\n"
+            ],
+            drain(&mut session).as_slice()
+        );
+
+        feed(
+            &mut session,
+            &[":irc.example.com CAP * LS :sasl=PLAIN multi-prefix"],
+        );
+        assert_eq!(
+            ["CAP REQ :multi-prefix sasl\r\n"],
+            drain(&mut session).as_slice()
+        );
+
+        feed(
+            &mut session,
+            &[":irc.example.com CAP * ACK :multi-prefix sasl"],
+        );
+        assert_eq!(["AUTHENTICATE PLAIN\r\n"], drain(&mut session).as_slice());
+
+        feed(
+            &mut session,
+            &[
+                "AUTHENTICATE +",
+                ":irc.example.com 900 jilles jilles!j@localhost jilles :You are now logged in",
+                ":irc.example.com 903 jilles :SASL authentication successful",
+            ],
+        );
+        assert_eq!(
+            ["AUTHENTICATE AGppbGxlcwBzZXNhbWU=\r\n", "CAP END\r\n"],
+            drain(&mut session).as_slice()
+        );
+        assert_eq!(
+            Some(SessionEvent::LoggedIn {
+                account: Some("jilles".to_owned())
+            }),
+            session.poll_event()
+        );
+        assert_eq!(SessionState::Registering, session.state());
+
+        feed(&mut session, &[":irc.example.com 001 jilles :Welcome"]);
+        assert!(session.is_registered());
+        assert!(session.cap().is_enabled("multi-prefix"));
+        assert_eq!(
+            Some(SessionEvent::Registered {
+                nick: "jilles".to_owned()
+            }),
+            session.poll_event()
+        );
+    }
+
+    #[test]
+    fn sasl_unavailable() {
+        let mut session = ClientSession::new("nick").sasl(Mechanism::plain("nick", "password"));
+        session.start().unwrap();
+        drain(&mut session);
+
+        feed(&mut session, &[":irc.example.com CAP * LS :multi-prefix"]);
+        assert_eq!(["CAP END\r\n"], drain(&mut session).as_slice());
+        assert_eq!(
+            Some(SessionEvent::SaslFailed { outcome: None }),
+            session.poll_event()
+        );
+
+        let mut session = ClientSession::new("nick").sasl(Mechanism::plain("nick", "password"));
+        session.start().unwrap();
+        feed(
+            &mut session,
+            &[
+                ":irc.example.com CAP * LS :sasl",
+                ":irc.example.com CAP * ACK :sasl",
+                "AUTHENTICATE +",
+                ":irc.example.com 904 nick :SASL authentication failed",
+            ],
+        );
+        assert_eq!(
+            Some("CAP END\r\n"),
+            drain(&mut session).last().map(|s| s.as_str())
+        );
+        assert_eq!(
+            Some(SessionEvent::SaslFailed {
+                outcome: Some(SaslOutcome::Failed)
+            }),
+            session.poll_event()
+        );
+    }
+
+    #[test]
+    fn invalid_registration() {
+        let mut session = ClientSession::new("bad nick");
+        assert!(session.start().is_err());
+        assert_eq!(SessionState::Idle, session.state());
+        assert!(drain(&mut session).is_empty());
+
+        let mut session = ClientSession::new("nick").realname("bad\r\nname");
+        assert!(session.start().is_err());
+        assert!(drain(&mut session).is_empty());
+
+        let mut session = ClientSession::new("nick").password("pass\r\nword");
+        assert!(session.start().is_err());
+        assert!(drain(&mut session).is_empty());
+    }
+
+    #[test]
+    fn alt_nick_invalid() {
+        let mut session = ClientSession::new("nick").alt_nicks(["bad\0nick"]);
+        session.start().unwrap();
+        drain(&mut session);
+
+        let err = session
+            .handle(&crate::parse(":irc.example.com 433 * nick :in use").unwrap())
+            .unwrap_err();
+        assert!(matches!(err, SessionError::Ser(_)));
+    }
+
+    #[test]
+    fn twitch() {
+        let mut session = ClientSession::new("bot")
+            .password("oauth:token")
+            .caps(["twitch.tv/tags", "twitch.tv/commands"]);
+        session.start().unwrap();
+        assert_eq!(
+            [
+                "CAP LS 302\r\n",
+                "PASS oauth:token\r\n",
+                "NICK bot\r\n",
+                "USER bot 0 * :bot\r\n"
+            ],
+            drain(&mut session).as_slice()
+        );
+
+        feed(
+            &mut session,
+            &[
+                ":tmi.twitch.tv CAP * LS :twitch.tv/membership twitch.tv/tags twitch.tv/commands",
+                ":tmi.twitch.tv CAP * ACK :twitch.tv/tags twitch.tv/commands",
+                ":tmi.twitch.tv 001 bot :Welcome, GLHF!",
+            ],
+        );
+        assert_eq!(
+            [
+                "CAP REQ :twitch.tv/tags twitch.tv/commands\r\n",
+                "CAP END\r\n"
+            ],
+            drain(&mut session).as_slice()
+        );
+        assert!(session.is_registered());
+    }
+
+    #[test]
+    fn nick_fallback() {
+        let mut session = ClientSession::new("nick").alt_nicks(["nick_", "nick__"]);
+        session.start().unwrap();
+        drain(&mut session);
+
+        feed(
+            &mut session,
+            &[
+                ":irc.example.com 421 * CAP :Unknown command",
+                ":irc.example.com 433 * nick :Nickname is already in use",
+                ":irc.example.com 437 * nick_ :Nick/channel is temporarily unavailable",
+            ],
+        );
+        assert_eq!(
+            ["NICK nick_\r\n", "NICK nick__\r\n"],
+            drain(&mut session).as_slice()
+        );
+        assert_eq!("nick__", session.nick());
+
+        let err = session
+            .handle(&crate::parse(":irc.example.com 433 * nick__ :in use").unwrap())
+            .unwrap_err();
+        assert!(err.is_nick_unavailable());
+
+        // After registration a collision is the caller's business.
+        feed(
+            &mut session,
+            &[
+                ":irc.example.com 001 nick__ :Welcome",
+                ":nick__!u@h NICK :ferris",
+                ":other!u@h NICK :crab",
+            ],
+        );
+        assert!(!session
+            .handle(&crate::parse(":irc.example.com 433 ferris x :in use").unwrap())
+            .unwrap());
+        assert_eq!("ferris", session.nick());
+        session.poll_event();
+        assert_eq!(
+            Some(SessionEvent::NickChanged {
+                old: "nick__".to_owned(),
+                new: "ferris".to_owned()
+            }),
+            session.poll_event()
+        );
+        assert_eq!(None, session.poll_event());
+    }
+
+    #[test]
+    fn ping_isupport_error() {
+        let mut session = ClientSession::new("nick");
+        feed(
+            &mut session,
+            &[
+                "PING :token",
+                ":irc.example.com 005 nick CHANTYPES=# EXCEPTS NICKLEN=30 :are supported",
+                ":irc.example.com 005 nick -EXCEPTS NICKLEN=31 :are supported",
+                "ERROR :Closing Link: (Ping timeout)",
+            ],
+        );
+        assert_eq!(["PONG token\r\n"], drain(&mut session).as_slice());
+
+        let isupport = session.isupport();
+        assert_eq!(Some("#"), isupport.get("CHANTYPES"));
+        assert_eq!(Some("31"), isupport.get("NICKLEN"));
+        assert!(!isupport.contains("EXCEPTS"));
+        assert!(!isupport.contains("are supported"));
+        assert_eq!(2, isupport.iter().count());
+
+        assert_eq!(SessionState::Closed, session.state());
+        assert_eq!(
+            Some(SessionEvent::Closed {
+                reason: "Closing Link: (Ping timeout)".to_owned()
+            }),
+            session.poll_event()
+        );
+    }
+}
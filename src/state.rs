@@ -0,0 +1,1114 @@
+//! Channel membership tracking.
+//!
+//! [`StateTracker`] follows who is in the channels we joined, with their
+//! membership prefixes, hostmasks, accounts and away status. It applies
+//! `JOIN` (including `extended-join`), `PART`, `KICK`, `QUIT`, `NICK`,
+//! `MODE`, `353`/`366` `NAMES` (with `multi-prefix` and `userhost-in-names`),
+//! `CHGHOST`, `SETNAME`, `ACCOUNT`, `AWAY` and `352` `WHO` replies. Nicknames
+//! and channel names are compared using the server's `CASEMAPPING`. It does
+//! not do any I/O.
+//!
+//! ```rust
+//! use ircv3_parse::state::StateTracker;
+//!
+//! let mut state = StateTracker::new();
+//! let lines = [
+//!     ":irc.example.com 001 ferris :Welcome",
+//!     ":irc.example.com 005 ferris CASEMAPPING=rfc1459 PREFIX=(ov)@+ :are supported by this server",
+//!     ":ferris!ferris@rust.example JOIN #rust",
+//!     ":irc.example.com 353 ferris = #rust :ferris @Corro[m]",
+//!     ":irc.example.com 366 ferris #rust :End of /NAMES list",
+//!     ":corro{m}!c@host MODE #rust +v ferris",
+//!     ":corro{m}!c@host ACCOUNT corro",
+//! ];
+//! for line in lines {
+//!     state.handle(&ircv3_parse::parse(line)?)?;
+//! }
+//!
+//! let channel = state.channel("#RUST").unwrap();
+//! assert!(channel.is_synced());
+//! assert_eq!(2, channel.members().count());
+//! assert!(state.is_at_least("#rust", "CORRO{M}", '@'));
+//! assert_eq!("+", state.member("#rust", "ferris").unwrap().prefixes);
+//! assert_eq!(Some("corro"), state.user("corro[m]").unwrap().account.as_deref());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::compat::{format, String, ToOwned, Vec};
+
+use crate::{
+    components::{Numeric, Source},
+    de::FromMessage,
+    messages::{
+        client::{Away, Join, Kick, Mode, Nick, Part},
+        replies::{Member, NamReply, WhoReply, DEFAULT_PREFIXES},
+        required,
+    },
+    session::ISupport,
+    Commands, DeError, Message,
+};
+
+/// Mode letters of [`DEFAULT_PREFIXES`], used until `PREFIX` is received.
+const DEFAULT_PREFIX_MODES: &str = "qaohv";
+
+/// `CHANMODES` used until the server sends its own.
+const DEFAULT_CHANMODES: [&str; 4] = ["beI", "k", "l", "imnpst"];
+
+/// How nicknames and channel names are compared, from `ISUPPORT CASEMAPPING`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CaseMapping {
+    /// Only `A`-`Z` are folded to lowercase.
+    Ascii,
+    /// Like [`CaseMapping::Ascii`], and `[]\~` are the uppercase of `{}|^`.
+    /// Assumed until the server says otherwise.
+    #[default]
+    Rfc1459,
+    /// Like [`CaseMapping::Rfc1459`] without `~` and `^`.
+    StrictRfc1459,
+}
+
+impl CaseMapping {
+    /// Parses a `CASEMAPPING` value, `None` for one not known here.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ascii" => Some(Self::Ascii),
+            "rfc1459" => Some(Self::Rfc1459),
+            "strict-rfc1459" => Some(Self::StrictRfc1459),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ascii => "ascii",
+            Self::Rfc1459 => "rfc1459",
+            Self::StrictRfc1459 => "strict-rfc1459",
+        }
+    }
+
+    /// Folds a single character to lowercase.
+    pub fn fold(&self, c: char) -> char {
+        match (self, c) {
+            (_, 'A'..='Z') => c.to_ascii_lowercase(),
+            (Self::Ascii, _) => c,
+            (_, '[') => '{',
+            (_, ']') => '}',
+            (_, '\\') => '|',
+            (Self::Rfc1459, '~') => '^',
+            _ => c,
+        }
+    }
+
+    /// Returns `name` folded to lowercase.
+    pub fn to_lowercase(&self, name: &str) -> String {
+        name.chars().map(|c| self.fold(c)).collect()
+    }
+
+    /// Returns `true` if both names are the same under this mapping.
+    pub fn equals(&self, a: &str, b: &str) -> bool {
+        // Only ASCII characters are folded, so lengths never change.
+        a.len() == b.len()
+            && a.chars()
+                .map(|c| self.fold(c))
+                .eq(b.chars().map(|c| self.fold(c)))
+    }
+}
+
+/// A user sharing at least one channel with us, or ourselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub nick: String,
+    pub user: Option<String>,
+    pub host: Option<String>,
+    /// From `extended-join`, `SETNAME` or `WHO`.
+    pub realname: Option<String>,
+    /// From `extended-join` or `ACCOUNT`, `None` if logged out or unknown.
+    pub account: Option<String>,
+    pub away: bool,
+    /// From `away-notify`, `None` if not away or the message is unknown.
+    pub away_message: Option<String>,
+}
+
+impl User {
+    fn new(nick: &str) -> Self {
+        Self {
+            nick: nick.to_owned(),
+            user: None,
+            host: None,
+            realname: None,
+            account: None,
+            away: false,
+            away_message: None,
+        }
+    }
+
+    /// Returns `nick!user@host`, with `*` for unknown parts.
+    pub fn hostmask(&self) -> String {
+        format!(
+            "{}!{}@{}",
+            self.nick,
+            self.user.as_deref().unwrap_or("*"),
+            self.host.as_deref().unwrap_or("*")
+        )
+    }
+
+    fn update_source(&mut self, source: &Source<'_>) {
+        if let Some(user) = source.user {
+            self.user = Some(user.to_owned());
+        }
+        if let Some(host) = source.host {
+            self.host = Some(host.to_owned());
+        }
+    }
+}
+
+/// A user's membership in a [`Channel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Membership {
+    pub nick: String,
+    /// All membership prefixes, highest first.
+    pub prefixes: String,
+}
+
+impl Membership {
+    pub fn highest_prefix(&self) -> Option<char> {
+        self.prefixes.chars().next()
+    }
+
+    pub fn has_prefix(&self, prefix: char) -> bool {
+        self.prefixes.contains(prefix)
+    }
+}
+
+/// A channel we joined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Channel {
+    /// The channel as sent in our `JOIN`.
+    pub name: String,
+    members: Vec<Membership>,
+    /// Modes without a list, with their parameter if any.
+    modes: Vec<(char, Option<String>)>,
+    receiving_names: bool,
+    synced: bool,
+}
+
+impl Channel {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            members: Vec::new(),
+            modes: Vec::new(),
+            receiving_names: false,
+            synced: false,
+        }
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = &Membership> {
+        self.members.iter()
+    }
+
+    /// Returns `true` once the `NAMES` list sent after joining is complete.
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    /// Returns `true` if a channel mode such as `m` or `k` is set.
+    ///
+    /// Only modes changed by `MODE` since joining are known.
+    pub fn has_mode(&self, mode: char) -> bool {
+        self.modes.iter().any(|(m, _)| *m == mode)
+    }
+
+    /// Returns the parameter of a channel mode such as `k` or `l`.
+    pub fn mode_param(&self, mode: char) -> Option<&str> {
+        self.modes
+            .iter()
+            .find(|(m, _)| *m == mode)
+            .and_then(|(_, param)| param.as_deref())
+    }
+
+    fn set_mode(&mut self, mode: char, adding: bool, param: Option<&str>) {
+        self.modes.retain(|(m, _)| *m != mode);
+        if adding {
+            self.modes.push((mode, param.map(ToOwned::to_owned)));
+        }
+    }
+}
+
+/// Tracks the members of our channels and what we know about them.
+///
+/// Feed every incoming message to [`StateTracker::handle()`], starting with
+/// `001 RPL_WELCOME`, which tells us our nickname and starts over for the new
+/// connection. `005 RPL_ISUPPORT` provides `CASEMAPPING`, `PREFIX` and
+/// `CHANMODES`.
+///
+/// Users are forgotten once they share no channel with us anymore.
+#[derive(Debug, Clone)]
+pub struct StateTracker {
+    nick: Option<String>,
+    casemapping: CaseMapping,
+    /// Mode letters of `PREFIX`, highest first.
+    prefix_modes: String,
+    /// Prefix characters of `PREFIX`, in the same order as `prefix_modes`.
+    prefixes: String,
+    /// `CHANMODES` types A (lists), B, C and D.
+    chanmodes: [String; 4],
+    isupport: ISupport,
+    channels: Vec<Channel>,
+    users: Vec<User>,
+}
+
+impl Default for StateTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateTracker {
+    pub fn new() -> Self {
+        Self {
+            nick: None,
+            casemapping: CaseMapping::default(),
+            prefix_modes: DEFAULT_PREFIX_MODES.to_owned(),
+            prefixes: DEFAULT_PREFIXES.to_owned(),
+            chanmodes: DEFAULT_CHANMODES.map(ToOwned::to_owned),
+            isupport: ISupport::new(),
+            channels: Vec::new(),
+            users: Vec::new(),
+        }
+    }
+
+    /// Processes an incoming message.
+    ///
+    /// Returns `Ok(true)` if the message changed what we know, and `Ok(false)`
+    /// for any other message, including those about channels we are not in.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DeError`] if one of the handled messages is malformed, e.g. a
+    /// `JOIN` without a source.
+    pub fn handle(&mut self, msg: &Message<'_>) -> Result<bool, DeError> {
+        let command = msg.command();
+
+        if command == Commands::JOIN {
+            self.handle_join(msg)
+        } else if command == Commands::PART {
+            let source = source(msg)?;
+            let part = Part::from_message(msg)?;
+            let mut applied = false;
+            for channel in part.channels {
+                applied |= self.remove_member(channel, source.name);
+            }
+            self.prune();
+            Ok(applied)
+        } else if command == Commands::KICK {
+            let kick = Kick::from_message(msg)?;
+            let applied = self.remove_member(kick.channel, kick.user);
+            self.prune();
+            Ok(applied)
+        } else if command == Commands::QUIT {
+            self.handle_quit(msg)
+        } else if command == Commands::NICK {
+            self.handle_nick(msg)
+        } else if command == Commands::MODE {
+            self.handle_mode(msg)
+        } else if command == Commands::CHGHOST {
+            // CHGHOST <new_user> <new_host>
+            let params = msg.params();
+            let (user, host) = (required(&params, 0)?, required(&params, 1)?);
+            self.update_user(msg, |u| {
+                u.user = Some(user.to_owned());
+                u.host = Some(host.to_owned());
+            })
+        } else if command == Commands::SETNAME {
+            let realname = required(&msg.params(), 0)?;
+            self.update_user(msg, |u| u.realname = Some(realname.to_owned()))
+        } else if command == Commands::ACCOUNT {
+            let account = required(&msg.params(), 0)?;
+            self.update_user(msg, |u| u.account = account_name(account))
+        } else if command == Commands::AWAY {
+            let away = Away::from_message(msg)?;
+            self.update_user(msg, |u| {
+                u.away = away.is_away();
+                u.away_message = away.message.filter(|_| u.away).map(ToOwned::to_owned);
+            })
+        } else {
+            self.handle_numeric(msg)
+        }
+    }
+
+    /// Our nickname, known after `001 RPL_WELCOME`.
+    pub fn nick(&self) -> Option<&str> {
+        self.nick.as_deref()
+    }
+
+    pub fn casemapping(&self) -> CaseMapping {
+        self.casemapping
+    }
+
+    /// Membership prefix characters, highest first.
+    pub fn prefixes(&self) -> &str {
+        &self.prefixes
+    }
+
+    /// Tokens of `005 RPL_ISUPPORT` received since `001 RPL_WELCOME`.
+    pub fn isupport(&self) -> &ISupport {
+        &self.isupport
+    }
+
+    pub fn channel(&self, name: &str) -> Option<&Channel> {
+        self.channels
+            .iter()
+            .find(|c| self.casemapping.equals(&c.name, name))
+    }
+
+    pub fn channels(&self) -> impl Iterator<Item = &Channel> {
+        self.channels.iter()
+    }
+
+    pub fn user(&self, nick: &str) -> Option<&User> {
+        self.users
+            .iter()
+            .find(|u| self.casemapping.equals(&u.nick, nick))
+    }
+
+    pub fn users(&self) -> impl Iterator<Item = &User> {
+        self.users.iter()
+    }
+
+    /// Returns the membership of `nick` in `channel`.
+    pub fn member(&self, channel: &str, nick: &str) -> Option<&Membership> {
+        self.channel(channel)?
+            .members
+            .iter()
+            .find(|m| self.casemapping.equals(&m.nick, nick))
+    }
+
+    /// Returns the channels we share with `nick`.
+    pub fn channels_of<'s>(&'s self, nick: &'s str) -> impl Iterator<Item = &'s Channel> + 's {
+        self.channels.iter().filter(move |c| {
+            c.members
+                .iter()
+                .any(|m| self.casemapping.equals(&m.nick, nick))
+        })
+    }
+
+    /// Returns `true` if `nick` has `prefix` or a higher one in `channel`,
+    /// e.g. `'@'` for operators and above.
+    pub fn is_at_least(&self, channel: &str, nick: &str, prefix: char) -> bool {
+        let rank = |p: char| self.prefixes.chars().position(|c| c == p);
+        let (Some(member), Some(wanted)) = (self.member(channel, nick), rank(prefix)) else {
+            return false;
+        };
+        member
+            .highest_prefix()
+            .and_then(rank)
+            .is_some_and(|rank| rank <= wanted)
+    }
+
+    fn handle_join(&mut self, msg: &Message<'_>) -> Result<bool, DeError> {
+        let source = source(msg)?;
+        let join = Join::from_message(msg)?;
+        let Some(name) = join.channels.first() else {
+            return Ok(false);
+        };
+
+        let index = match self.channel_index(name) {
+            Some(index) => index,
+            None if self.is_own(source.name) => {
+                self.channels.push(Channel::new(name));
+                self.channels.len() - 1
+            }
+            None => return Ok(false),
+        };
+
+        let casemapping = self.casemapping;
+        let channel = &mut self.channels[index];
+        if !channel
+            .members
+            .iter()
+            .any(|m| casemapping.equals(&m.nick, source.name))
+        {
+            channel.members.push(Membership {
+                nick: source.name.to_owned(),
+                prefixes: String::new(),
+            });
+        }
+
+        let user = self.user_entry(source.name);
+        user.update_source(&source);
+
+        // extended-join: JOIN <channel> <account> :<realname>
+        let params = msg.params();
+        if let (Some(account), Some(realname)) = (params.get(1), params.get(2)) {
+            user.account = account_name(account);
+            user.realname = Some(realname.to_owned());
+        }
+        Ok(true)
+    }
+
+    fn handle_quit(&mut self, msg: &Message<'_>) -> Result<bool, DeError> {
+        let source = source(msg)?;
+        if self.is_own(source.name) {
+            self.channels.clear();
+            self.prune();
+            return Ok(true);
+        }
+
+        let Some(index) = self.user_index(source.name) else {
+            return Ok(false);
+        };
+        let casemapping = self.casemapping;
+        for channel in &mut self.channels {
+            channel
+                .members
+                .retain(|m| !casemapping.equals(&m.nick, source.name));
+        }
+        self.users.remove(index);
+        Ok(true)
+    }
+
+    fn handle_nick(&mut self, msg: &Message<'_>) -> Result<bool, DeError> {
+        let source = source(msg)?;
+        let new = Nick::from_message(msg)?.nickname;
+
+        if self.is_own(source.name) {
+            self.nick = Some(new.to_owned());
+        }
+        let Some(index) = self.user_index(source.name) else {
+            return Ok(false);
+        };
+
+        let casemapping = self.casemapping;
+        for channel in &mut self.channels {
+            if let Some(member) = channel
+                .members
+                .iter_mut()
+                .find(|m| casemapping.equals(&m.nick, source.name))
+            {
+                member.nick = new.to_owned();
+            }
+        }
+        let user = &mut self.users[index];
+        user.nick = new.to_owned();
+        user.update_source(&source);
+        Ok(true)
+    }
+
+    fn handle_mode(&mut self, msg: &Message<'_>) -> Result<bool, DeError> {
+        let mode = Mode::from_message(msg)?;
+        let (Some(index), Some(modes)) = (self.channel_index(mode.target), mode.modes) else {
+            return Ok(false);
+        };
+
+        let casemapping = self.casemapping;
+        let channel = &mut self.channels[index];
+        let mut args = modes.args.iter().copied();
+        let mut adding = true;
+
+        for c in modes.modestring.chars() {
+            match c {
+                '+' => adding = true,
+                '-' => adding = false,
+                _ => {
+                    if let Some(rank) = self.prefix_modes.chars().position(|m| m == c) {
+                        let (Some(nick), Some(prefix)) =
+                            (args.next(), self.prefixes.chars().nth(rank))
+                        else {
+                            continue;
+                        };
+                        let Some(member) = channel
+                            .members
+                            .iter_mut()
+                            .find(|m| casemapping.equals(&m.nick, nick))
+                        else {
+                            continue;
+                        };
+
+                        if adding {
+                            member.prefixes.push(prefix);
+                            member.prefixes = sort_prefixes(&member.prefixes, &self.prefixes);
+                        } else {
+                            member.prefixes.retain(|p| p != prefix);
+                        }
+                        continue;
+                    }
+
+                    match self.chanmodes.iter().position(|group| group.contains(c)) {
+                        // Lists such as bans are not tracked.
+                        Some(0) => {
+                            args.next();
+                        }
+                        Some(1) => channel.set_mode(c, adding, args.next()),
+                        // Only takes a parameter when set.
+                        Some(2) => {
+                            let param = if adding { args.next() } else { None };
+                            channel.set_mode(c, adding, param);
+                        }
+                        _ => channel.set_mode(c, adding, None),
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn handle_numeric(&mut self, msg: &Message<'_>) -> Result<bool, DeError> {
+        let params = msg.params();
+
+        match msg.command().numeric() {
+            Some(Numeric::RPL_WELCOME) => {
+                *self = Self::new();
+                let nick = required(&params, 0)?;
+                self.nick = Some(nick.to_owned());
+                self.users.push(User::new(nick));
+                Ok(true)
+            }
+            Some(Numeric::RPL_ISUPPORT) => {
+                self.isupport.extend_reply(msg);
+                self.apply_isupport();
+                Ok(true)
+            }
+            Some(Numeric::RPL_NAMREPLY) => {
+                let reply = NamReply::from_message(msg)?;
+                let Some(index) = self.channel_index(reply.channel) else {
+                    return Ok(false);
+                };
+                let members: Vec<Member<'_>> =
+                    reply.members_with_prefixes(&self.prefixes).collect();
+
+                let casemapping = self.casemapping;
+                let channel = &mut self.channels[index];
+                // A new list replaces the old one, it may span several replies.
+                if !channel.receiving_names {
+                    channel.receiving_names = true;
+                    channel.members.clear();
+                }
+                for member in &members {
+                    let prefixes = sort_prefixes(member.prefixes, &self.prefixes);
+                    match channel
+                        .members
+                        .iter_mut()
+                        .find(|m| casemapping.equals(&m.nick, member.nick))
+                    {
+                        Some(existing) => existing.prefixes = prefixes,
+                        None => channel.members.push(Membership {
+                            nick: member.nick.to_owned(),
+                            prefixes,
+                        }),
+                    }
+                }
+
+                for member in members {
+                    let user = self.user_entry(member.nick);
+                    if let Some(name) = member.user {
+                        user.user = Some(name.to_owned());
+                    }
+                    if let Some(host) = member.host {
+                        user.host = Some(host.to_owned());
+                    }
+                }
+                Ok(true)
+            }
+            Some(Numeric::RPL_ENDOFNAMES) => {
+                let Some(index) = self.channel_index(required(&params, 1)?) else {
+                    return Ok(false);
+                };
+                let channel = &mut self.channels[index];
+                channel.receiving_names = false;
+                channel.synced = true;
+                self.prune();
+                Ok(true)
+            }
+            Some(Numeric::RPL_WHOREPLY) => self.handle_who(&WhoReply::from_message(msg)?),
+            Some(Numeric::RPL_NOWAWAY) => Ok(self.update_own(|u| u.away = true)),
+            Some(Numeric::RPL_UNAWAY) => Ok(self.update_own(|u| {
+                u.away = false;
+                u.away_message = None;
+            })),
+            _ => Ok(false),
+        }
+    }
+
+    fn handle_who(&mut self, reply: &WhoReply<'_>) -> Result<bool, DeError> {
+        let Some(index) = self.user_index(reply.nick) else {
+            return Ok(false);
+        };
+
+        let user = &mut self.users[index];
+        user.user = Some(reply.username.to_owned());
+        user.host = Some(reply.host.to_owned());
+        user.realname = Some(reply.realname.to_owned());
+        user.away = reply.is_away();
+        if !user.away {
+            user.away_message = None;
+        }
+
+        let Some(channel) = reply.channel.and_then(|c| self.channel_index(c)) else {
+            return Ok(true);
+        };
+        let prefixes = sort_prefixes(reply.flags, &self.prefixes);
+        let order = &self.prefixes;
+        let casemapping = self.casemapping;
+        if let Some(member) = self.channels[channel]
+            .members
+            .iter_mut()
+            .find(|m| casemapping.equals(&m.nick, reply.nick))
+        {
+            // With multi-prefix the reply lists them all. Without it only the
+            // highest is sent, so keep the ones we know that rank below it.
+            member.prefixes = if prefixes.chars().count() > 1 {
+                prefixes
+            } else {
+                let below = prefixes
+                    .chars()
+                    .next()
+                    .and_then(|highest| order.find(highest))
+                    .map_or("", |i| &order[i..]);
+                let known: String = member
+                    .prefixes
+                    .chars()
+                    .filter(|p| below.contains(*p))
+                    .collect();
+                sort_prefixes(&(prefixes + &known), order)
+            };
+        }
+        Ok(true)
+    }
+
+    /// Reads `CASEMAPPING`, `PREFIX` and `CHANMODES` from the ISUPPORT
+    /// tokens, using the defaults for those that are missing.
+    fn apply_isupport(&mut self) {
+        self.casemapping = match self.isupport.get("CASEMAPPING") {
+            Some(value) => CaseMapping::parse(value).unwrap_or(CaseMapping::Ascii),
+            None => CaseMapping::default(),
+        };
+
+        // PREFIX=(qaohv)~&@%+
+        let (modes, prefixes) = match self.isupport.get("PREFIX") {
+            Some(value) => value
+                .strip_prefix('(')
+                .and_then(|v| v.split_once(')'))
+                .unwrap_or_default(),
+            None => (DEFAULT_PREFIX_MODES, DEFAULT_PREFIXES),
+        };
+        self.prefix_modes = modes.to_owned();
+        self.prefixes = prefixes.to_owned();
+
+        // CHANMODES=A,B,C,D
+        self.chanmodes = match self.isupport.get("CHANMODES") {
+            Some(value) => {
+                let mut groups = value.split(',');
+                [(); 4].map(|()| groups.next().unwrap_or_default().to_owned())
+            }
+            None => DEFAULT_CHANMODES.map(ToOwned::to_owned),
+        };
+    }
+
+    /// Removes `nick` from `channel`, or the whole channel if it is us.
+    fn remove_member(&mut self, channel: &str, nick: &str) -> bool {
+        let Some(index) = self.channel_index(channel) else {
+            return false;
+        };
+
+        if self.is_own(nick) {
+            self.channels.remove(index);
+            return true;
+        }
+        let casemapping = self.casemapping;
+        let members = &mut self.channels[index].members;
+        let len = members.len();
+        members.retain(|m| !casemapping.equals(&m.nick, nick));
+        members.len() != len
+    }
+
+    /// Applies `update` to the source of `msg` if we know them.
+    fn update_user(
+        &mut self,
+        msg: &Message<'_>,
+        update: impl FnOnce(&mut User),
+    ) -> Result<bool, DeError> {
+        let source = source(msg)?;
+        let Some(index) = self.user_index(source.name) else {
+            return Ok(false);
+        };
+        let user = &mut self.users[index];
+        user.update_source(&source);
+        update(user);
+        Ok(true)
+    }
+
+    fn update_own(&mut self, update: impl FnOnce(&mut User)) -> bool {
+        let Some(index) = self.nick.as_deref().and_then(|n| self.user_index(n)) else {
+            return false;
+        };
+        update(&mut self.users[index]);
+        true
+    }
+
+    /// Forgets users who share no channel with us.
+    fn prune(&mut self) {
+        let casemapping = self.casemapping;
+        let (nick, channels) = (self.nick.as_deref(), &self.channels);
+        self.users.retain(|u| {
+            nick.is_some_and(|n| casemapping.equals(n, &u.nick))
+                || channels.iter().any(|c| {
+                    c.members
+                        .iter()
+                        .any(|m| casemapping.equals(&m.nick, &u.nick))
+                })
+        });
+    }
+
+    fn is_own(&self, nick: &str) -> bool {
+        self.nick
+            .as_deref()
+            .is_some_and(|n| self.casemapping.equals(n, nick))
+    }
+
+    fn channel_index(&self, name: &str) -> Option<usize> {
+        self.channels
+            .iter()
+            .position(|c| self.casemapping.equals(&c.name, name))
+    }
+
+    fn user_index(&self, nick: &str) -> Option<usize> {
+        self.users
+            .iter()
+            .position(|u| self.casemapping.equals(&u.nick, nick))
+    }
+
+    fn user_entry(&mut self, nick: &str) -> &mut User {
+        let index = match self.user_index(nick) {
+            Some(index) => index,
+            None => {
+                self.users.push(User::new(nick));
+                self.users.len() - 1
+            }
+        };
+        &mut self.users[index]
+    }
+}
+
+fn source<'a>(msg: &Message<'a>) -> Result<Source<'a>, DeError> {
+    msg.source().ok_or_else(DeError::source_component_not_found)
+}
+
+/// `*` means not logged in.
+fn account_name(account: &str) -> Option<String> {
+    Some(account).filter(|a| *a != "*").map(ToOwned::to_owned)
+}
+
+/// Keeps the characters of `prefixes` found in `order`, sorted by it.
+fn sort_prefixes(prefixes: &str, order: &str) -> String {
+    let rank = |p: char| order.chars().position(|c| c == p);
+    let mut sorted: Vec<char> = prefixes.chars().filter(|c| rank(*c).is_some()).collect();
+    sorted.sort_by_key(|c| rank(*c));
+    sorted.dedup();
+    sorted.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(state: &mut StateTracker, lines: &[&str]) {
+        for line in lines {
+            state.handle(&crate::parse(line).unwrap()).unwrap();
+        }
+    }
+
+    fn nicks(state: &StateTracker, channel: &str) -> Vec<String> {
+        state
+            .channel(channel)
+            .unwrap()
+            .members()
+            .map(|m| format!("{}{}", m.prefixes, m.nick))
+            .collect()
+    }
+
+    fn registered() -> StateTracker {
+        let mut state = StateTracker::new();
+        replay(
+            &mut state,
+            &[
+                ":irc.example.com 001 ferris :Welcome",
+                ":irc.example.com 005 ferris CASEMAPPING=rfc1459 CHANMODES=beI,k,l,imnst \
+                 PREFIX=(qaohv)~&@%+ :are supported by this server",
+            ],
+        );
+        state
+    }
+
+    #[test]
+    fn casemapping() {
+        let rfc = CaseMapping::Rfc1459;
+        assert!(rfc.equals("Ferris[a]~", "ferris{A}^"));
+        assert!(!CaseMapping::StrictRfc1459.equals("a~", "a^"));
+        assert!(CaseMapping::StrictRfc1459.equals("a\\", "A|"));
+        assert!(!CaseMapping::Ascii.equals("a[", "a{"));
+        assert_eq!("ferris{}|^", rfc.to_lowercase("FERRIS[]\\~"));
+        assert_eq!(Some(CaseMapping::Ascii), CaseMapping::parse("ascii"));
+        assert_eq!(None, CaseMapping::parse("rfc7613"));
+    }
+
+    #[test]
+    fn isupport() {
+        let mut state = registered();
+        assert_eq!(CaseMapping::Rfc1459, state.casemapping());
+        assert_eq!(Some("beI,k,l,imnst"), state.isupport().get("CHANMODES"));
+
+        replay(
+            &mut state,
+            &[":irc.example.com 005 ferris PREFIX=(ov)@+ :are supported by this server"],
+        );
+        assert_eq!("@+", state.prefixes());
+        assert_eq!(CaseMapping::Rfc1459, state.casemapping());
+
+        // Removed tokens fall back to the defaults.
+        replay(
+            &mut state,
+            &[":irc.example.com 005 ferris -PREFIX -CASEMAPPING :are supported by this server"],
+        );
+        assert_eq!(DEFAULT_PREFIXES, state.prefixes());
+        assert_eq!(CaseMapping::default(), state.casemapping());
+        assert!(!state.isupport().contains("PREFIX"));
+    }
+
+    #[test]
+    fn names_and_modes() {
+        let mut state = registered();
+        replay(
+            &mut state,
+            &[
+                ":ferris!f@rust.example JOIN #rust",
+                ":irc.example.com 353 ferris = #rust :ferris @%bob!b@bob.example +carol!c@carol.example",
+                ":irc.example.com 353 ferris = #rust :~dave!d@dave.example",
+                ":irc.example.com 366 ferris #rust :End of /NAMES list",
+            ],
+        );
+
+        let channel = state.channel("#Rust").unwrap();
+        assert!(channel.is_synced());
+        assert_eq!(
+            ["ferris", "@%bob", "+carol", "~dave"],
+            nicks(&state, "#rust").as_slice()
+        );
+        assert_eq!("bob!b@bob.example", state.user("BOB").unwrap().hostmask());
+
+        replay(
+            &mut state,
+            &[
+                ":dave!d@dave.example MODE #rust +vo-o+kl ferris ferris bob secret 50",
+                ":dave!d@dave.example MODE #rust +b-l *!*@spam.example",
+                ":dave!d@dave.example MODE #other +o ferris",
+            ],
+        );
+        assert_eq!(
+            ["@+ferris", "%bob", "+carol", "~dave"],
+            nicks(&state, "#rust").as_slice()
+        );
+        assert!(state.is_at_least("#rust", "ferris", '@'));
+        assert!(state.is_at_least("#rust", "bob", '%'));
+        assert!(!state.is_at_least("#rust", "bob", '@'));
+        assert!(!state.is_at_least("#rust", "nobody", '+'));
+
+        let channel = state.channel("#rust").unwrap();
+        assert_eq!(Some("secret"), channel.mode_param('k'));
+        assert!(!channel.has_mode('l'));
+        assert!(!channel.has_mode('b'));
+
+        // A refreshed list replaces the old one.
+        replay(
+            &mut state,
+            &[
+                ":irc.example.com 353 ferris = #rust :@ferris carol",
+                ":irc.example.com 366 ferris #rust :End of /NAMES list",
+            ],
+        );
+        assert_eq!(["@ferris", "carol"], nicks(&state, "#rust").as_slice());
+        assert_eq!(None, state.user("bob"));
+    }
+
+    #[test]
+    fn joins_and_leaves() {
+        let mut state = registered();
+        replay(
+            &mut state,
+            &[
+                ":ferris!f@rust.example JOIN #rust * :Ferris",
+                ":ferris!f@rust.example JOIN #go * :Ferris",
+                ":irc.example.com 353 ferris = #rust :ferris",
+                ":irc.example.com 366 ferris #rust :End of /NAMES list",
+                ":bob!b@bob.example JOIN #rust bob :Bob Builder",
+                ":bob!b@bob.example JOIN #go bob :Bob Builder",
+                ":carol!c@carol.example JOIN #rust",
+                ":dave!d@dave.example JOIN #rust",
+            ],
+        );
+
+        let bob = state.user("bob").unwrap();
+        assert_eq!(Some("bob"), bob.account.as_deref());
+        assert_eq!(Some("Bob Builder"), bob.realname.as_deref());
+        assert_eq!(
+            ["#rust", "#go"],
+            state
+                .channels_of("BOB")
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+
+        replay(
+            &mut state,
+            &[
+                ":bob!b@bob.example PART #rust :bye",
+                ":ferris!f@rust.example KICK #rust carol :spam",
+                ":dave!d@dave.example QUIT :Quit: leaving",
+            ],
+        );
+        assert_eq!(["ferris"], nicks(&state, "#rust").as_slice());
+        // Still shares #go.
+        assert!(state.user("bob").is_some());
+        assert_eq!(None, state.user("carol"));
+        assert_eq!(None, state.user("dave"));
+
+        // Not our channel.
+        assert!(!state
+            .handle(&crate::parse(":eve!e@e JOIN #secret").unwrap())
+            .unwrap());
+
+        replay(&mut state, &[":ferris!f@rust.example PART #go"]);
+        assert_eq!(None, state.channel("#go"));
+        assert_eq!(None, state.user("bob"));
+
+        replay(&mut state, &[":op!o@o KICK #rust ferris :out"]);
+        assert_eq!(0, state.channels().count());
+        assert_eq!(
+            ["ferris"],
+            state
+                .users()
+                .map(|u| u.nick.as_str())
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn user_updates() {
+        let mut state = registered();
+        replay(
+            &mut state,
+            &[
+                ":ferris!f@rust.example JOIN #rust",
+                ":irc.example.com 353 ferris = #rust :ferris @bob",
+                ":irc.example.com 366 ferris #rust :End of /NAMES list",
+                ":bob!b@old.example CHGHOST bobby new.example",
+                ":bob!bobby@new.example SETNAME :Robert",
+                ":bob!bobby@new.example ACCOUNT robert",
+                ":bob!bobby@new.example AWAY :lunch",
+                ":bob!bobby@new.example NICK Bob[m]",
+            ],
+        );
+
+        assert_eq!(["ferris", "@Bob[m]"], nicks(&state, "#rust").as_slice());
+        let bob = state.user("bob{m}").unwrap();
+        assert_eq!("Bob[m]!bobby@new.example", bob.hostmask());
+        assert_eq!(Some("Robert"), bob.realname.as_deref());
+        assert_eq!(Some("robert"), bob.account.as_deref());
+        assert!(bob.away);
+        assert_eq!(Some("lunch"), bob.away_message.as_deref());
+        assert_eq!(None, state.user("bob"));
+
+        replay(
+            &mut state,
+            &[
+                ":Bob[m]!bobby@new.example AWAY",
+                ":Bob[m]!bobby@new.example ACCOUNT *",
+                ":ferris!f@rust.example NICK crab",
+                ":irc.example.com 306 crab :You have been marked as being away",
+            ],
+        );
+        let bob = state.user("bob[m]").unwrap();
+        assert!(!bob.away && bob.away_message.is_none());
+        assert_eq!(None, bob.account);
+        assert_eq!(Some("crab"), state.nick());
+        assert!(state.user("crab").unwrap().away);
+        assert_eq!(
+            Some("crab"),
+            state.member("#rust", "CRAB").map(|m| m.nick.as_str())
+        );
+
+        // Unknown users are ignored.
+        assert!(!state
+            .handle(&crate::parse(":eve!e@e ACCOUNT eve").unwrap())
+            .unwrap());
+        assert!(state
+            .handle(&crate::parse("ACCOUNT eve").unwrap())
+            .unwrap_err()
+            .is_source_component_not_found());
+    }
+
+    #[test]
+    fn who_replies() {
+        let mut state = registered();
+        replay(
+            &mut state,
+            &[
+                ":ferris!f@rust.example JOIN #rust",
+                ":irc.example.com 353 ferris = #rust :ferris @+bob",
+                ":irc.example.com 366 ferris #rust :End of /NAMES list",
+                ":irc.example.com 352 ferris #rust b bob.example irc.example.com bob G@ :0 Bob",
+                ":irc.example.com 352 ferris #rust f rust.example irc.example.com ferris H% :0 Ferris",
+                ":irc.example.com 352 ferris * e e.example irc.example.com eve H :0 Eve",
+                ":irc.example.com 315 ferris #rust :End of WHO list",
+            ],
+        );
+
+        let bob = state.user("bob").unwrap();
+        assert_eq!("bob!b@bob.example", bob.hostmask());
+        assert_eq!(Some("Bob"), bob.realname.as_deref());
+        assert!(bob.away);
+        // The highest prefix is unchanged, so +v is kept.
+        assert_eq!(["%ferris", "@+bob"], nicks(&state, "#rust").as_slice());
+        assert_eq!(None, state.user("eve"));
+
+        replay(
+            &mut state,
+            &[
+                ":irc.example.com 352 ferris #rust b bob.example irc.example.com bob H% :0 Bob",
+                ":irc.example.com 352 ferris #rust f rust.example irc.example.com ferris H@+ :0 Ferris",
+            ],
+        );
+        // A lower highest prefix drops @ but keeps +v; multi-prefix adds +v.
+        assert_eq!(["@+ferris", "%+bob"], nicks(&state, "#rust").as_slice());
+
+        replay(
+            &mut state,
+            &[
+                ":irc.example.com 352 ferris #rust b bob.example irc.example.com bob H :0 Bob",
+                ":irc.example.com 352 ferris #rust f rust.example irc.example.com ferris H% :0 Ferris",
+            ],
+        );
+        assert_eq!(["%+ferris", "bob"], nicks(&state, "#rust").as_slice());
+    }
+
+    #[test]
+    fn new_connection() {
+        let mut state = registered();
+        replay(&mut state, &[":ferris!f@rust.example JOIN #rust"]);
+        assert_eq!(CaseMapping::Rfc1459, state.casemapping());
+
+        replay(
+            &mut state,
+            &[
+                ":irc.example.com 001 ferris_ :Welcome",
+                ":irc.example.com 005 ferris_ CASEMAPPING=ascii PREFIX=(ov)@+ :are supported",
+            ],
+        );
+        assert_eq!(0, state.channels().count());
+        assert_eq!(Some("ferris_"), state.nick());
+        assert_eq!(CaseMapping::Ascii, state.casemapping());
+        assert_eq!("@+", state.prefixes());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::compat::{String, ToOwned, ToString, Vec};
+
+use crate::{de::FromMessage, Commands, DeError, Message};
+
+use super::{GlobalUserState, Rgb, RoomSettings, RoomState, UserState};
+
+/// What we know about a joined channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Channel {
+    /// The channel, including the leading `#`.
+    pub name: String,
+    pub room_id: Option<String>,
+    pub settings: RoomSettings,
+    /// Our own status, from the last `USERSTATE`.
+    pub moderator: bool,
+    pub vip: bool,
+    pub broadcaster: bool,
+    pub subscriber: bool,
+}
+
+impl Channel {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            room_id: None,
+            settings: RoomSettings::default(),
+            moderator: false,
+            vip: false,
+            broadcaster: false,
+            subscriber: false,
+        }
+    }
+
+    /// Returns `true` if we may moderate the channel, as a moderator or its
+    /// broadcaster.
+    pub fn can_moderate(&self) -> bool {
+        self.moderator || self.broadcaster
+    }
+
+    /// Returns `true` if our messages are exempt from slow and other chat
+    /// modes.
+    pub fn is_exempt(&self) -> bool {
+        self.can_moderate() || self.vip
+    }
+}
+
+/// Our account, from `GLOBALUSERSTATE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalUser {
+    pub user_id: Option<String>,
+    pub display_name: Option<String>,
+    pub color: Option<Rgb>,
+    pub emote_sets: Vec<String>,
+}
+
+/// Tracks chat settings and our own status per channel.
+///
+/// Feed every incoming message to [`TwitchChannelState::handle()`]. A
+/// `ROOMSTATE` only changes the settings it contains. It does not do any I/O.
+///
+/// ```rust
+/// use ircv3_parse::twitch::{FollowersOnly, TwitchChannelState};
+///
+/// let mut state = TwitchChannelState::new();
+/// let lines = [
+///     "@emote-only=0;followers-only=-1;r9k=0;room-id=1;slow=0;subs-only=0 \
+///      :tmi.twitch.tv ROOMSTATE #rust",
+///     "@badges=moderator/1;mod=1 :tmi.twitch.tv USERSTATE #rust",
+///     "@room-id=1;slow=10 :tmi.twitch.tv ROOMSTATE #rust",
+/// ];
+/// for line in lines {
+///     assert!(state.handle(&ircv3_parse::parse(line)?)?);
+/// }
+///
+/// let channel = state.channel("#rust").unwrap();
+/// assert_eq!(10, channel.settings.slow);
+/// assert_eq!(FollowersOnly::Off, channel.settings.followers_only);
+/// assert!(channel.can_moderate());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TwitchChannelState {
+    global: Option<GlobalUser>,
+    channels: Vec<Channel>,
+}
+
+impl TwitchChannelState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes an incoming message.
+    ///
+    /// Returns `Ok(true)` for `ROOMSTATE`, `USERSTATE` and `GLOBALUSERSTATE`,
+    /// and `Ok(false)` for any other message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DeError`] if one of those messages lacks its channel.
+    pub fn handle(&mut self, msg: &Message<'_>) -> Result<bool, DeError> {
+        let command = msg.command();
+
+        if command == Commands::ROOMSTATE {
+            let room = RoomState::from_message(msg)?;
+            let channel = self.entry(room.channel);
+            if let Some(room_id) = room.room_id {
+                channel.room_id = Some(room_id.to_owned());
+            }
+            room.apply(&mut channel.settings);
+        } else if command == Commands::USERSTATE {
+            let user = UserState::from_message(msg)?;
+            let channel = self.entry(user.channel);
+            channel.moderator = user.moderator || user.has_badge("moderator");
+            channel.vip = user.is_vip();
+            channel.broadcaster = user.is_broadcaster();
+            channel.subscriber = user.subscriber;
+        } else if command == Commands::GLOBALUSERSTATE {
+            let user = GlobalUserState::from_message(msg)?;
+            self.global = Some(GlobalUser {
+                user_id: user.user_id.map(ToString::to_string),
+                display_name: user.display_name,
+                color: user.color,
+                emote_sets: user.emote_sets.iter().map(|s| s.to_string()).collect(),
+            });
+        } else {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    pub fn global(&self) -> Option<&GlobalUser> {
+        self.global.as_ref()
+    }
+
+    /// Returns the channel, compared case-insensitively.
+    pub fn channel(&self, name: &str) -> Option<&Channel> {
+        self.channels
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn channels(&self) -> impl Iterator<Item = &Channel> {
+        self.channels.iter()
+    }
+
+    /// Forgets a channel, e.g. after leaving it.
+    pub fn remove(&mut self, name: &str) -> Option<Channel> {
+        let index = self
+            .channels
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(name))?;
+        Some(self.channels.remove(index))
+    }
+
+    fn entry(&mut self, name: &str) -> &mut Channel {
+        let index = match self
+            .channels
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(name))
+        {
+            Some(index) => index,
+            None => {
+                self.channels.push(Channel::new(name));
+                self.channels.len() - 1
+            }
+        };
+        &mut self.channels[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitch::FollowersOnly;
+
+    fn handle(state: &mut TwitchChannelState, line: &str) -> bool {
+        state.handle(&crate::parse(line).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn partial_roomstate() {
+        let mut state = TwitchChannelState::new();
+        assert!(handle(
+            &mut state,
+            "@emote-only=1;followers-only=30;r9k=1;room-id=1;slow=5;subs-only=1 \
+             :tmi.twitch.tv ROOMSTATE #rust"
+        ));
+        assert!(handle(
+            &mut state,
+            "@emote-only=0;room-id=1 :tmi.twitch.tv ROOMSTATE #RUST"
+        ));
+
+        let channel = state.channel("#rust").unwrap();
+        assert_eq!(Some("1"), channel.room_id.as_deref());
+        assert_eq!(
+            RoomSettings {
+                emote_only: false,
+                followers_only: FollowersOnly::Minutes(30),
+                r9k: true,
+                slow: 5,
+                subs_only: true,
+            },
+            channel.settings
+        );
+        assert_eq!(1, state.channels().count());
+    }
+
+    #[test]
+    fn own_status() {
+        let mut state = TwitchChannelState::new();
+        assert!(handle(
+            &mut state,
+            "@badges=;color=;display-name=Bot;emote-sets=0,1;user-id=42 \
+             :tmi.twitch.tv GLOBALUSERSTATE"
+        ));
+        assert!(handle(
+            &mut state,
+            "@badges=vip/1;mod=0 :tmi.twitch.tv USERSTATE #rust"
+        ));
+        assert!(handle(
+            &mut state,
+            "@badges=moderator/1;mod=1 :tmi.twitch.tv USERSTATE #go"
+        ));
+
+        let global = state.global().unwrap();
+        assert_eq!(Some("42"), global.user_id.as_deref());
+        assert_eq!(["0", "1"], global.emote_sets.as_slice());
+
+        let rust = state.channel("#rust").unwrap();
+        assert!(rust.vip && !rust.moderator);
+        assert!(rust.is_exempt() && !rust.can_moderate());
+        assert!(state.channel("#go").unwrap().can_moderate());
+
+        // Losing the badge is reported by the next USERSTATE.
+        assert!(handle(
+            &mut state,
+            "@badges=;mod=0 :tmi.twitch.tv USERSTATE #rust"
+        ));
+        assert!(!state.channel("#rust").unwrap().is_exempt());
+
+        assert!(!handle(&mut state, ":a!a@a PRIVMSG #rust :hi"));
+        assert_eq!("#go", state.remove("#GO").unwrap().name);
+        assert_eq!(None, state.channel("#go"));
+        assert_eq!(None, state.remove("#go"));
+    }
+}
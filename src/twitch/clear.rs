@@ -0,0 +1,132 @@
+use crate::{
+    de::FromMessage,
+    messages::{expect_command, required},
+    Commands, DeError, Message,
+};
+
+use super::TwitchTags;
+
+/// Removal of a user's messages after a ban or timeout, or of all messages
+/// in the channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClearChat<'a> {
+    /// The channel, including the leading `#`.
+    pub channel: &'a str,
+    /// Login of the banned or timed out user, `None` if the chat was cleared.
+    pub target: Option<&'a str>,
+    pub target_user_id: Option<&'a str>,
+    /// `ban-duration` in seconds, `None` for a permanent ban.
+    pub ban_duration: Option<u32>,
+    pub room_id: Option<&'a str>,
+    /// `tmi-sent-ts`, milliseconds since the Unix epoch.
+    pub sent_ts: Option<u64>,
+}
+
+impl ClearChat<'_> {
+    pub fn is_ban(&self) -> bool {
+        self.target.is_some() && self.ban_duration.is_none()
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        self.target.is_some() && self.ban_duration.is_some()
+    }
+
+    /// Returns `true` if all messages of the channel were removed.
+    pub fn is_clear(&self) -> bool {
+        self.target.is_none()
+    }
+}
+
+impl<'a> FromMessage<'a> for ClearChat<'a> {
+    const COMMAND: Option<&'static str> = Some("CLEARCHAT");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::CLEARCHAT)?;
+        let params = msg.params();
+        let tags = msg.tags();
+
+        Ok(Self {
+            channel: required(&params, 0)?,
+            target: params.get(1).filter(|login| !login.is_empty()),
+            target_user_id: tags.value("target-user-id"),
+            ban_duration: tags.number("ban-duration"),
+            room_id: tags.value("room-id"),
+            sent_ts: tags.number("tmi-sent-ts"),
+        })
+    }
+}
+
+/// Removal of a single message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClearMsg<'a> {
+    /// The channel, including the leading `#`.
+    pub channel: &'a str,
+    /// `target-msg-id`, the `id` of the removed message.
+    pub target_msg_id: &'a str,
+    /// Login of the message's sender.
+    pub login: Option<&'a str>,
+    /// The removed text.
+    pub text: Option<&'a str>,
+    /// `tmi-sent-ts`, milliseconds since the Unix epoch.
+    pub sent_ts: Option<u64>,
+}
+
+impl<'a> FromMessage<'a> for ClearMsg<'a> {
+    const COMMAND: Option<&'static str> = Some("CLEARMSG");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::CLEARMSG)?;
+        let params = msg.params();
+        let tags = msg.tags();
+
+        Ok(Self {
+            channel: required(&params, 0)?,
+            target_msg_id: tags
+                .value("target-msg-id")
+                .ok_or_else(|| DeError::not_found_tag("target-msg-id"))?,
+            login: tags.value("login"),
+            text: params.get(1),
+            sent_ts: tags.number("tmi-sent-ts"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clearchat() {
+        let line = "@ban-duration=600;room-id=1111;target-user-id=2222;tmi-sent-ts=1700000000000 \
+                    :tmi.twitch.tv CLEARCHAT #rust :spammer";
+        let clear = ClearChat::from_str(line).unwrap();
+        assert_eq!("#rust", clear.channel);
+        assert_eq!(Some("spammer"), clear.target);
+        assert_eq!(Some("2222"), clear.target_user_id);
+        assert_eq!(Some(600), clear.ban_duration);
+        assert!(clear.is_timeout());
+        assert!(!clear.is_ban());
+
+        let line = "@room-id=1111;target-user-id=2222 :tmi.twitch.tv CLEARCHAT #rust :spammer";
+        assert!(ClearChat::from_str(line).unwrap().is_ban());
+
+        let clear = ClearChat::from_str("@room-id=1111 :tmi.twitch.tv CLEARCHAT #rust").unwrap();
+        assert!(clear.is_clear());
+        assert!(!clear.is_ban() && !clear.is_timeout());
+    }
+
+    #[test]
+    fn clearmsg() {
+        let line = "@login=spammer;room-id=;target-msg-id=m-1;tmi-sent-ts=1700000000000 \
+                    :tmi.twitch.tv CLEARMSG #rust :buy followers";
+        let clear = ClearMsg::from_str(line).unwrap();
+        assert_eq!("m-1", clear.target_msg_id);
+        assert_eq!(Some("spammer"), clear.login);
+        assert_eq!(Some("buy followers"), clear.text);
+        assert_eq!(Some(1_700_000_000_000), clear.sent_ts);
+
+        assert!(ClearMsg::from_str(":tmi.twitch.tv CLEARMSG #rust :hi")
+            .unwrap_err()
+            .is_not_found_tag());
+    }
+}
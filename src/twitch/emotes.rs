@@ -0,0 +1,245 @@
+use core::ops::Range;
+
+use crate::compat::Vec;
+
+/// A part of a chat message, see [`segments()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Emote { id: &'a str, text: &'a str },
+}
+
+impl<'a> Segment<'a> {
+    /// Returns the text of the segment, the emote name for an emote.
+    pub fn as_str(&self) -> &'a str {
+        match self {
+            Self::Text(text) | Self::Emote { text, .. } => text,
+        }
+    }
+}
+
+/// A part of a message flagged by AutoMod, see [`flags()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Flag<'a> {
+    pub text: &'a str,
+    /// Categories with their level, e.g. `P.6` or `S.3/I.5`.
+    pub categories: &'a str,
+}
+
+/// An entry of the `emotes` tag: an emote id and where it appears.
+///
+/// Indices count Unicode code points and `end` is inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EmoteRange<'a> {
+    pub id: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Parses the `emotes` tag, e.g. `25:0-4,12-16/1902:6-10`.
+///
+/// Malformed entries are skipped.
+pub fn emote_ranges(value: &str) -> impl Iterator<Item = EmoteRange<'_>> {
+    value
+        .split('/')
+        .filter_map(|emote| emote.split_once(':'))
+        .flat_map(|(id, ranges)| {
+            ranges.split(',').filter_map(move |range| {
+                let (start, end) = parse_range(range)?;
+                Some(EmoteRange { id, start, end })
+            })
+        })
+}
+
+/// Splits `text` into plain text and emotes according to the `emotes` tag.
+///
+/// Ranges that are out of bounds, reversed or overlap a previous range are
+/// ignored, so a wrong tag never splits a character.
+///
+/// ```rust
+/// use ircv3_parse::twitch::{segments, Segment};
+///
+/// let text = "Kappa 🦀 Keepo";
+/// let parts = segments(text, "25:0-4/1902:8-12");
+/// assert_eq!(
+///     [
+///         Segment::Emote { id: "25", text: "Kappa" },
+///         Segment::Text(" 🦀 "),
+///         Segment::Emote { id: "1902", text: "Keepo" },
+///     ],
+///     parts.as_slice()
+/// );
+/// ```
+pub fn segments<'a>(text: &'a str, emotes: &'a str) -> Vec<Segment<'a>> {
+    let ranges = emote_ranges(emotes).map(|e| (e.start, e.end, e.id));
+
+    let mut segments = Vec::new();
+    let mut last = 0;
+    for (bytes, id) in byte_ranges(text, ranges) {
+        if last < bytes.start {
+            segments.push(Segment::Text(&text[last..bytes.start]));
+        }
+        last = bytes.end;
+        segments.push(Segment::Emote {
+            id,
+            text: &text[bytes],
+        });
+    }
+    if last < text.len() {
+        segments.push(Segment::Text(&text[last..]));
+    }
+
+    segments
+}
+
+/// Returns the parts of `text` flagged by AutoMod according to the `flags`
+/// tag, e.g. `0-6:S.7,10-15:P.6`.
+///
+/// Invalid ranges are ignored as in [`segments()`].
+pub fn flags<'a>(text: &'a str, flags: &'a str) -> Vec<Flag<'a>> {
+    let ranges = flags.split(',').filter_map(|flag| {
+        let (range, categories) = flag.split_once(':').unwrap_or((flag, ""));
+        let (start, end) = parse_range(range)?;
+        Some((start, end, categories))
+    });
+
+    byte_ranges(text, ranges)
+        .into_iter()
+        .map(|(bytes, categories)| Flag {
+            text: &text[bytes],
+            categories,
+        })
+        .collect()
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let (start, end) = range.split_once('-')?;
+    Some((start.parse().ok()?, end.parse().ok()?))
+}
+
+/// Converts inclusive code point ranges into sorted, non-overlapping byte
+/// ranges of `text`.
+fn byte_ranges<T>(
+    text: &str,
+    ranges: impl Iterator<Item = (usize, usize, T)>,
+) -> Vec<(Range<usize>, T)> {
+    let mut ranges: Vec<_> = ranges.collect();
+    ranges.sort_by_key(|(start, end, _)| (*start, *end));
+
+    let mut offsets: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    let chars = offsets.len();
+    offsets.push(text.len());
+
+    let mut result = Vec::with_capacity(ranges.len());
+    let mut next = 0;
+    for (start, end, value) in ranges {
+        if start < next || start > end || end >= chars {
+            continue;
+        }
+        next = end + 1;
+        result.push((offsets[start]..offsets[end + 1], value));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        let ranges: Vec<_> = emote_ranges("25:0-4,12-16/1902:6-10/bad/x:1/y:a-b").collect();
+        assert_eq!(
+            [
+                EmoteRange {
+                    id: "25",
+                    start: 0,
+                    end: 4
+                },
+                EmoteRange {
+                    id: "25",
+                    start: 12,
+                    end: 16
+                },
+                EmoteRange {
+                    id: "1902",
+                    start: 6,
+                    end: 10
+                },
+            ],
+            ranges.as_slice()
+        );
+        assert_eq!(0, emote_ranges("").count());
+    }
+
+    #[test]
+    fn code_points() {
+        // Each emoji is one code point but four bytes, the family is five code points.
+        let text = "🦀🦀 Kappa 👨‍👩‍👧 Kappa";
+        let parts = segments(text, "25:3-7,15-19");
+        assert_eq!(
+            [
+                Segment::Text("🦀🦀 "),
+                Segment::Emote {
+                    id: "25",
+                    text: "Kappa"
+                },
+                Segment::Text(" 👨‍👩‍👧 "),
+                Segment::Emote {
+                    id: "25",
+                    text: "Kappa"
+                },
+            ],
+            parts.as_slice()
+        );
+        assert_eq!(text, parts.iter().map(Segment::as_str).collect::<String>());
+    }
+
+    #[test]
+    fn robust() {
+        let text = "Kappa hi";
+        assert_eq!([Segment::Text(text)], segments(text, "").as_slice());
+        assert_eq!([Segment::Text(text)], segments(text, "25:6-99").as_slice());
+        assert_eq!([Segment::Text(text)], segments(text, "25:4-2").as_slice());
+
+        // The overlapping range is dropped.
+        let parts = segments(text, "1:0-4/2:3-7/3:6-7");
+        assert_eq!(
+            [
+                Segment::Emote {
+                    id: "1",
+                    text: "Kappa"
+                },
+                Segment::Text(" "),
+                Segment::Emote {
+                    id: "3",
+                    text: "hi"
+                },
+            ],
+            parts.as_slice()
+        );
+
+        assert!(segments("", "25:0-4").is_empty());
+    }
+
+    #[test]
+    fn automod_flags() {
+        let text = "you are 🦀 stupid";
+        let flagged = flags(text, "10-15:P.6/I.5,0-2:,99-100:A.1");
+        assert_eq!(
+            [
+                Flag {
+                    text: "you",
+                    categories: ""
+                },
+                Flag {
+                    text: "stupid",
+                    categories: "P.6/I.5"
+                },
+            ],
+            flagged.as_slice()
+        );
+        assert!(flags(text, "").is_empty());
+    }
+}
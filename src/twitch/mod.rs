@@ -0,0 +1,223 @@
+//! Twitch chat messages and tags.
+//!
+//! Twitch sends its chat metadata as message tags, e.g. `badges`, `color` or
+//! `display-name`. The types here read those tags into typed values.
+//!
+//! - [`TwitchPrivmsg`] - a chat message with all of its tags
+//! - [`TwitchUserNotice`] and [`UserNoticeEvent`] - subs, gifts, raids and other events
+//! - [`RoomState`], [`UserState`] and [`GlobalUserState`] - chat settings and our own status
+//! - [`ClearChat`], [`ClearMsg`] and [`Whisper`] - moderation and private messages
+//! - [`TwitchChannelState`] - settings and our status per channel, kept up to date
+//! - [`Badge`] and [`badges()`] - `badges` and `badge-info` lists
+//! - [`Rgb`] - the `color` tag
+//! - [`segments()`] - message text split into text and emotes
+//!
+//! ```rust
+//! use ircv3_parse::{de::FromMessage, twitch::TwitchPrivmsg};
+//!
+//! let line = "@badge-info=subscriber/14;badges=subscriber/12,premium/1;color=#1E90FF;\
+//!             display-name=Ferris;user-id=1234 :ferris!ferris@ferris.tmi.twitch.tv \
+//!             PRIVMSG #rust :hello chat";
+//! let msg = TwitchPrivmsg::from_str(line)?;
+//!
+//! assert_eq!("ferris", msg.login);
+//! assert_eq!(Some("Ferris"), msg.display_name.as_deref());
+//! assert_eq!(Some(14), msg.subscriber_months());
+//! assert!(msg.has_badge("premium"));
+//! assert_eq!(Some((0x1e, 0x90, 0xff)), msg.color.map(|c| (c.r, c.g, c.b)));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod channel;
+mod clear;
+mod emotes;
+mod privmsg;
+mod room;
+mod usernotice;
+mod whisper;
+
+pub use channel::{Channel, GlobalUser, TwitchChannelState};
+pub use clear::{ClearChat, ClearMsg};
+pub use emotes::{emote_ranges, flags, segments, EmoteRange, Flag, Segment};
+pub use privmsg::{ReplyParent, TwitchPrivmsg};
+pub use room::{FollowersOnly, GlobalUserState, RoomSettings, RoomState, UserState};
+pub use usernotice::{SubPlan, Subscription, TwitchUserNotice, UserNoticeEvent};
+pub use whisper::Whisper;
+
+use crate::compat::{Display, FmtResult, Formatter, String, Vec};
+
+use crate::components::{TagValue, Tags};
+
+/// A chat badge such as `subscriber/12`.
+///
+/// In `badge-info` the version holds extra data instead, e.g. the exact number
+/// of months subscribed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Badge<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+}
+
+impl<'a> Badge<'a> {
+    /// Parses `name/version`; a missing version is empty.
+    pub fn parse(value: &'a str) -> Self {
+        match value.split_once('/') {
+            Some((name, version)) => Self { name, version },
+            None => Self {
+                name: value,
+                version: "",
+            },
+        }
+    }
+}
+
+impl Display for Badge<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}/{}", self.name, self.version)
+    }
+}
+
+/// Parses a comma-separated badge list such as `subscriber/12,premium/1`.
+pub fn badges(value: &str) -> impl Iterator<Item = Badge<'_>> {
+    value
+        .split(',')
+        .filter(|badge| !badge.is_empty())
+        .map(Badge::parse)
+}
+
+/// An RGB color, as sent in the `color` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    #[inline]
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Parses `#RRGGBB`, the leading `#` is optional.
+    pub fn parse(value: &str) -> Option<Self> {
+        let hex = value.strip_prefix('#').unwrap_or(value);
+        if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Self::new(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+impl Display for Rgb {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+}
+
+/// Reads Twitch tags, which are often sent empty instead of omitted.
+pub(crate) trait TwitchTags<'a> {
+    /// Returns the value of `key`, or `None` if missing or empty.
+    fn value(&self, key: &str) -> Option<&'a str>;
+
+    fn flag(&self, key: &str) -> bool {
+        flag(self.value(key))
+    }
+
+    fn number<T: core::str::FromStr>(&self, key: &str) -> Option<T> {
+        number(self.value(key))
+    }
+
+    fn unescaped(&self, key: &str) -> Option<String> {
+        unescaped(self.value(key))
+    }
+
+    fn badges(&self, key: &str) -> Vec<Badge<'a>> {
+        self.value(key)
+            .map(|v| badges(v).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl<'a> TwitchTags<'a> for Option<Tags<'a>> {
+    fn value(&self, key: &str) -> Option<&'a str> {
+        match self.as_ref()?.get(key)? {
+            TagValue::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// The value helpers behind `TwitchTags`, also usable with `#[irc(with = "..")]`.
+
+pub(crate) fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.filter(|v| !v.is_empty())
+}
+
+/// Twitch flags are `0` or `1`.
+pub(crate) fn flag(value: Option<&str>) -> bool {
+    value == Some("1")
+}
+
+pub(crate) fn number<T: core::str::FromStr>(value: Option<&str>) -> Option<T> {
+    non_empty(value).and_then(|v| v.parse().ok())
+}
+
+pub(crate) fn unescaped(value: Option<&str>) -> Option<String> {
+    non_empty(value).map(crate::unescape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_helpers() {
+        assert!(flag(Some("1")));
+        assert!(!flag(Some("true")));
+        assert!(!flag(None));
+        assert_eq!(Some(5u32), number(Some("5")));
+        assert_eq!(None::<u32>, number(Some("")));
+        assert_eq!(None, non_empty(Some("")));
+        assert_eq!(Some("a b".to_owned()), unescaped(Some("a\\sb")));
+        assert_eq!(None, unescaped(Some("")));
+    }
+
+    #[test]
+    fn badge_list() {
+        let list: Vec<_> = badges("subscriber/12,premium/1,,broadcaster").collect();
+        assert_eq!(
+            [
+                Badge::parse("subscriber/12"),
+                Badge {
+                    name: "premium",
+                    version: "1"
+                },
+                Badge {
+                    name: "broadcaster",
+                    version: ""
+                },
+            ],
+            list.as_slice()
+        );
+        assert_eq!("subscriber/12", list[0].to_string());
+        assert_eq!(0, badges("").count());
+
+        // badge-info values may contain a slash.
+        let badge = Badge::parse("predictions/blue/1");
+        assert_eq!(("predictions", "blue/1"), (badge.name, badge.version));
+    }
+
+    #[test]
+    fn rgb() {
+        assert_eq!(Some(Rgb::new(0x1e, 0x90, 0xff)), Rgb::parse("#1E90FF"));
+        assert_eq!(Some(Rgb::new(0, 0xff, 0x7f)), Rgb::parse("00ff7f"));
+        assert_eq!("#00FF7F", Rgb::new(0, 0xff, 0x7f).to_string());
+        assert_eq!(None, Rgb::parse(""));
+        assert_eq!(None, Rgb::parse("#FFF"));
+        assert_eq!(None, Rgb::parse("#GGGGGG"));
+        assert_eq!(None, Rgb::parse("#+1+1+1"));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::compat::{String, Vec};
+
+use crate::{
+    de::FromMessage,
+    messages::{expect_command, required},
+    Commands, DeError, Message,
+};
+
+use super::{flags, segments, Badge, Flag, Rgb, Segment, TwitchTags};
+
+/// A Twitch chat message.
+///
+/// Tags that are missing or empty are `None`, `false` or an empty list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwitchPrivmsg<'a> {
+    /// The channel, including the leading `#`.
+    pub channel: &'a str,
+    /// The sender's login name, from the source.
+    pub login: &'a str,
+    pub text: &'a str,
+    /// `id`, the id of this message.
+    pub id: Option<&'a str>,
+    pub user_id: Option<&'a str>,
+    pub room_id: Option<&'a str>,
+    /// `display-name` with `\s` and other escapes removed.
+    pub display_name: Option<String>,
+    pub color: Option<Rgb>,
+    pub badges: Vec<Badge<'a>>,
+    pub badge_info: Vec<Badge<'a>>,
+    /// The raw `emotes` tag, e.g. `25:0-4,12-16/1902:6-10`.
+    pub emotes: Option<&'a str>,
+    /// The raw `flags` tag set by AutoMod, e.g. `0-6:S.7`.
+    pub flags: Option<&'a str>,
+    /// Bits cheered with this message.
+    pub bits: Option<u32>,
+    pub first_msg: bool,
+    pub returning_chatter: bool,
+    pub moderator: bool,
+    pub subscriber: bool,
+    pub vip: bool,
+    /// `tmi-sent-ts`, milliseconds since the Unix epoch.
+    pub sent_ts: Option<u64>,
+    /// The message this one replies to.
+    pub reply: Option<ReplyParent<'a>>,
+}
+
+/// The `reply-parent-*` tags of a reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplyParent<'a> {
+    pub msg_id: &'a str,
+    pub user_id: Option<&'a str>,
+    pub user_login: Option<&'a str>,
+    pub display_name: Option<String>,
+    pub msg_body: Option<String>,
+    /// `reply-thread-parent-msg-id`, the first message of the thread.
+    pub thread_msg_id: Option<&'a str>,
+}
+
+impl<'a> TwitchPrivmsg<'a> {
+    /// Splits the text into plain text and emotes, see [`segments()`].
+    pub fn segments(&self) -> Vec<Segment<'a>> {
+        segments(self.text, self.emotes.unwrap_or_default())
+    }
+
+    /// Returns the parts of the text flagged by AutoMod, see [`flags()`].
+    pub fn flagged(&self) -> Vec<Flag<'a>> {
+        flags(self.text, self.flags.unwrap_or_default())
+    }
+
+    pub fn has_badge(&self, name: &str) -> bool {
+        self.badges.iter().any(|b| b.name == name)
+    }
+
+    /// Returns the exact number of months subscribed, from `badge-info`.
+    pub fn subscriber_months(&self) -> Option<u32> {
+        self.badge_info
+            .iter()
+            .find(|b| b.name == "subscriber" || b.name == "founder")
+            .and_then(|b| b.version.parse().ok())
+    }
+
+    pub fn is_broadcaster(&self) -> bool {
+        self.has_badge("broadcaster")
+    }
+}
+
+impl<'a> FromMessage<'a> for TwitchPrivmsg<'a> {
+    const COMMAND: Option<&'static str> = Some("PRIVMSG");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::PRIVMSG)?;
+        let params = msg.params();
+        let tags = msg.tags();
+
+        let reply = tags.value("reply-parent-msg-id").map(|msg_id| ReplyParent {
+            msg_id,
+            user_id: tags.value("reply-parent-user-id"),
+            user_login: tags.value("reply-parent-user-login"),
+            display_name: tags.unescaped("reply-parent-display-name"),
+            msg_body: tags.unescaped("reply-parent-msg-body"),
+            thread_msg_id: tags.value("reply-thread-parent-msg-id"),
+        });
+
+        Ok(Self {
+            channel: required(&params, 0)?,
+            login: msg
+                .source()
+                .ok_or_else(DeError::source_component_not_found)?
+                .name,
+            text: required(&params, 1)?,
+            id: tags.value("id"),
+            user_id: tags.value("user-id"),
+            room_id: tags.value("room-id"),
+            display_name: tags.unescaped("display-name"),
+            color: tags.value("color").and_then(Rgb::parse),
+            badges: tags.badges("badges"),
+            badge_info: tags.badges("badge-info"),
+            emotes: tags.value("emotes"),
+            flags: tags.value("flags"),
+            bits: tags.number("bits"),
+            first_msg: tags.flag("first-msg"),
+            returning_chatter: tags.flag("returning-chatter"),
+            moderator: tags.flag("mod"),
+            subscriber: tags.flag("subscriber"),
+            vip: tags.flag("vip") || tags.is_some_and(|t| t.get_flag("vip")),
+            sent_ts: tags.number("tmi-sent-ts"),
+            reply,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str = "@badge-info=subscriber/26;badges=moderator/1,subscriber/24,bits/1000;\
+        bits=100;color=#8A2BE2;display-name=Cool\\sName;emotes=;first-msg=0;id=abc-123;mod=1;\
+        returning-chatter=1;room-id=1111;subscriber=1;tmi-sent-ts=1700000000000;turbo=0;\
+        user-id=2222;user-type=mod \
+        :coolname!coolname@coolname.tmi.twitch.tv PRIVMSG #rust :cheer100 nice stream";
+
+    #[test]
+    fn privmsg() {
+        let msg = TwitchPrivmsg::from_str(LINE).unwrap();
+
+        assert_eq!("#rust", msg.channel);
+        assert_eq!("coolname", msg.login);
+        assert_eq!("cheer100 nice stream", msg.text);
+        assert_eq!(Some("abc-123"), msg.id);
+        assert_eq!(Some("2222"), msg.user_id);
+        assert_eq!(Some("1111"), msg.room_id);
+        assert_eq!(Some("Cool Name"), msg.display_name.as_deref());
+        assert_eq!(Some(Rgb::new(0x8a, 0x2b, 0xe2)), msg.color);
+        assert_eq!(3, msg.badges.len());
+        assert!(msg.has_badge("moderator"));
+        assert!(!msg.is_broadcaster());
+        assert_eq!(Some(26), msg.subscriber_months());
+        assert_eq!(None, msg.emotes);
+        assert_eq!(Some(100), msg.bits);
+        assert!(!msg.first_msg);
+        assert!(msg.returning_chatter);
+        assert!(msg.moderator);
+        assert!(msg.subscriber);
+        assert!(!msg.vip);
+        assert_eq!(Some(1_700_000_000_000), msg.sent_ts);
+        assert_eq!(None, msg.reply);
+        assert_eq!([Segment::Text(msg.text)], msg.segments().as_slice());
+    }
+
+    #[test]
+    fn emotes() {
+        let line =
+            "@emotes=25:0-4;flags=6-9:P.6 :a!a@a.tmi.twitch.tv PRIVMSG #rust :Kappa 🦀🦀🦀🦀 ok";
+        let msg = TwitchPrivmsg::from_str(line).unwrap();
+        assert_eq!(
+            [
+                Segment::Emote {
+                    id: "25",
+                    text: "Kappa"
+                },
+                Segment::Text(" 🦀🦀🦀🦀 ok"),
+            ],
+            msg.segments().as_slice()
+        );
+        assert_eq!("🦀🦀🦀🦀", msg.flagged()[0].text);
+    }
+
+    #[test]
+    fn reply() {
+        let line = "@reply-parent-display-name=Some\\sOne;reply-parent-msg-body=hi\\sthere\\:)\
+            ;reply-parent-msg-id=p-1;reply-parent-user-id=3333;reply-parent-user-login=someone;\
+            reply-thread-parent-msg-id=t-1;vip;color= :a!a@a.tmi.twitch.tv PRIVMSG #rust :@someone hi";
+        let msg = TwitchPrivmsg::from_str(line).unwrap();
+
+        let reply = msg.reply.unwrap();
+        assert_eq!("p-1", reply.msg_id);
+        assert_eq!(Some("someone"), reply.user_login);
+        assert_eq!(Some("Some One"), reply.display_name.as_deref());
+        assert_eq!(Some("hi there;)"), reply.msg_body.as_deref());
+        assert_eq!(Some("t-1"), reply.thread_msg_id);
+        assert!(msg.vip);
+        assert_eq!(None, msg.color);
+        assert!(msg.badges.is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        let msg = TwitchPrivmsg::from_str(":a!a@a PRIVMSG #rust :no tags").unwrap();
+        assert_eq!(None, msg.display_name);
+        assert_eq!(None, msg.subscriber_months());
+
+        assert!(TwitchPrivmsg::from_str(":a!a@a NOTICE #rust :hi")
+            .unwrap_err()
+            .is_command_mismatch());
+        assert!(TwitchPrivmsg::from_str("PRIVMSG #rust :hi")
+            .unwrap_err()
+            .is_source_component_not_found());
+    }
+}
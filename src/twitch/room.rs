@@ -0,0 +1,246 @@
+use crate::compat::{String, Vec};
+
+use crate::{
+    de::FromMessage,
+    messages::{expect_command, required},
+    Commands, DeError, Message,
+};
+
+use super::{Badge, Rgb, TwitchTags};
+
+/// A change of the chat settings of a channel.
+///
+/// Twitch sends every setting after joining, later only the ones that
+/// changed, so each one is `None` if its tag is missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomState<'a> {
+    /// The channel, including the leading `#`.
+    pub channel: &'a str,
+    pub room_id: Option<&'a str>,
+    pub emote_only: Option<bool>,
+    pub followers_only: Option<FollowersOnly>,
+    /// `r9k`, only unique messages are allowed.
+    pub r9k: Option<bool>,
+    /// Seconds between messages of a user, `0` if disabled.
+    pub slow: Option<u32>,
+    pub subs_only: Option<bool>,
+}
+
+/// The `followers-only` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FollowersOnly {
+    /// Sent as `-1`.
+    #[default]
+    Off,
+    /// Users must follow for at least this many minutes, `0` for any follower.
+    Minutes(u32),
+}
+
+impl FollowersOnly {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "-1" => Some(Self::Off),
+            minutes => minutes.parse().ok().map(Self::Minutes),
+        }
+    }
+}
+
+/// The complete chat settings of a channel, updated by [`RoomState::apply()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct RoomSettings {
+    pub emote_only: bool,
+    pub followers_only: FollowersOnly,
+    pub r9k: bool,
+    pub slow: u32,
+    pub subs_only: bool,
+}
+
+impl RoomState<'_> {
+    /// Applies the settings present in this message, leaving the others as
+    /// they are.
+    pub fn apply(&self, settings: &mut RoomSettings) {
+        if let Some(emote_only) = self.emote_only {
+            settings.emote_only = emote_only;
+        }
+        if let Some(followers_only) = self.followers_only {
+            settings.followers_only = followers_only;
+        }
+        if let Some(r9k) = self.r9k {
+            settings.r9k = r9k;
+        }
+        if let Some(slow) = self.slow {
+            settings.slow = slow;
+        }
+        if let Some(subs_only) = self.subs_only {
+            settings.subs_only = subs_only;
+        }
+    }
+}
+
+impl<'a> FromMessage<'a> for RoomState<'a> {
+    const COMMAND: Option<&'static str> = Some("ROOMSTATE");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::ROOMSTATE)?;
+        let tags = msg.tags();
+        let toggle = |key| tags.value(key).map(|v| v == "1");
+
+        Ok(Self {
+            channel: required(&msg.params(), 0)?,
+            room_id: tags.value("room-id"),
+            emote_only: toggle("emote-only"),
+            followers_only: tags.value("followers-only").and_then(FollowersOnly::parse),
+            r9k: toggle("r9k"),
+            slow: tags.number("slow"),
+            subs_only: toggle("subs-only"),
+        })
+    }
+}
+
+/// Our own state in a channel, sent after joining and after each message we
+/// send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserState<'a> {
+    /// The channel, including the leading `#`.
+    pub channel: &'a str,
+    pub display_name: Option<String>,
+    pub color: Option<Rgb>,
+    pub badges: Vec<Badge<'a>>,
+    pub badge_info: Vec<Badge<'a>>,
+    pub emote_sets: Vec<&'a str>,
+    /// `id` of the message we sent, if this is the reply to one.
+    pub id: Option<&'a str>,
+    pub moderator: bool,
+    pub subscriber: bool,
+}
+
+impl UserState<'_> {
+    pub fn has_badge(&self, name: &str) -> bool {
+        self.badges.iter().any(|b| b.name == name)
+    }
+
+    pub fn is_vip(&self) -> bool {
+        self.has_badge("vip")
+    }
+
+    pub fn is_broadcaster(&self) -> bool {
+        self.has_badge("broadcaster")
+    }
+}
+
+impl<'a> FromMessage<'a> for UserState<'a> {
+    const COMMAND: Option<&'static str> = Some("USERSTATE");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::USERSTATE)?;
+        let tags = msg.tags();
+
+        Ok(Self {
+            channel: required(&msg.params(), 0)?,
+            display_name: tags.unescaped("display-name"),
+            color: tags.value("color").and_then(Rgb::parse),
+            badges: tags.badges("badges"),
+            badge_info: tags.badges("badge-info"),
+            emote_sets: emote_sets(tags.value("emote-sets")),
+            id: tags.value("id"),
+            moderator: tags.flag("mod"),
+            subscriber: tags.flag("subscriber"),
+        })
+    }
+}
+
+/// Our own account, sent once after logging in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalUserState<'a> {
+    pub user_id: Option<&'a str>,
+    pub display_name: Option<String>,
+    pub color: Option<Rgb>,
+    pub badges: Vec<Badge<'a>>,
+    pub badge_info: Vec<Badge<'a>>,
+    pub emote_sets: Vec<&'a str>,
+}
+
+impl<'a> FromMessage<'a> for GlobalUserState<'a> {
+    const COMMAND: Option<&'static str> = Some("GLOBALUSERSTATE");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::GLOBALUSERSTATE)?;
+        let tags = msg.tags();
+
+        Ok(Self {
+            user_id: tags.value("user-id"),
+            display_name: tags.unescaped("display-name"),
+            color: tags.value("color").and_then(Rgb::parse),
+            badges: tags.badges("badges"),
+            badge_info: tags.badges("badge-info"),
+            emote_sets: emote_sets(tags.value("emote-sets")),
+        })
+    }
+}
+
+fn emote_sets(value: Option<&str>) -> Vec<&str> {
+    value
+        .map(|v| v.split(',').filter(|set| !set.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roomstate() {
+        let line = "@emote-only=0;followers-only=-1;r9k=0;room-id=1111;slow=0;subs-only=0 \
+                    :tmi.twitch.tv ROOMSTATE #rust";
+        let state = RoomState::from_str(line).unwrap();
+        assert_eq!("#rust", state.channel);
+        assert_eq!(Some("1111"), state.room_id);
+        assert_eq!(Some(FollowersOnly::Off), state.followers_only);
+
+        let mut settings = RoomSettings {
+            slow: 30,
+            ..RoomSettings::default()
+        };
+        state.apply(&mut settings);
+        assert_eq!(RoomSettings::default(), settings);
+
+        let line = "@followers-only=10;room-id=1111 :tmi.twitch.tv ROOMSTATE #rust";
+        let state = RoomState::from_str(line).unwrap();
+        assert_eq!(None, state.slow);
+        assert_eq!(None, state.emote_only);
+
+        settings.slow = 5;
+        state.apply(&mut settings);
+        assert_eq!(FollowersOnly::Minutes(10), settings.followers_only);
+        assert_eq!(5, settings.slow);
+
+        assert_eq!(Some(FollowersOnly::Minutes(0)), FollowersOnly::parse("0"));
+        assert_eq!(None, FollowersOnly::parse("-2"));
+    }
+
+    #[test]
+    fn userstate() {
+        let line = "@badge-info=;badges=vip/1;color=;display-name=Bot;emote-sets=0,33,,50;\
+                    id=m-1;mod=0;subscriber=0;user-type= :tmi.twitch.tv USERSTATE #rust";
+        let state = UserState::from_str(line).unwrap();
+        assert_eq!("#rust", state.channel);
+        assert_eq!(Some("Bot"), state.display_name.as_deref());
+        assert_eq!(None, state.color);
+        assert_eq!(["0", "33", "50"], state.emote_sets.as_slice());
+        assert_eq!(Some("m-1"), state.id);
+        assert!(state.is_vip());
+        assert!(!state.moderator);
+        assert!(!state.is_broadcaster());
+
+        let line = "@badges=;color=#0000FF;display-name=Bot;emote-sets=0;user-id=42 \
+                    :tmi.twitch.tv GLOBALUSERSTATE";
+        let global = GlobalUserState::from_str(line).unwrap();
+        assert_eq!(Some("42"), global.user_id);
+        assert_eq!(Some(Rgb::new(0, 0, 0xff)), global.color);
+        assert!(global.badges.is_empty());
+
+        assert!(UserState::from_str(":tmi.twitch.tv USERSTATE")
+            .unwrap_err()
+            .is_not_found_param());
+    }
+}
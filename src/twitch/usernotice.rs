@@ -0,0 +1,301 @@
+use crate::compat::{String, Vec};
+
+use crate::{
+    de::FromMessage,
+    messages::{expect_command, required},
+    Commands, DeError, FromMessage, Message,
+};
+
+use super::{flag, non_empty, number, unescaped, Badge, Rgb, TwitchTags};
+
+/// A Twitch event announced in chat, such as a subscription or a raid.
+///
+/// Tags that are missing or empty are `None`, `false` or an empty list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwitchUserNotice<'a> {
+    /// The channel, including the leading `#`.
+    pub channel: &'a str,
+    /// `msg-id`, the raw event type, also kept for [`UserNoticeEvent::Unknown`].
+    pub msg_id: &'a str,
+    pub event: UserNoticeEvent<'a>,
+    /// The message the user added, e.g. to a resub.
+    pub text: Option<&'a str>,
+    /// `system-msg` with `\s` and other escapes removed.
+    pub system_msg: Option<String>,
+    /// `login`, the user who caused the event.
+    pub login: Option<&'a str>,
+    pub display_name: Option<String>,
+    pub user_id: Option<&'a str>,
+    pub room_id: Option<&'a str>,
+    pub id: Option<&'a str>,
+    pub color: Option<Rgb>,
+    pub badges: Vec<Badge<'a>>,
+    pub badge_info: Vec<Badge<'a>>,
+    /// `tmi-sent-ts`, milliseconds since the Unix epoch.
+    pub sent_ts: Option<u64>,
+}
+
+/// The event of a [`TwitchUserNotice`], selected by the `msg-id` tag.
+///
+/// ```rust
+/// use ircv3_parse::{de::FromMessage, twitch::{SubPlan, UserNoticeEvent}};
+///
+/// let line = "@msg-id=raid;msg-param-login=ferris;msg-param-viewerCount=42 \
+///             :tmi.twitch.tv USERNOTICE #rust";
+/// let UserNoticeEvent::Raid { login, viewer_count, .. } = UserNoticeEvent::from_str(line)? else {
+///     panic!("expected a raid");
+/// };
+/// assert_eq!((Some("ferris"), Some(42)), (login, viewer_count));
+/// # Ok::<(), ircv3_parse::DeError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, FromMessage)]
+#[irc(tag = "msg-id", default = "Unknown")]
+pub enum UserNoticeEvent<'a> {
+    Sub(Subscription),
+    Resub(Subscription),
+    #[irc(value = "subgift", value = "anonsubgift")]
+    SubGift {
+        #[irc(tag = "msg-param-recipient-id", with = "non_empty")]
+        recipient_id: Option<&'a str>,
+        #[irc(tag = "msg-param-recipient-user-name", with = "non_empty")]
+        recipient_login: Option<&'a str>,
+        #[irc(tag = "msg-param-recipient-display-name", with = "unescaped")]
+        recipient_display_name: Option<String>,
+        /// Months the recipient has been subscribed.
+        #[irc(tag = "msg-param-months", with = "number")]
+        months: Option<u32>,
+        /// Months gifted at once.
+        #[irc(tag = "msg-param-gift-months", with = "number")]
+        gift_months: Option<u32>,
+        sub_plan: Option<SubPlan>,
+    },
+    #[irc(value = "submysterygift", value = "anonsubmysterygift")]
+    SubMysteryGift {
+        /// Number of subscriptions gifted at once.
+        #[irc(tag = "msg-param-mass-gift-count", with = "number")]
+        count: Option<u32>,
+        /// Total gifts by the sender in this channel.
+        #[irc(tag = "msg-param-sender-count", with = "number")]
+        sender_count: Option<u32>,
+        sub_plan: Option<SubPlan>,
+    },
+    Raid {
+        #[irc(tag = "msg-param-login", with = "non_empty")]
+        login: Option<&'a str>,
+        #[irc(tag = "msg-param-displayName", with = "unescaped")]
+        display_name: Option<String>,
+        #[irc(tag = "msg-param-viewerCount", with = "number")]
+        viewer_count: Option<u32>,
+    },
+    Ritual {
+        /// e.g. `new_chatter`.
+        #[irc(tag = "msg-param-ritual-name", with = "non_empty")]
+        name: Option<&'a str>,
+    },
+    Announcement {
+        /// e.g. `PRIMARY` or `BLUE`.
+        #[irc(tag = "msg-param-color", with = "non_empty")]
+        color: Option<&'a str>,
+    },
+    /// Any other `msg-id`, see [`TwitchUserNotice::msg_id`].
+    Unknown,
+}
+
+/// The `msg-param-*` tags of a `sub` or `resub`.
+#[derive(Debug, Clone, PartialEq, Eq, FromMessage)]
+pub struct Subscription {
+    #[irc(tag = "msg-param-cumulative-months", with = "number")]
+    pub cumulative_months: Option<u32>,
+    /// Only set if the user shares it.
+    #[irc(tag = "msg-param-streak-months", with = "number")]
+    pub streak_months: Option<u32>,
+    #[irc(tag = "msg-param-should-share-streak", with = "flag")]
+    pub should_share_streak: bool,
+    pub sub_plan: Option<SubPlan>,
+    #[irc(tag = "msg-param-sub-plan-name", with = "unescaped")]
+    pub sub_plan_name: Option<String>,
+}
+
+/// The `msg-param-sub-plan` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromMessage)]
+#[irc(tag = "msg-param-sub-plan")]
+pub enum SubPlan {
+    #[irc(value = "Prime")]
+    Prime,
+    #[irc(value = "1000")]
+    Tier1,
+    #[irc(value = "2000")]
+    Tier2,
+    #[irc(value = "3000")]
+    Tier3,
+}
+
+impl<'a> FromMessage<'a> for TwitchUserNotice<'a> {
+    const COMMAND: Option<&'static str> = Some("USERNOTICE");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::USERNOTICE)?;
+        let params = msg.params();
+        let tags = msg.tags();
+
+        Ok(Self {
+            channel: required(&params, 0)?,
+            msg_id: tags
+                .value("msg-id")
+                .ok_or_else(|| DeError::not_found_tag("msg-id"))?,
+            event: UserNoticeEvent::from_message(msg)?,
+            text: params.get(1),
+            system_msg: tags.unescaped("system-msg"),
+            login: tags.value("login"),
+            display_name: tags.unescaped("display-name"),
+            user_id: tags.value("user-id"),
+            room_id: tags.value("room-id"),
+            id: tags.value("id"),
+            color: tags.value("color").and_then(Rgb::parse),
+            badges: tags.badges("badges"),
+            badge_info: tags.badges("badge-info"),
+            sent_ts: tags.number("tmi-sent-ts"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESUB: &str = "@badge-info=subscriber/13;badges=subscriber/12;color=#008000;\
+        display-name=Ferris;id=abc;login=ferris;msg-id=resub;msg-param-cumulative-months=13;\
+        msg-param-should-share-streak=1;msg-param-streak-months=5;msg-param-sub-plan=1000;\
+        msg-param-sub-plan-name=Channel\\sSubscription;room-id=1111;\
+        system-msg=Ferris\\ssubscribed\\sat\\sTier\\s1.;tmi-sent-ts=1700000000000;user-id=2222 \
+        :tmi.twitch.tv USERNOTICE #rust :still here";
+
+    #[test]
+    fn resub() {
+        let notice = TwitchUserNotice::from_str(RESUB).unwrap();
+
+        assert_eq!("#rust", notice.channel);
+        assert_eq!("resub", notice.msg_id);
+        assert_eq!(Some("still here"), notice.text);
+        assert_eq!(Some("ferris"), notice.login);
+        assert_eq!(Some("Ferris"), notice.display_name.as_deref());
+        assert_eq!(
+            Some("Ferris subscribed at Tier 1."),
+            notice.system_msg.as_deref()
+        );
+        assert_eq!(Some(Rgb::new(0, 0x80, 0)), notice.color);
+        assert_eq!(Some(1_700_000_000_000), notice.sent_ts);
+        assert_eq!(
+            UserNoticeEvent::Resub(Subscription {
+                cumulative_months: Some(13),
+                streak_months: Some(5),
+                should_share_streak: true,
+                sub_plan: Some(SubPlan::Tier1),
+                sub_plan_name: Some("Channel Subscription".to_string()),
+            }),
+            notice.event
+        );
+    }
+
+    #[test]
+    fn events() {
+        let line = |tags: &str| format!("@{tags} :tmi.twitch.tv USERNOTICE #rust");
+
+        assert_eq!(
+            UserNoticeEvent::Sub(Subscription {
+                cumulative_months: Some(1),
+                streak_months: None,
+                should_share_streak: false,
+                sub_plan: Some(SubPlan::Prime),
+                sub_plan_name: None,
+            }),
+            UserNoticeEvent::from_str(&line(
+                "msg-id=sub;msg-param-cumulative-months=1;msg-param-sub-plan=Prime"
+            ))
+            .unwrap()
+        );
+        assert_eq!(
+            UserNoticeEvent::SubGift {
+                recipient_id: Some("3333"),
+                recipient_login: Some("crab"),
+                recipient_display_name: Some("Crab".to_string()),
+                months: Some(2),
+                gift_months: Some(1),
+                sub_plan: Some(SubPlan::Tier2),
+            },
+            UserNoticeEvent::from_str(&line(
+                "msg-id=anonsubgift;msg-param-recipient-id=3333;\
+                 msg-param-recipient-user-name=crab;msg-param-recipient-display-name=Crab;\
+                 msg-param-months=2;msg-param-gift-months=1;msg-param-sub-plan=2000"
+            ))
+            .unwrap()
+        );
+        assert_eq!(
+            UserNoticeEvent::SubMysteryGift {
+                count: Some(5),
+                sender_count: Some(50),
+                sub_plan: Some(SubPlan::Tier3),
+            },
+            UserNoticeEvent::from_str(&line(
+                "msg-id=submysterygift;msg-param-mass-gift-count=5;\
+                 msg-param-sender-count=50;msg-param-sub-plan=3000"
+            ))
+            .unwrap()
+        );
+        assert_eq!(
+            UserNoticeEvent::Raid {
+                login: Some("crab"),
+                display_name: Some("Crab".to_string()),
+                viewer_count: Some(9),
+            },
+            UserNoticeEvent::from_str(&line("msg-id=raid;msg-param-login=crab;msg-param-displayName=Crab;msg-param-viewerCount=9")).unwrap()
+        );
+        assert_eq!(
+            UserNoticeEvent::Ritual {
+                name: Some("new_chatter")
+            },
+            UserNoticeEvent::from_str(&line("msg-id=ritual;msg-param-ritual-name=new_chatter"))
+                .unwrap()
+        );
+        assert_eq!(
+            UserNoticeEvent::Announcement {
+                color: Some("PRIMARY")
+            },
+            UserNoticeEvent::from_str(&line("msg-id=announcement;msg-param-color=PRIMARY"))
+                .unwrap()
+        );
+        assert_eq!(
+            UserNoticeEvent::Sub(Subscription {
+                cumulative_months: None,
+                streak_months: None,
+                should_share_streak: false,
+                sub_plan: None,
+                sub_plan_name: None,
+            }),
+            UserNoticeEvent::from_str(&line(
+                "msg-id=sub;msg-param-cumulative-months=;msg-param-sub-plan=Tier9"
+            ))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        let line = "@msg-id=viewermilestone;msg-param-value=5 :tmi.twitch.tv USERNOTICE #rust";
+        let notice = TwitchUserNotice::from_str(line).unwrap();
+        assert_eq!("viewermilestone", notice.msg_id);
+        assert_eq!(UserNoticeEvent::Unknown, notice.event);
+        assert_eq!(None, notice.text);
+
+        assert!(
+            TwitchUserNotice::from_str(":tmi.twitch.tv USERNOTICE #rust")
+                .unwrap_err()
+                .is_not_found_tag()
+        );
+        assert!(
+            TwitchUserNotice::from_str("@msg-id=sub :tmi.twitch.tv PRIVMSG #rust :hi")
+                .unwrap_err()
+                .is_command_mismatch()
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use crate::compat::{String, Vec};
+
+use crate::{
+    de::FromMessage,
+    messages::{expect_command, required},
+    Commands, DeError, Message,
+};
+
+use super::{Badge, Rgb, TwitchTags};
+
+/// A private message between two users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Whisper<'a> {
+    /// The sender's login name, from the source.
+    pub login: &'a str,
+    /// The recipient's login name.
+    pub to: &'a str,
+    pub text: &'a str,
+    pub message_id: Option<&'a str>,
+    pub thread_id: Option<&'a str>,
+    pub user_id: Option<&'a str>,
+    pub display_name: Option<String>,
+    pub color: Option<Rgb>,
+    pub badges: Vec<Badge<'a>>,
+}
+
+impl<'a> FromMessage<'a> for Whisper<'a> {
+    const COMMAND: Option<&'static str> = Some("WHISPER");
+
+    fn from_message(msg: &Message<'a>) -> Result<Self, DeError> {
+        expect_command(msg, Commands::WHISPER)?;
+        let params = msg.params();
+        let tags = msg.tags();
+
+        Ok(Self {
+            login: msg
+                .source()
+                .ok_or_else(DeError::source_component_not_found)?
+                .name,
+            to: required(&params, 0)?,
+            text: required(&params, 1)?,
+            message_id: tags.value("message-id"),
+            thread_id: tags.value("thread-id"),
+            user_id: tags.value("user-id"),
+            display_name: tags.unescaped("display-name"),
+            color: tags.value("color").and_then(Rgb::parse),
+            badges: tags.badges("badges"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whisper() {
+        let line = "@badges=;color=#FF0000;display-name=Ferris;message-id=3;thread-id=1_2;\
+                    user-id=1 :ferris!ferris@ferris.tmi.twitch.tv WHISPER bot :psst";
+        let whisper = Whisper::from_str(line).unwrap();
+        assert_eq!("ferris", whisper.login);
+        assert_eq!("bot", whisper.to);
+        assert_eq!("psst", whisper.text);
+        assert_eq!(Some("3"), whisper.message_id);
+        assert_eq!(Some("1_2"), whisper.thread_id);
+        assert_eq!(Some(Rgb::new(0xff, 0, 0)), whisper.color);
+
+        assert!(Whisper::from_str(":ferris!f@f WHISPER bot")
+            .unwrap_err()
+            .is_not_found_param());
+    }
+}
@@ -273,8 +273,12 @@ pub fn tag_value(input: &str) -> Result<(), TagError> {
 /// # Rules
 ///
 /// - Must not be empty
-/// - Must not contain: space, NUL, CR, LF, colon
-/// - Colon is forbidden because it indicates the start of trailing parameter
+/// - Must not contain: space, NUL, CR, LF
+/// - Must not start with a colon, which indicates the start of trailing parameter
+///
+/// Colons are allowed after the first character, as in the RFC grammar
+/// (`middle = nospcrlfcl *( ":" / nospcrlfcl )`), so `CHANLIMIT=#:120` or an
+/// IPv6 address can be sent as a middle parameter.
 #[inline]
 pub fn param(input: &str) -> Result<(), ParamError> {
     if input.is_empty() {
@@ -282,7 +286,7 @@ pub fn param(input: &str) -> Result<(), ParamError> {
     }
 
     for (i, &c) in input.as_bytes().iter().enumerate() {
-        if matches!(c, SPACE | NUL | CR | LF | COLON) {
+        if matches!(c, SPACE | NUL | CR | LF) || (i == 0 && c == COLON) {
             return Err(ParamError::InvalidMiddleChar {
                 char: c as char,
                 position: i,
@@ -514,12 +518,22 @@ mod tests {
     #[test]
     fn param_invalid() {
         assert!(param("space space").is_err());
-        assert!(param("colon:colon").is_err());
         assert!(param("NUL\nNUL").is_err());
         assert!(param("CR\rCR").is_err());
         assert!(param("LF\nLF").is_err());
     }
 
+    #[test]
+    fn param_colon() {
+        // Only a leading colon starts the trailing parameter.
+        assert!(param(":colon").is_err());
+        assert!(param("colon:colon").is_ok());
+        assert!(param("CHANLIMIT=#:120").is_ok());
+        assert!(param("2001:db8::1").is_ok());
+        assert!(params("param1 :colon").is_err());
+        assert!(params("param1 colon:colon").is_ok());
+    }
+
     #[test]
     fn params_multiple() {
         assert!(params("#channel target").is_ok());
@@ -529,7 +543,6 @@ mod tests {
 
     #[test]
     fn params_invalid() {
-        assert!(params("param1 colon:colon").is_err());
         assert!(params("param1 NUL\0NUL").is_err());
     }
 
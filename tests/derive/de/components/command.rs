@@ -86,6 +86,32 @@ fn validation_and_extraction() {
     assert!(ircv3_parse::from_str::<Cmd>(input).is_err());
 }
 
+#[test]
+fn numeric_name() {
+    #[derive(FromMessage, ToMessage)]
+    #[irc(command = "ERR_NICKNAMEINUSE")]
+    struct NickInUse<'a> {
+        #[irc(param = 1)]
+        nick: &'a str,
+        #[irc(trailing)]
+        message: &'a str,
+    }
+
+    let input = ":irc.example.com 433 * alice :Nickname is already in use";
+    let msg = ircv3_parse::from_str::<NickInUse>(input).unwrap();
+    assert_eq!("alice", msg.nick);
+    assert_eq!("Nickname is already in use", msg.message);
+
+    let output = ircv3_parse::to_message(&msg).unwrap();
+    assert!(output.starts_with(b"433 "));
+
+    let input = ":irc.example.com 432 * alice :Erroneous nickname";
+    assert_eq!(
+        ircv3_parse::DeError::command_mismatch("433", "432"),
+        ircv3_parse::from_str::<NickInUse>(input).err().unwrap()
+    );
+}
+
 #[test]
 fn unnamed() {
     #[derive(FromMessage, ToMessage)]
@@ -486,3 +486,31 @@ fn skip_none() {
     let output = ircv3_parse::to_message(&msg).unwrap();
     assert_eq!("", output)
 }
+
+#[test]
+fn server_time() {
+    use ircv3_parse::components::ServerTime;
+
+    #[derive(Debug, FromMessage, ToMessage)]
+    #[irc(command = "PRIVMSG")]
+    struct Timed {
+        #[irc(tag = "time")]
+        at: ServerTime,
+        #[irc(tag = "time")]
+        maybe: Option<ServerTime>,
+        #[irc(trailing)]
+        text: String,
+    }
+
+    let input = "@time=2024-01-01T12:00:00.5Z PRIVMSG #channel :hello";
+    let msg: Timed = ircv3_parse::from_str(input).unwrap();
+    assert_eq!(1_704_110_400_500, msg.at.unix_millis());
+    assert_eq!(Some(msg.at), msg.maybe);
+    assert_eq!("hello", msg.text);
+
+    let output = ircv3_parse::to_message(&msg).unwrap();
+    assert_eq!("@time=2024-01-01T12:00:00.500Z PRIVMSG :hello", output);
+
+    let err = ircv3_parse::from_str::<Timed>("@time=now PRIVMSG #channel :hello").unwrap_err();
+    assert!(err.is_not_found_tag());
+}
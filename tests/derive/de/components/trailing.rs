@@ -354,3 +354,35 @@ fn optional_with_default_present() {
     let output = ircv3_parse::to_message(&msg).unwrap();
     assert_eq!(" :hi", output);
 }
+
+#[test]
+fn ctcp() {
+    use ircv3_parse::ctcp::Ctcp;
+
+    #[derive(FromMessage, ToMessage)]
+    #[irc(command = "PRIVMSG")]
+    struct Action<'a> {
+        #[irc(param)]
+        target: &'a str,
+        #[irc(trailing)]
+        ctcp: Ctcp<'a>,
+    }
+
+    let input = ":nick!user@host PRIVMSG #rust :\x01ACTION waves\x01";
+    let msg: Action = ircv3_parse::from_str(input).unwrap();
+    assert_eq!("#rust", msg.target);
+    assert!(msg.ctcp.is_action());
+    assert_eq!(Some("waves"), msg.ctcp.params);
+
+    let output = ircv3_parse::to_message(&msg).unwrap();
+    assert_eq!("PRIVMSG #rust :\x01ACTION waves\x01", output);
+
+    #[derive(FromMessage)]
+    struct MaybeCtcp<'a> {
+        #[irc(trailing)]
+        ctcp: Option<Ctcp<'a>>,
+    }
+
+    let msg: MaybeCtcp = ircv3_parse::from_str("PRIVMSG #rust :hello").unwrap();
+    assert!(msg.ctcp.is_none());
+}
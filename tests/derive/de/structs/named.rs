@@ -108,6 +108,55 @@ fn command_check() {
     assert!(ircv3_parse::from_str::<CommandCheck>(input).is_err());
 }
 
+#[test]
+fn command_const() {
+    #[derive(FromMessage)]
+    #[irc(command = "PRIVMSG")]
+    struct Text<'a> {
+        #[irc(trailing)]
+        content: &'a str,
+    }
+
+    #[derive(FromMessage)]
+    #[irc(command = "RPL_WELCOME")]
+    struct Welcome {
+        #[irc(param = 0)]
+        nick: String,
+    }
+
+    #[derive(FromMessage)]
+    struct Any<'a> {
+        #[irc(command)]
+        command: &'a str,
+    }
+
+    assert_eq!(Some("PRIVMSG"), Text::COMMAND);
+    assert_eq!(Some("RPL_WELCOME"), Welcome::COMMAND);
+    assert_eq!(None, Any::COMMAND);
+
+    let mut texts = Vec::new();
+    let mut nicks = Vec::new();
+    let mut commands = 0;
+    let mut dispatcher = ircv3_parse::dispatch::Dispatcher::new();
+    dispatcher
+        .on::<Text>(|text| texts.push(text.content.to_owned()))
+        .on::<Welcome>(|welcome| nicks.push(welcome.nick))
+        .on::<Any>(|_| commands += 1);
+
+    for line in [
+        ":irc.example.com 001 ferris :Welcome",
+        ":nick!user@host PRIVMSG #channel :hello",
+        ":nick!user@host NOTICE #channel :hi",
+    ] {
+        dispatcher.dispatch(&ircv3_parse::parse(line).unwrap());
+    }
+    drop(dispatcher);
+
+    assert_eq!(["hello"], texts.as_slice());
+    assert_eq!(["ferris"], nicks.as_slice());
+    assert_eq!(3, commands);
+}
+
 #[test]
 fn command_check_with_extraction() {
     #[allow(unused)]
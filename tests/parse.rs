@@ -3,7 +3,7 @@ mod components;
 use components::{
     commands::invalid_command_strategy, escape::escaped_strategy, params::ParamsType, TestMessage,
 };
-use ircv3_parse::{unescape, validators};
+use ircv3_parse::{components::Tags, unescape, validators};
 use proptest::{prelude::any, prop_assert, prop_assert_eq, proptest, test_runner::Config};
 
 proptest! {
@@ -128,6 +128,33 @@ fn test_edge_cases() {
     assert_eq!(unescape("multiple\\s\\:escapes"), "multiple ;escapes");
 }
 
+#[test]
+fn well_known_tags() {
+    let input = "@time=2024-01-01T12:00:00.000Z;msgid=abc;account=ferris;label=1;batch=b1;+draft/reply=xyz;bot :ferris!f@h PRIVMSG #rust :hi";
+    let msg = ircv3_parse::parse(input).unwrap();
+    let tags = msg.tags().unwrap();
+
+    let time = tags.server_time().unwrap();
+    assert_eq!(
+        (2024, 1, 1, 12),
+        (time.year, time.month, time.day, time.hour)
+    );
+    assert_eq!(Some("abc"), tags.msgid());
+    assert_eq!(Some("ferris"), tags.account());
+    assert_eq!(Some("1"), tags.label());
+    assert_eq!(Some("b1"), tags.batch());
+    assert_eq!(Some("xyz"), tags.reply_to());
+    assert!(tags.bot());
+
+    let tags = Tags::new("time=soon;msgid=;+reply=r1;+draft/reply=r2;draft/bot");
+    assert_eq!(None, tags.server_time());
+    assert_eq!(None, tags.msgid());
+    assert_eq!(None, tags.account());
+    assert_eq!(Some("r1"), tags.reply_to());
+    assert!(tags.bot());
+    assert!(!Tags::new("botanist=1").bot());
+}
+
 proptest! {
     #![proptest_config(Config::with_cases(3000))]
     #[test]
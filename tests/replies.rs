@@ -0,0 +1,187 @@
+use ircv3_parse::{
+    components::Numeric,
+    de::FromMessage,
+    messages::replies::{
+        BanListEntry, ChannelSymbol, ErrorReply, ListReply, Member, NamReply, TopicReply,
+        TopicWhoTime, WhoReply, WhoisUser,
+    },
+};
+
+// Server transcript in the format sent by solanum (Libera.Chat).
+const SOLANUM: &[&str] = &[
+    ":tantalum.libera.chat 332 rustbot #rust-bots :Bots about Rust | https://www.rust-lang.org",
+    ":tantalum.libera.chat 333 rustbot #rust-bots ferris!~ferris@user/ferris 1693526400",
+    ":tantalum.libera.chat 353 rustbot = #rust-bots :rustbot @ChanServ +ferris corro",
+    ":tantalum.libera.chat 366 rustbot #rust-bots :End of /NAMES list.",
+    ":tantalum.libera.chat 352 rustbot #rust-bots ~ferris user/ferris tantalum.libera.chat ferris H+ :0 Ferris the Crab",
+    ":tantalum.libera.chat 352 rustbot #rust-bots ChanServ services.libera.chat services.libera.chat ChanServ H*@ :3 Channel Services",
+    ":tantalum.libera.chat 311 rustbot ferris ~ferris user/ferris * :Ferris the Crab",
+    ":tantalum.libera.chat 322 rustbot #rust 1500 :[+nt] The Rust Programming Language",
+    ":tantalum.libera.chat 367 rustbot #rust-bots *!*@spam.example ChanServ!ChanServ@services.libera.chat 1700000000",
+    ":tantalum.libera.chat 433 * rustbot :Nickname is already in use.",
+    ":tantalum.libera.chat 403 rustbot #nope :No such channel",
+];
+
+// Server transcript in the format sent by Ergo with multi-prefix and userhost-in-names.
+const ERGO: &[&str] = &[
+    ":ergo.test 353 alice @ #secret :~@alice!alice@127.0.0.1 +bob!b@example.com",
+    ":ergo.test 353 alice * #private :carol",
+    ":ergo.test 352 alice * bob example.com ergo.test bob G :0 Bob",
+    ":ergo.test 367 alice #ergo *!*@bad.example",
+    ":ergo.test 322 alice #ergo 3 :",
+    ":ergo.test 421 alice FOO :Unknown command",
+];
+
+#[test]
+fn solanum_transcript() {
+    let topic = TopicReply::from_str(SOLANUM[0]).unwrap();
+    assert_eq!("rustbot", topic.client);
+    assert_eq!("#rust-bots", topic.channel);
+    assert_eq!("Bots about Rust | https://www.rust-lang.org", topic.topic);
+
+    let whotime = TopicWhoTime::from_str(SOLANUM[1]).unwrap();
+    assert_eq!("ferris!~ferris@user/ferris", whotime.setter);
+    assert_eq!(1693526400, whotime.set_at);
+
+    let names = NamReply::from_str(SOLANUM[2]).unwrap();
+    assert_eq!(ChannelSymbol::Public, names.symbol);
+    let members: Vec<_> = names.members().map(|m| (m.prefixes, m.nick)).collect();
+    assert_eq!(
+        members,
+        [
+            ("", "rustbot"),
+            ("@", "ChanServ"),
+            ("+", "ferris"),
+            ("", "corro")
+        ]
+    );
+
+    assert!(NamReply::from_str(SOLANUM[3])
+        .unwrap_err()
+        .is_command_mismatch());
+
+    let who = WhoReply::from_str(SOLANUM[4]).unwrap();
+    assert_eq!(Some("#rust-bots"), who.channel);
+    assert_eq!("~ferris", who.username);
+    assert_eq!("user/ferris", who.host);
+    assert_eq!("tantalum.libera.chat", who.server);
+    assert_eq!("ferris", who.nick);
+    assert_eq!(0, who.hopcount);
+    assert_eq!("Ferris the Crab", who.realname);
+    assert!(!who.is_away());
+    assert!(!who.is_oper());
+    assert_eq!("+", who.prefixes());
+
+    let who = WhoReply::from_str(SOLANUM[5]).unwrap();
+    assert!(who.is_oper());
+    assert_eq!("@", who.prefixes());
+    assert_eq!(3, who.hopcount);
+
+    let whois = WhoisUser::from_str(SOLANUM[6]).unwrap();
+    assert_eq!(
+        ("ferris", "~ferris", "user/ferris", "Ferris the Crab"),
+        (whois.nick, whois.username, whois.host, whois.realname)
+    );
+
+    let list = ListReply::from_str(SOLANUM[7]).unwrap();
+    assert_eq!("#rust", list.channel);
+    assert_eq!(1500, list.visible);
+    assert_eq!("[+nt] The Rust Programming Language", list.topic);
+
+    let ban = BanListEntry::from_str(SOLANUM[8]).unwrap();
+    assert_eq!("*!*@spam.example", ban.mask);
+    assert_eq!(Some("ChanServ!ChanServ@services.libera.chat"), ban.setter);
+    assert_eq!(Some(1700000000), ban.set_at);
+
+    let err = ErrorReply::from_str(SOLANUM[9]).unwrap();
+    assert_eq!(Some(Numeric::ERR_NICKNAMEINUSE), err.numeric());
+    assert_eq!("*", err.client);
+    assert_eq!(err.args, ["rustbot"]);
+    assert_eq!(Some("Nickname is already in use."), err.description);
+
+    let err = ErrorReply::from_str(SOLANUM[10]).unwrap();
+    assert_eq!(Some(Numeric::ERR_NOSUCHCHANNEL), err.numeric());
+    assert_eq!(err.args, ["#nope"]);
+}
+
+#[test]
+fn ergo_transcript() {
+    let names = NamReply::from_str(ERGO[0]).unwrap();
+    assert_eq!(ChannelSymbol::Secret, names.symbol);
+    let members: Vec<_> = names.members().collect();
+    assert_eq!(
+        members,
+        [
+            Member {
+                prefixes: "~@",
+                nick: "alice",
+                user: Some("alice"),
+                host: Some("127.0.0.1"),
+            },
+            Member {
+                prefixes: "+",
+                nick: "bob",
+                user: Some("b"),
+                host: Some("example.com"),
+            },
+        ]
+    );
+    assert_eq!(Some('~'), members[0].highest_prefix());
+
+    let names = NamReply::from_str(ERGO[1]).unwrap();
+    assert_eq!(ChannelSymbol::Private, names.symbol);
+
+    let who = WhoReply::from_str(ERGO[2]).unwrap();
+    assert_eq!(None, who.channel);
+    assert!(who.is_away());
+    assert_eq!("", who.prefixes());
+
+    let ban = BanListEntry::from_str(ERGO[3]).unwrap();
+    assert_eq!((None, None), (ban.setter, ban.set_at));
+
+    let list = ListReply::from_str(ERGO[4]).unwrap();
+    assert_eq!((3, ""), (list.visible, list.topic));
+
+    let err = ErrorReply::from_str(ERGO[5]).unwrap();
+    assert_eq!(Some(Numeric::ERR_UNKNOWNCOMMAND), err.numeric());
+    assert_eq!(err.args, ["FOO"]);
+}
+
+#[test]
+fn custom_prefixes() {
+    let names = NamReply::from_str(":irc.test 353 me = #c :!admin .owner user").unwrap();
+    let prefixes: Vec<_> = names
+        .members_with_prefixes("!.@+")
+        .map(|m| m.prefixes)
+        .collect();
+    assert_eq!(prefixes, ["!", ".", ""]);
+}
+
+#[test]
+fn invalid_values() {
+    let err = TopicWhoTime::from_str(":irc.test 333 me #c nick soon").unwrap_err();
+    assert!(err.is_not_found_param());
+
+    let err = ListReply::from_str(":irc.test 322 me #c many :topic").unwrap_err();
+    assert!(err.is_not_found_param());
+
+    let err = NamReply::from_str(":irc.test 353 me ? #c :nick").unwrap_err();
+    assert!(err.is_not_found_param());
+
+    let err = ErrorReply::from_str(":irc.test 001 me :Welcome").unwrap_err();
+    assert!(err.is_command_mismatch());
+
+    // RPL_LOGGEDIN is not an error even though it is outside 400-599.
+    let err = ErrorReply::from_str(":irc.test 900 me n!u@h acc :Logged in").unwrap_err();
+    assert!(err.is_command_mismatch());
+}
+
+#[test]
+fn error_outside_400_599() {
+    let err = ErrorReply::from_str(":irc.test 904 me :SASL authentication failed").unwrap();
+    assert_eq!(Some(Numeric::ERR_SASLFAIL), err.numeric());
+    assert_eq!(Some("SASL authentication failed"), err.description);
+
+    let err = ErrorReply::from_str(":irc.test 499 me #c :Custom").unwrap();
+    assert_eq!(None, err.numeric());
+}